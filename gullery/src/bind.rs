@@ -0,0 +1,92 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared binding logic for OpenGL binding points.
+//!
+//! Every OpenGL object type that gets bound to a binding point follows the same pattern:
+//!
+//! - A `*Target` struct represents the binding point, and implements [`Bind`] to cache the handle
+//!   of the bound object. Redundant binds are skipped by checking against that cache.
+//! - The target's `bind` method returns a `Bound*` guard borrowing the object. Operations that
+//!   require the object to be bound are methods on that guard, so they can't be called on an
+//!   unbound object.
+//! - When an object is deleted, it calls [`Bind::unbind_handle`] on the targets it may be bound
+//!   to, so the cache never refers to a dead handle.
+//!
+//! New object types should follow the same convention.
+
+use crate::{
+    gl::{types::*, Gl},
+    Handle,
+};
+
+use std::cell::Cell;
+
+/// A cached OpenGL binding point.
+///
+/// A cached handle of `None` corresponds to object `0` being bound, which is the initial state of
+/// every binding point.
+pub trait Bind {
+    /// The `glGet` parameter used to query the handle bound to the binding point.
+    const BINDING: GLenum;
+
+    /// The cached handle of the currently bound object.
+    fn bound_handle(&self) -> &Cell<Option<Handle>>;
+
+    /// Performs the raw OpenGL binding call, without checking or updating the cache.
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl);
+
+    /// Binds `handle` to the binding point, if it isn't already bound.
+    #[inline]
+    unsafe fn bind_handle(&self, handle: Option<Handle>, gl: &Gl) {
+        if self.bound_handle().get() != handle {
+            self.bound_handle().set(handle);
+            self.gl_bind(handle, gl);
+        }
+    }
+
+    /// Binds object `0`, unconditionally.
+    #[inline]
+    unsafe fn reset_bind(&self, gl: &Gl) {
+        self.bound_handle().set(None);
+        self.gl_bind(None, gl);
+    }
+
+    /// Binds `handle` to the binding point, unconditionally.
+    #[inline]
+    unsafe fn force_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        self.bound_handle().set(handle);
+        self.gl_bind(handle, gl);
+    }
+
+    /// Queries OpenGL for the currently bound handle and updates the cache to match. Should be
+    /// called when code outside of Gullery may have changed the binding.
+    #[inline]
+    unsafe fn sync_bind(&self, gl: &Gl) -> Option<Handle> {
+        let mut bound = 0;
+        gl.GetIntegerv(Self::BINDING, &mut bound);
+        let handle = Handle::new(bound as GLuint);
+        self.bound_handle().set(handle);
+        handle
+    }
+
+    /// Resets the binding point if `handle` is bound to it. Should be called when the object
+    /// referred to by `handle` is deleted.
+    #[inline]
+    unsafe fn unbind_handle(&self, handle: Handle, gl: &Gl) {
+        if self.bound_handle().get() == Some(handle) {
+            self.reset_bind(gl);
+        }
+    }
+}
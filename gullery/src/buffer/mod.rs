@@ -15,18 +15,31 @@
 //! Generic GPU data buffer.
 
 mod raw;
+mod ring;
 
-pub use self::raw::BufferUsage;
+pub use self::raw::{AlreadyMapped, BufferAllocError, BufferUsage, PersistentFlags};
+pub use self::ring::RingBuffer;
 pub(crate) use self::raw::RawBindTarget;
-use self::raw::{targets, RawBuffer};
+use self::raw::{targets, MapState, RawBuffer};
 
-use crate::{gl::Gl, ContextState, GLObject, Handle};
+use crate::{
+    bind::Bind,
+    gl::{self, Gl},
+    object_registry::ObjectKind,
+    ContextState, GLObject, Handle,
+};
 
-use std::{ops::RangeBounds, rc::Rc};
+use std::{
+    any, mem,
+    ops::{Deref, DerefMut, Range, RangeBounds},
+    ptr::NonNull,
+    rc::Rc,
+    slice,
+};
 
 pub(crate) struct BufferBinds {
-    copy_read: targets::RawCopyRead,
-    copy_write: targets::RawCopyWrite,
+    pub(crate) copy_read: targets::RawCopyRead,
+    pub(crate) copy_write: targets::RawCopyWrite,
 }
 
 impl BufferBinds {
@@ -38,12 +51,8 @@ impl BufferBinds {
     }
 
     unsafe fn unbind<T: Copy>(&self, buf: &RawBuffer<T>, gl: &Gl) {
-        if self.copy_read.bound_buffer().get() == Some(buf.handle()) {
-            self.copy_read.reset_bind(gl);
-        }
-        if self.copy_write.bound_buffer().get() == Some(buf.handle()) {
-            self.copy_write.reset_bind(gl);
-        }
+        self.copy_read.unbind_handle(buf.handle(), gl);
+        self.copy_write.unbind_handle(buf.handle(), gl);
     }
 }
 
@@ -53,11 +62,66 @@ pub struct Buffer<T: 'static + Copy> {
     state: Rc<ContextState>,
 }
 
+/// A buffer with immutable storage that stays mapped into CPU memory for its entire lifetime.
+///
+/// Created with [`Buffer::new_persistent`]. Data can be read and written through the mapping
+/// while the GPU is using the buffer, without any binding or copying, which makes this the fastest
+/// way to stream per-frame data to the GPU. However, nothing stops the CPU from overwriting data
+/// the GPU is still reading, so writes need to be synchronized with fences. A common approach is
+/// splitting the buffer into several regions and rotating between them each frame.
+///
+/// The storage can't be resized, so this only dereferences to an immutable [`Buffer`].
+///
+/// [`Buffer`]: ./struct.Buffer.html
+/// [`Buffer::new_persistent`]: ./struct.Buffer.html#method.new_persistent
+pub struct PersistentBuffer<T: 'static + Copy> {
+    buffer: Buffer<T>,
+    ptr: NonNull<T>,
+    flags: PersistentFlags,
+}
+
+/// A buffer's storage, mapped into CPU memory for reading. Created with [`Buffer::map`].
+///
+/// The buffer gets unmapped when this is dropped.
+///
+/// [`Buffer::map`]: ./struct.Buffer.html#method.map
+pub struct BufferMap<'a, T: 'static + Copy> {
+    buffer: &'a Buffer<T>,
+    ptr: NonNull<T>,
+}
+
+/// A buffer's storage, mapped into CPU memory for reading and writing. Created with
+/// [`Buffer::map_mut`].
+///
+/// The buffer gets unmapped when this is dropped.
+///
+/// [`Buffer::map_mut`]: ./struct.Buffer.html#method.map_mut
+pub struct BufferMapMut<'a, T: 'static + Copy> {
+    buffer: &'a mut Buffer<T>,
+    ptr: NonNull<T>,
+}
+
+/// A view into a sub-range of a [`Buffer`], created with [`Buffer::slice`].
+///
+/// This allows a sub-range of a buffer to be used without copying it into a new allocation, which
+/// is useful when sub-allocating many meshes out of one large buffer.
+///
+/// [`Buffer`]: ./struct.Buffer.html
+/// [`Buffer::slice`]: ./struct.Buffer.html#method.slice
+#[derive(Clone, Copy)]
+pub struct BufferSlice<'a, T: 'static + Copy> {
+    buffer: &'a Buffer<T>,
+    offset: usize,
+    len: usize,
+}
+
 impl<T: 'static + Copy> Buffer<T> {
     /// Create a new buffer and upload the provided data to the buffer.
     ///
     /// ## Panics
-    /// Panics is GPU is out of memory.
+    /// Panics is GPU is out of memory. Use [`try_with_data`] to handle allocation failures.
+    ///
+    /// [`try_with_data`]: ./struct.Buffer.html#method.try_with_data
     #[inline]
     pub fn with_data(usage: BufferUsage, data: &[T], state: Rc<ContextState>) -> Buffer<T> {
         let raw = {
@@ -75,7 +139,44 @@ impl<T: 'static + Copy> Buffer<T> {
             raw
         };
 
-        Buffer { raw, state }
+        let buffer = Buffer { raw, state };
+        buffer.track();
+        buffer
+    }
+
+    /// Create a new buffer and upload the provided data to the buffer, returning an error if the
+    /// allocation fails.
+    ///
+    /// Running out of GPU memory gets reported as [`BufferAllocError::OutOfMemory`], separately
+    /// from any other OpenGL error. Unlike [`with_data`], this also checks that the driver
+    /// allocated exactly as much storage as was requested, instead of risking a silently
+    /// truncated buffer.
+    ///
+    /// [`BufferAllocError::OutOfMemory`]: ./enum.BufferAllocError.html#variant.OutOfMemory
+    ///
+    /// [`with_data`]: ./struct.Buffer.html#method.with_data
+    pub fn try_with_data(
+        usage: BufferUsage,
+        data: &[T],
+        state: Rc<ContextState>,
+    ) -> Result<Buffer<T>, BufferAllocError> {
+        let mut buffer = Buffer {
+            raw: RawBuffer::new(&state.gl),
+            state,
+        };
+        {
+            let ContextState {
+                ref buffer_binds,
+                ref gl,
+                ..
+            } = *buffer.state;
+
+            let mut bind = unsafe { buffer_binds.copy_write.bind_mut(&mut buffer.raw, gl) };
+            bind.try_alloc_upload(data, usage)?;
+        }
+
+        buffer.track();
+        Ok(buffer)
     }
 
     /// Creates a new buffer that can hold the specified number of elements.
@@ -99,7 +200,134 @@ impl<T: 'static + Copy> Buffer<T> {
             raw
         };
 
-        Buffer { raw, state }
+        let buffer = Buffer { raw, state };
+        buffer.track();
+        buffer
+    }
+
+    /// Creates a buffer that holds `len` elements, and fills it with the elements yielded by
+    /// `iter`.
+    ///
+    /// The elements get written straight into mapped GPU memory, so generated data doesn't have
+    /// to get collected into a temporary `Vec` first. The buffer gets rebound before it's
+    /// unmapped, so `iter` may create and bind other buffers while it runs.
+    ///
+    /// ## Panics
+    /// Panics if the GPU is out of memory. In debug builds, also panics if `iter` doesn't yield
+    /// exactly `len` elements. In release builds, elements past `len` are ignored and the contents
+    /// of any elements it doesn't yield are unspecified.
+    pub fn from_iter<I>(
+        usage: BufferUsage,
+        len: usize,
+        iter: I,
+        state: Rc<ContextState>,
+    ) -> Buffer<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        assert!(
+            len.checked_mul(mem::size_of::<T>())
+                .is_some_and(|byte_size| byte_size <= isize::MAX as usize),
+            "buffer of length {} is too large",
+            len
+        );
+        let mut buffer = Buffer::with_size(usage, len, state);
+
+        let mut iter = iter.into_iter();
+        let mut num_written = 0;
+        {
+            let ptr = {
+                let ContextState {
+                    ref buffer_binds,
+                    ref gl,
+                    ..
+                } = *buffer.state;
+
+                let bind = unsafe { buffer_binds.copy_write.bind(&buffer.raw, gl) };
+                bind.map(gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT)
+                    .unwrap()
+            };
+            // Unmaps the buffer when dropped, including if `iter` panics.
+            let map: BufferMapMut<T> = BufferMapMut {
+                buffer: &mut buffer,
+                ptr: unsafe { NonNull::new_unchecked(ptr) },
+            };
+            for (index, element) in iter.by_ref().take(len).enumerate() {
+                unsafe { map.ptr.as_ptr().add(index).write(element) };
+                num_written += 1;
+            }
+        }
+
+        debug_assert_eq!(
+            len, num_written,
+            "iterator yielded {} elements, but buffer was created with length {}",
+            num_written, len
+        );
+        debug_assert!(
+            iter.next().is_none(),
+            "iterator yielded more than the {} elements the buffer was created with",
+            len
+        );
+        buffer
+    }
+
+    /// Creates a buffer with immutable storage that's persistently mapped into CPU memory.
+    ///
+    /// Requires OpenGL 4.4 or `GL_ARB_buffer_storage`. See [`PersistentBuffer`] for details.
+    ///
+    /// ## Panics
+    /// Panics if persistent buffers aren't supported, or if the GPU is out of memory. Check
+    /// [`PersistentBuffer::is_supported`] beforehand.
+    ///
+    /// [`PersistentBuffer`]: ./struct.PersistentBuffer.html
+    /// [`PersistentBuffer::is_supported`]: ./struct.PersistentBuffer.html#method.is_supported
+    pub fn new_persistent(
+        size: usize,
+        flags: PersistentFlags,
+        state: Rc<ContextState>,
+    ) -> PersistentBuffer<T> {
+        assert!(
+            PersistentBuffer::<T>::is_supported(&state),
+            "persistent buffers require OpenGL 4.4 or GL_ARB_buffer_storage"
+        );
+        let (raw, ptr) = {
+            let ContextState {
+                ref buffer_binds,
+                ref gl,
+                ..
+            } = *state;
+
+            let mut raw = RawBuffer::new(gl);
+            let ptr = unsafe {
+                let mut bind = buffer_binds.copy_write.bind_mut(&mut raw, gl);
+                bind.alloc_persistent(size, flags)
+            };
+            (raw, NonNull::new(ptr).unwrap_or_else(NonNull::dangling))
+        };
+
+        let buffer = Buffer { raw, state };
+        buffer.track();
+        PersistentBuffer { buffer, ptr, flags }
+    }
+
+    fn track(&self) {
+        self.state.object_registry.track(
+            ObjectKind::Buffer,
+            self.handle(),
+            any::type_name::<Self>(),
+            || Some(self.byte_len()),
+        );
+    }
+
+    /// Sets the buffer's debug label, which graphics debuggers and driver debug messages use to
+    /// identify it. Does nothing on the OpenGL side unless the context supports OpenGL 4.3 or
+    /// `GL_KHR_debug`. With the `object-registry` feature, the label also shows up in the
+    /// buffer's [`ObjectInfo`].
+    ///
+    /// [`ObjectInfo`]: ../struct.ObjectInfo.html
+    pub fn set_label(&self, label: &str) {
+        self.state
+            .set_object_label(ObjectKind::Buffer, self.handle(), label);
     }
 
     /// Returns the number of elements in the buffer.
@@ -108,6 +336,12 @@ impl<T: 'static + Copy> Buffer<T> {
         self.raw.size()
     }
 
+    /// Returns the size of the buffer in bytes.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+
     /// Reads data from the GPU into `buf`, starting at `offset` elements into the buffer.
     ///
     /// ## Safety
@@ -118,7 +352,9 @@ impl<T: 'static + Copy> Buffer<T> {
     /// info on what unspecified data can do.
     ///
     /// ## Panics
-    /// Panics if `offset + buf.len() > self.len()`
+    /// Panics if `offset + buf.len() > self.len()`, or if the buffer is mapped with [`map`].
+    ///
+    /// [`map`]: ./struct.Buffer.html#method.map
     #[inline]
     pub unsafe fn get_data(&self, offset: usize, buf: &mut [T]) {
         let ContextState {
@@ -135,7 +371,7 @@ impl<T: 'static + Copy> Buffer<T> {
     /// the buffer.
     ///
     /// ## Panics
-    /// Panics if `offset + buf.len() > self.len()`
+    /// Panics if `offset + buf.len() > self.len()`, or if the buffer is mapped.
     #[inline]
     pub fn sub_data(&mut self, offset: usize, data: &[T]) {
         let ContextState {
@@ -148,6 +384,126 @@ impl<T: 'static + Copy> Buffer<T> {
         bind.sub_data(offset, data);
     }
 
+    /// Replaces the buffer's storage with a new allocation containing `data`.
+    ///
+    /// The buffer keeps its handle, but OpenGL is free to hand it fresh storage instead of
+    /// waiting for any draw calls still reading the old contents. This is the preferred way to
+    /// re-upload data that changes every frame, and it also allows the buffer to change length.
+    ///
+    /// ## Panics
+    /// Panics is GPU is out of memory.
+    #[inline]
+    pub fn orphan(&mut self, usage: BufferUsage, data: &[T]) {
+        let ContextState {
+            ref buffer_binds,
+            ref gl,
+            ..
+        } = *self.state;
+
+        {
+            let mut bind = unsafe { buffer_binds.copy_write.bind_mut(&mut self.raw, gl) };
+            bind.alloc_upload(data, usage);
+        }
+        self.track();
+    }
+
+    /// Returns a view into the elements of the buffer within `range`.
+    ///
+    /// ## Panics
+    /// Panics if the range starts after it ends, or if it ends past `self.len()`.
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> BufferSlice<'_, T> {
+        let start = crate::bound_to_num_start(range.start_bound(), 0);
+        let end = crate::bound_to_num_end(range.end_bound(), self.len());
+        let len = end.checked_sub(start).unwrap_or_else(|| {
+            panic!("Slice range starts at {} but ends at {}", start, end)
+        });
+        if end > self.len() {
+            panic!(
+                "Slice end {} out of range for buffer of length {}",
+                end,
+                self.len()
+            );
+        }
+
+        BufferSlice {
+            buffer: self,
+            offset: start,
+            len,
+        }
+    }
+
+    /// Returns whether the buffer's storage is mapped into CPU memory, either by a live
+    /// [`BufferMap`] or because the buffer is a [`PersistentBuffer`].
+    ///
+    /// [`BufferMap`]: ./struct.BufferMap.html
+    /// [`PersistentBuffer`]: ./struct.PersistentBuffer.html
+    #[inline]
+    pub fn is_mapped(&self) -> bool {
+        self.raw.map_state() != MapState::Unmapped
+    }
+
+    /// Maps the buffer's storage into CPU memory for reading.
+    ///
+    /// While the buffer is mapped, it can't be read from with [`get_data`] or copied to or from
+    /// with [`copy_to`], and those functions will panic if called. The buffer also must not be
+    /// used by any draw calls until the map gets dropped.
+    ///
+    /// Returns an error if the buffer is already mapped, which includes persistent buffers.
+    ///
+    /// ## Safety
+    /// The same as [`get_data`]: if no data has been uploaded to the buffer, its contents are
+    /// unspecified.
+    ///
+    /// [`get_data`]: ./struct.Buffer.html#method.get_data
+    /// [`copy_to`]: ./struct.Buffer.html#method.copy_to
+    pub unsafe fn map(&self) -> Result<BufferMap<'_, T>, AlreadyMapped> {
+        let ContextState {
+            ref buffer_binds,
+            ref gl,
+            ..
+        } = *self.state;
+
+        let bind = buffer_binds.copy_read.bind(&self.raw, gl);
+        let ptr = bind.map(gl::MAP_READ_BIT)?;
+        Ok(BufferMap {
+            buffer: self,
+            ptr: NonNull::new_unchecked(ptr),
+        })
+    }
+
+    /// Maps the buffer's storage into CPU memory for reading and writing.
+    ///
+    /// Returns an error if the buffer is already mapped.
+    ///
+    /// ## Safety
+    /// The same as [`get_data`]: if no data has been uploaded to the buffer, its contents are
+    /// unspecified.
+    ///
+    /// [`get_data`]: ./struct.Buffer.html#method.get_data
+    pub unsafe fn map_mut(&mut self) -> Result<BufferMapMut<'_, T>, AlreadyMapped> {
+        let ptr = {
+            let ContextState {
+                ref buffer_binds,
+                ref gl,
+                ..
+            } = *self.state;
+
+            let bind = buffer_binds.copy_write.bind(&self.raw, gl);
+            bind.map(gl::MAP_READ_BIT | gl::MAP_WRITE_BIT)?
+        };
+        Ok(BufferMapMut {
+            buffer: self,
+            ptr: NonNull::new_unchecked(ptr),
+        })
+    }
+
+    /// Copies the elements within `self_range` into `dest_buf`, starting at `write_offset`.
+    ///
+    /// ## Panics
+    /// Panics if either range is out of bounds, or if either buffer is mapped with [`map`].
+    ///
+    /// [`map`]: ./struct.Buffer.html#method.map
     #[inline]
     pub fn copy_to<R: RangeBounds<usize>>(
         &self,
@@ -167,6 +523,212 @@ impl<T: 'static + Copy> Buffer<T> {
     }
 }
 
+impl<T: 'static + Copy> PersistentBuffer<T> {
+    /// Returns whether the OpenGL implementation supports persistent buffers.
+    #[inline]
+    pub fn is_supported(state: &ContextState) -> bool {
+        state.gl.BufferStorage.is_loaded()
+    }
+
+    /// Returns the access flags the buffer was created with.
+    #[inline]
+    pub fn flags(&self) -> PersistentFlags {
+        self.flags
+    }
+
+    /// Returns a pointer to the start of the mapped storage. The pointer is valid for reads and
+    /// writes of `self.len()` elements (as permitted by the buffer's flags) until the buffer gets
+    /// dropped.
+    #[inline]
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the mapped storage as a slice.
+    ///
+    /// ## Safety
+    /// The GPU must not be writing to the buffer while the slice is alive. If the buffer wasn't
+    /// created with `COHERENT`, GPU writes also need a `GL_CLIENT_MAPPED_BUFFER_BARRIER_BIT`
+    /// memory barrier before they become visible.
+    #[inline]
+    pub unsafe fn as_slice(&self) -> &[T] {
+        slice::from_raw_parts(self.ptr.as_ptr(), self.len())
+    }
+
+    /// Returns the mapped storage as a mutable slice.
+    ///
+    /// ## Safety
+    /// The GPU must not be reading from or writing to the buffer while the slice is alive.
+    #[inline]
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [T] {
+        slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len())
+    }
+
+    /// Makes writes to the given range of elements visible to the GPU.
+    ///
+    /// Only needed for buffers created with `WRITE` but without `COHERENT`; for those, writes
+    /// aren't guaranteed to reach the GPU until they get flushed.
+    ///
+    /// ## Panics
+    /// Panics if the range ends past `self.len()`.
+    pub fn flush<R: RangeBounds<usize>>(&mut self, range: R) {
+        let start = crate::bound_to_num_start(range.start_bound(), 0);
+        let end = crate::bound_to_num_end(range.end_bound(), self.len());
+        assert!(start <= end && end <= self.len());
+        if self.flags.contains(PersistentFlags::COHERENT) || start == end {
+            return;
+        }
+
+        let ContextState {
+            ref buffer_binds,
+            ref gl,
+            ..
+        } = *self.buffer.state;
+
+        let mut bind = unsafe { buffer_binds.copy_write.bind_mut(&mut self.buffer.raw, gl) };
+        bind.flush_mapped_range(start, end - start);
+    }
+}
+
+impl<T: 'static + Copy> Deref for PersistentBuffer<T> {
+    type Target = Buffer<T>;
+
+    #[inline]
+    fn deref(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+}
+
+impl<'a, T: 'static + Copy> Deref for BufferMap<'a, T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.buffer.len()) }
+    }
+}
+
+impl<'a, T: 'static + Copy> Drop for BufferMap<'a, T> {
+    fn drop(&mut self) {
+        let ContextState {
+            ref buffer_binds,
+            ref gl,
+            ..
+        } = *self.buffer.state;
+
+        unsafe { buffer_binds.copy_read.bind(&self.buffer.raw, gl) }.unmap();
+    }
+}
+
+impl<'a, T: 'static + Copy> Deref for BufferMapMut<'a, T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.buffer.len()) }
+    }
+}
+
+impl<'a, T: 'static + Copy> DerefMut for BufferMapMut<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.buffer.len()) }
+    }
+}
+
+impl<'a, T: 'static + Copy> Drop for BufferMapMut<'a, T> {
+    fn drop(&mut self) {
+        let ContextState {
+            ref buffer_binds,
+            ref gl,
+            ..
+        } = *self.buffer.state;
+
+        unsafe { buffer_binds.copy_write.bind(&self.buffer.raw, gl) }.unmap();
+    }
+}
+
+impl<'a, T: 'static + Copy> BufferSlice<'a, T> {
+    /// Returns the buffer being sliced.
+    #[inline]
+    pub fn buffer(&self) -> &'a Buffer<T> {
+        self.buffer
+    }
+
+    /// Returns the index of the slice's first element within the buffer.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of elements in the slice.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the range of buffer elements covered by the slice. This can be passed directly to
+    /// the `range` parameter of [`Framebuffer::draw`] when drawing from a sub-allocated mesh.
+    ///
+    /// [`Framebuffer::draw`]: ../framebuffer/trait.Framebuffer.html#method.draw
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    /// Returns the offset of the slice into the buffer, in bytes, as expected by functions like
+    /// `glBindBufferRange`.
+    #[inline]
+    pub fn byte_offset(&self) -> usize {
+        self.offset * mem::size_of::<T>()
+    }
+
+    /// Returns the size of the slice in bytes.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.len * mem::size_of::<T>()
+    }
+
+    /// Returns a view into the elements of the slice within `range`.
+    ///
+    /// ## Panics
+    /// Panics if the range starts after it ends, or if it ends past `self.len()`.
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> BufferSlice<'a, T> {
+        let start = crate::bound_to_num_start(range.start_bound(), 0);
+        let end = crate::bound_to_num_end(range.end_bound(), self.len);
+        if end > self.len {
+            panic!(
+                "Slice end {} out of range for slice of length {}",
+                end, self.len
+            );
+        }
+
+        self.buffer.slice(self.offset + start..self.offset + end)
+    }
+
+    /// Reads data from the GPU into `buf`, starting at `offset` elements into the slice.
+    ///
+    /// ## Safety
+    /// See [`Buffer::get_data`](./struct.Buffer.html#method.get_data).
+    ///
+    /// ## Panics
+    /// Panics if `offset + buf.len() > self.len()`
+    #[inline]
+    pub unsafe fn get_data(&self, offset: usize, buf: &mut [T]) {
+        assert!(
+            offset + buf.len() <= self.len,
+            "Attempted to get data from slice where offset + request length exceeded slice length"
+        );
+        self.buffer.get_data(self.offset + offset, buf);
+    }
+}
+
 impl<T: 'static + Copy> GLObject for Buffer<T> {
     #[inline]
     fn handle(&self) -> Handle {
@@ -180,6 +742,9 @@ impl<T: 'static + Copy> GLObject for Buffer<T> {
 
 impl<T: 'static + Copy> Drop for Buffer<T> {
     fn drop(&mut self) {
+        self.state
+            .object_registry
+            .untrack(ObjectKind::Buffer, self.handle());
         unsafe {
             self.raw.delete(&self.state);
         }
@@ -189,7 +754,7 @@ impl<T: 'static + Copy> Drop for Buffer<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_helper::CONTEXT_STATE;
+    use crate::{test_helper::CONTEXT_STATE, GLError};
 
     quickcheck! {
         fn buffer_data(data: Vec<u32>) -> bool {
@@ -201,5 +766,135 @@ mod tests {
                 buf_read == data
             })
         }
+
+        fn buffer_from_iter(data: Vec<u32>) -> bool {
+            CONTEXT_STATE.with(|context_state| {
+                let buffer = Buffer::from_iter(
+                    BufferUsage::StaticDraw,
+                    data.len(),
+                    data.iter().cloned(),
+                    context_state.clone(),
+                );
+                let mut buf_read = vec![0; data.len()];
+                unsafe{ buffer.get_data(0, &mut buf_read) };
+
+                buf_read == data
+            })
+        }
+
+        fn buffer_slice_data(data: Vec<u32>, start: usize, end: usize) -> bool {
+            let (start, end) = match data.len() {
+                0 => (0, 0),
+                len => {
+                    let (a, b) = (start % len, end % len);
+                    (a.min(b), a.max(b))
+                }
+            };
+            CONTEXT_STATE.with(|context_state| {
+                let buffer = Buffer::with_data(BufferUsage::StaticDraw, &data, context_state.clone());
+                let slice = buffer.slice(start..end);
+                let mut buf_read = vec![0; slice.len()];
+                unsafe{ slice.get_data(0, &mut buf_read) };
+
+                buf_read[..] == data[start..end]
+            })
+        }
+    }
+
+    #[test]
+    fn data_offsets() {
+        CONTEXT_STATE.with(|context_state| {
+            let mut buffer =
+                Buffer::with_data(BufferUsage::StaticDraw, &[0u32; 8], context_state.clone());
+            buffer.sub_data(2, &[1, 2, 3]);
+
+            let mut buf_read = [0; 8];
+            unsafe { buffer.get_data(0, &mut buf_read) };
+            assert_eq!([0, 0, 1, 2, 3, 0, 0, 0], buf_read);
+
+            let mut buf_read = [0; 2];
+            unsafe { buffer.get_data(3, &mut buf_read) };
+            assert_eq!([2, 3], buf_read);
+        })
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "iterator yielded 2 elements")]
+    fn buffer_from_short_iter() {
+        CONTEXT_STATE.with(|context_state| {
+            Buffer::from_iter(
+                BufferUsage::StaticDraw,
+                4,
+                vec![1u32, 2],
+                context_state.clone(),
+            );
+        })
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "iterator yielded more than the 2 elements")]
+    fn buffer_from_long_iter() {
+        CONTEXT_STATE.with(|context_state| {
+            Buffer::from_iter(
+                BufferUsage::StaticDraw,
+                2,
+                vec![1u32, 2, 3],
+                context_state.clone(),
+            );
+        })
+    }
+
+    #[test]
+    fn try_with_data() {
+        CONTEXT_STATE.with(|context_state| {
+            let data = [1u32, 2, 3, 4];
+            let buffer =
+                Buffer::try_with_data(BufferUsage::StaticDraw, &data, context_state.clone())
+                    .unwrap();
+            assert_eq!(data.len(), buffer.len());
+
+            let mut buf_read = [0; 4];
+            unsafe { buffer.get_data(0, &mut buf_read) };
+            assert_eq!(data, buf_read);
+        })
+    }
+
+    #[test]
+    fn alloc_error_from_gl_error() {
+        assert_eq!(
+            BufferAllocError::OutOfMemory,
+            BufferAllocError::from(GLError::OutOfMemory)
+        );
+        assert_eq!(
+            BufferAllocError::Other(GLError::InvalidValue),
+            BufferAllocError::from(GLError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn buffer_map() {
+        CONTEXT_STATE.with(|context_state| {
+            let mut buffer = Buffer::with_data(
+                BufferUsage::DynamicDraw,
+                &[1u32, 2, 3],
+                context_state.clone(),
+            );
+            assert!(!buffer.is_mapped());
+
+            unsafe {
+                let map = buffer.map().unwrap();
+                assert_eq!(&[1, 2, 3], &map[..]);
+                assert!(buffer.is_mapped());
+                assert!(buffer.map().is_err());
+            }
+            assert!(!buffer.is_mapped());
+
+            unsafe { buffer.map_mut().unwrap()[1] = 5 };
+            let mut buf_read = [0; 3];
+            unsafe { buffer.get_data(0, &mut buf_read) };
+            assert_eq!([1, 5, 3], buf_read);
+        })
     }
 }
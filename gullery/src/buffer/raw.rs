@@ -12,12 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{ContextState, Handle};
+use crate::{
+    bind::Bind,
+    error::{ErrorScope, GLError},
+    ContextState, Handle,
+};
 
 use crate::gl::{self, types::*, Gl};
 
 use std::{
     cell::Cell,
+    error::Error,
+    fmt,
     marker::PhantomData,
     mem,
     ops::{Deref, RangeBounds},
@@ -27,6 +33,7 @@ use std::{
 pub struct RawBuffer<T: Copy> {
     handle: Handle,
     size: usize,
+    map_state: Cell<MapState>,
     /// `*const ()` used to opt out of `Send` and `Sync` without relying on the unstable opt-out
     /// features.
     _marker: PhantomData<(T, *const ())>,
@@ -57,6 +64,17 @@ where
     gl: &'a Gl,
 }
 
+/// Whether, and how, a buffer's storage is mapped into CPU memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MapState {
+    Unmapped,
+    /// Mapped by a `BufferMap` or `BufferMapMut`, which unmaps the buffer when dropped.
+    Mapped,
+    /// Mapped for the buffer's entire lifetime. Unlike regular mappings, persistent mappings don't
+    /// stop the buffer from being used by other OpenGL commands.
+    Persistent,
+}
+
 const USAGE_OFFSET: GLenum = 35039;
 
 /// Buffer usage hint.
@@ -77,9 +95,36 @@ pub enum BufferUsage {
     DynamicCopy = (gl::DYNAMIC_COPY - USAGE_OFFSET) as u8,
 }
 
-pub unsafe trait RawBindTarget: 'static + Sized {
+/// Error returned when allocating a buffer's storage fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferAllocError {
+    /// The GPU doesn't have enough free memory for the buffer.
+    OutOfMemory,
+    /// The driver allocated a different amount of storage than was requested. Sizes are in bytes.
+    SizeMismatch { requested: usize, allocated: usize },
+    /// OpenGL reported an error other than `GL_OUT_OF_MEMORY` while allocating the buffer.
+    Other(GLError),
+}
+
+/// Error returned when trying to map a buffer that's already mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyMapped;
+
+bitflags! {
+    /// Access flags for persistently-mapped buffers.
+    pub struct PersistentFlags: u32 {
+        /// The mapping can be read from.
+        const READ = gl::MAP_READ_BIT;
+        /// The mapping can be written to.
+        const WRITE = gl::MAP_WRITE_BIT;
+        /// Writes through the mapping become visible to the GPU without explicit flushes, and GPU
+        /// writes become visible to the mapping once a fence has been waited on.
+        const COHERENT = gl::MAP_COHERENT_BIT;
+    }
+}
+
+pub unsafe trait RawBindTarget: 'static + Sized + Bind {
     const TARGET: GLenum;
-    fn bound_buffer(&self) -> &Cell<Option<Handle>>;
 
     #[inline]
     unsafe fn bind<'a, T: Copy>(
@@ -88,11 +133,7 @@ pub unsafe trait RawBindTarget: 'static + Sized {
         gl: &'a Gl,
     ) -> RawBoundBuffer<'a, T, Self> {
         let handle = buffer.handle;
-        let bound_buffer = self.bound_buffer();
-        if bound_buffer.get() != Some(handle) {
-            gl.BindBuffer(Self::TARGET, handle.get());
-            bound_buffer.set(Some(handle));
-        }
+        self.bind_handle(Some(handle), gl);
 
         debug_assert_eq!(Some(handle), {
             let mut bound = 0;
@@ -118,18 +159,13 @@ pub unsafe trait RawBindTarget: 'static + Sized {
             gl,
         }
     }
-    #[inline]
-    unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_buffer().set(None);
-        gl.BindBuffer(Self::TARGET, 0)
-    }
 }
 
 pub mod targets {
     use super::*;
     macro_rules! raw_bind_target {
         ($(
-            pub target $target_name:ident = $target_enum:expr;
+            pub target $target_name:ident = ($target_enum:expr, $binding_enum:expr);
         )*) => ($(
             pub struct $target_name {
                 bound_buffer: Cell<Option<Handle>>,
@@ -146,11 +182,19 @@ pub mod targets {
             }
             unsafe impl RawBindTarget for $target_name {
                 const TARGET: GLenum = $target_enum;
+            }
+            impl Bind for $target_name {
+                const BINDING: GLenum = $binding_enum;
 
                 #[inline]
-                fn bound_buffer(&self) -> &Cell<Option<Handle>> {
+                fn bound_handle(&self) -> &Cell<Option<Handle>> {
                     &self.bound_buffer
                 }
+
+                #[inline]
+                unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+                    gl.BindBuffer($target_enum, handle.map(|h| h.get()).unwrap_or(0));
+                }
             }
         )*);
     }
@@ -158,14 +202,15 @@ pub mod targets {
     // The ARRAY_BUFFER and ELEMENT_ARRAY_BUFFER targets are implemented in the vertex::vao module,
     // under the VertexArrayObjTarget struct.
     raw_bind_target! {
-        pub target RawCopyRead = gl::COPY_READ_BUFFER;
-        pub target RawCopyWrite = gl::COPY_WRITE_BUFFER;
-        // pub target RawDrawIndirect = gl::DRAW_INDIRECT_BUFFER;
-        // pub target RawPixelPack = gl::PIXEL_PACK_BUFFER;
-        // pub target RawPixelUnpack = gl::PIXEL_UNPACK_BUFFER;
-        // pub target RawTexture = gl::TEXTURE_BUFFER;
-        // pub target RawTransformFeedback = gl::TRANSFORM_FEEDBACK_BUFFER;
-        // pub target RawUniform = gl::UNIFORM_BUFFER;
+        pub target RawCopyRead = (gl::COPY_READ_BUFFER, gl::COPY_READ_BUFFER_BINDING);
+        pub target RawCopyWrite = (gl::COPY_WRITE_BUFFER, gl::COPY_WRITE_BUFFER_BINDING);
+        // pub target RawDrawIndirect = (gl::DRAW_INDIRECT_BUFFER, gl::DRAW_INDIRECT_BUFFER_BINDING);
+        // pub target RawPixelPack = (gl::PIXEL_PACK_BUFFER, gl::PIXEL_PACK_BUFFER_BINDING);
+        // pub target RawPixelUnpack = (gl::PIXEL_UNPACK_BUFFER, gl::PIXEL_UNPACK_BUFFER_BINDING);
+        // pub target RawTexture = (gl::TEXTURE_BUFFER, gl::TEXTURE_BUFFER_BINDING);
+        // pub target RawTransformFeedback =
+        //     (gl::TRANSFORM_FEEDBACK_BUFFER, gl::TRANSFORM_FEEDBACK_BUFFER_BINDING);
+        // pub target RawUniform = (gl::UNIFORM_BUFFER, gl::UNIFORM_BUFFER_BINDING);
     }
 }
 
@@ -182,6 +227,7 @@ impl<T: Copy> RawBuffer<T> {
             RawBuffer {
                 handle,
                 size: 0,
+                map_state: Cell::new(MapState::Unmapped),
                 _marker: PhantomData,
             }
         }
@@ -198,6 +244,11 @@ impl<T: Copy> RawBuffer<T> {
         self.handle
     }
 
+    #[inline]
+    pub(crate) fn map_state(&self) -> MapState {
+        self.map_state.get()
+    }
+
     pub(crate) unsafe fn delete(&mut self, state: &ContextState) {
         if mem::size_of::<T>() != 0 {
             state.buffer_binds.unbind(&self, &state.gl);
@@ -213,11 +264,16 @@ where
 {
     #[inline]
     pub(crate) unsafe fn get_data(&self, offset: usize, buf: &mut [T]) {
+        assert_ne!(
+            MapState::Mapped,
+            self.buffer.map_state.get(),
+            "can't read data from a buffer while it's mapped"
+        );
         if mem::size_of::<T>() != 0 {
             if offset + buf.len() <= self.buffer.size {
                 self.gl.GetBufferSubData(
                     B::TARGET,
-                    offset as GLintptr,
+                    (offset * mem::size_of::<T>()) as GLintptr,
                     (buf.len() * mem::size_of::<T>()) as GLsizeiptr,
                     buf.as_mut_ptr() as *mut GLvoid,
                 );
@@ -237,6 +293,11 @@ where
         C: RawBindTarget,
         R: RangeBounds<usize>,
     {
+        assert!(
+            self.buffer.map_state.get() != MapState::Mapped
+                && dest_bind.buffer.map_state.get() != MapState::Mapped,
+            "can't copy data between buffers while either is mapped"
+        );
         if mem::size_of::<T>() != 0 {
             let read_offset = crate::bound_to_num_start(self_range.start_bound(), 0);
             let read_end = crate::bound_to_num_end(self_range.end_bound(), self.buffer.size);
@@ -270,6 +331,82 @@ where
             }
         }
     }
+
+    /// Maps all of the buffer's storage with the given `GL_MAP_*` access bits. Returns a dangling
+    /// pointer if the storage is empty, since OpenGL can't map zero bytes.
+    pub(crate) fn map(&self, access: GLbitfield) -> Result<*mut T, AlreadyMapped> {
+        if self.buffer.map_state.get() != MapState::Unmapped {
+            return Err(AlreadyMapped);
+        }
+
+        let byte_size = self.buffer.size * mem::size_of::<T>();
+        let mapped = if byte_size == 0 {
+            ptr::NonNull::dangling().as_ptr()
+        } else {
+            let _error_scope = ErrorScope::new(self.gl, "buffer mapping");
+            unsafe {
+                self.gl
+                    .MapBufferRange(B::TARGET, 0, byte_size as GLsizeiptr, access)
+                    as *mut T
+            }
+        };
+        assert!(!mapped.is_null(), "failed to map buffer");
+
+        self.buffer.map_state.set(MapState::Mapped);
+        Ok(mapped)
+    }
+
+    /// Unmaps a buffer mapped with `map`.
+    pub(crate) fn unmap(&self) {
+        debug_assert_eq!(MapState::Mapped, self.buffer.map_state.get());
+        if self.buffer.size * mem::size_of::<T>() != 0 {
+            let unmapped = unsafe { self.gl.UnmapBuffer(B::TARGET) };
+            assert_ne!(
+                gl::FALSE,
+                unmapped,
+                "buffer contents corrupted while mapped"
+            );
+        }
+        self.buffer.map_state.set(MapState::Unmapped);
+    }
+}
+
+impl Error for AlreadyMapped {}
+
+impl fmt::Display for AlreadyMapped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "buffer is already mapped")
+    }
+}
+
+impl From<GLError> for BufferAllocError {
+    fn from(error: GLError) -> BufferAllocError {
+        match error {
+            GLError::OutOfMemory => BufferAllocError::OutOfMemory,
+            error => BufferAllocError::Other(error),
+        }
+    }
+}
+
+impl Error for BufferAllocError {}
+
+impl fmt::Display for BufferAllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            BufferAllocError::OutOfMemory => write!(f, "out of GPU memory for buffer allocation"),
+            BufferAllocError::SizeMismatch {
+                requested,
+                allocated,
+            } => write!(
+                f,
+                "requested {} byte buffer, but OpenGL allocated {} bytes",
+                requested, allocated
+            ),
+            BufferAllocError::Other(error) => {
+                write!(f, "OpenGL error {} in buffer allocation", error)
+            }
+        }
+    }
 }
 
 impl<'a, T, B> RawBoundBufferMut<'a, T, B>
@@ -280,12 +417,17 @@ where
     #[inline]
     pub(crate) fn sub_data(&mut self, offset: usize, data: &[T]) {
         assert!(offset + data.len() <= isize::max_value() as usize);
+        assert_ne!(
+            MapState::Mapped,
+            self.buffer.map_state.get(),
+            "can't upload data to a buffer while it's mapped"
+        );
         if mem::size_of::<T>() != 0 {
             if offset + data.len() <= self.buffer.size {
                 unsafe {
                     self.gl.BufferSubData(
                         B::TARGET,
-                        offset as GLintptr,
+                        (offset * mem::size_of::<T>()) as GLintptr,
                         (data.len() * mem::size_of::<T>()) as GLsizeiptr,
                         data.as_ptr() as *const GLvoid,
                     )
@@ -300,30 +442,65 @@ where
     pub(crate) unsafe fn alloc_size(&mut self, size: usize, usage: BufferUsage) {
         assert!(size <= isize::max_value() as usize);
         if mem::size_of::<T>() != 0 {
+            let _error_scope = ErrorScope::new(self.gl, "glBufferData");
             self.gl.BufferData(
                 B::TARGET,
                 (size * mem::size_of::<T>()) as GLsizeiptr,
                 ptr::null_mut(),
                 usage.to_gl_enum(),
             );
+        }
+        // Zero-sized elements don't need any storage, but the length still has to be recorded
+        // so that draws from `Buffer<()>` know how many vertices to run.
+        self.buffer.size = size;
+    }
 
-            let error = self.gl.GetError();
-            if error == 0 {
-                self.buffer.size = size;
-            } else {
-                if error == gl::OUT_OF_MEMORY {
-                    panic!("OpenGL out of memory!");
-                } else {
-                    panic!("Unexpected OpenGL error: {}", error);
-                }
+    /// Like `alloc_upload`, but returns an error if OpenGL fails to allocate the storage or if the
+    /// driver allocates a different amount of storage than was requested.
+    pub(crate) fn try_alloc_upload(
+        &mut self,
+        data: &[T],
+        usage: BufferUsage,
+    ) -> Result<(), BufferAllocError> {
+        assert!(data.len() <= isize::MAX as usize);
+        if mem::size_of::<T>() == 0 {
+            self.buffer.size = data.len();
+            return Ok(());
+        }
+
+        let requested = mem::size_of_val(data);
+        unsafe {
+            let error_scope = ErrorScope::new(self.gl, "glBufferData");
+            self.gl.BufferData(
+                B::TARGET,
+                requested as GLsizeiptr,
+                data.as_ptr() as *const GLvoid,
+                usage.to_gl_enum(),
+            );
+
+            error_scope.finish()?;
+
+            // `GLint` overflows for buffers of 2 GiB or more, so the 64-bit query has to be used.
+            let mut allocated: GLint64 = 0;
+            self.gl
+                .GetBufferParameteri64v(B::TARGET, gl::BUFFER_SIZE, &mut allocated);
+            if allocated as u64 != requested as u64 {
+                return Err(BufferAllocError::SizeMismatch {
+                    requested,
+                    allocated: allocated as usize,
+                });
             }
         }
+
+        self.buffer.size = data.len();
+        Ok(())
     }
 
     #[inline]
     pub(crate) fn alloc_upload(&mut self, data: &[T], usage: BufferUsage) {
         assert!(data.len() <= isize::max_value() as usize);
         if mem::size_of::<T>() != 0 {
+            let _error_scope = ErrorScope::new(self.gl, "glBufferData");
             unsafe {
                 self.gl.BufferData(
                     B::TARGET,
@@ -332,19 +509,61 @@ where
                     usage.to_gl_enum(),
                 )
             };
+        }
+        self.buffer.size = data.len();
+    }
+}
 
-            let error = unsafe { self.gl.GetError() };
-            if error == 0 {
-                self.buffer.size = data.len();
+impl<'a, T, B> RawBoundBufferMut<'a, T, B>
+where
+    B: 'a + RawBindTarget,
+    T: 'a + Copy,
+{
+    /// Allocates immutable, persistently mappable storage and maps all of it. Returns a null
+    /// pointer if nothing got mapped, which happens for zero-sized allocations.
+    #[inline]
+    pub(crate) fn alloc_persistent(&mut self, size: usize, flags: PersistentFlags) -> *mut T {
+        assert!(size <= isize::MAX as usize);
+        let byte_size = size * mem::size_of::<T>();
+        let storage_flags = flags.bits() | gl::MAP_PERSISTENT_BIT;
+        let mut map_flags = storage_flags;
+        if !flags.contains(PersistentFlags::COHERENT) && flags.contains(PersistentFlags::WRITE) {
+            map_flags |= gl::MAP_FLUSH_EXPLICIT_BIT;
+        }
+
+        let _error_scope = ErrorScope::new(self.gl, "persistent buffer allocation");
+        unsafe {
+            self.gl.BufferStorage(
+                B::TARGET,
+                byte_size as GLsizeiptr,
+                ptr::null(),
+                storage_flags,
+            );
+            self.buffer.size = size;
+
+            self.buffer.map_state.set(MapState::Persistent);
+            if byte_size == 0 {
+                ptr::null_mut()
             } else {
-                if error == gl::OUT_OF_MEMORY {
-                    panic!("OpenGL out of memory!");
-                } else {
-                    panic!("Unexpected OpenGL error: {}", error);
-                }
+                self.gl
+                    .MapBufferRange(B::TARGET, 0, byte_size as GLsizeiptr, map_flags)
+                    as *mut T
             }
         }
     }
+
+    /// Makes writes made through a non-coherent persistent mapping visible to the GPU.
+    #[inline]
+    pub(crate) fn flush_mapped_range(&mut self, offset: usize, len: usize) {
+        assert!(offset + len <= self.buffer.size);
+        unsafe {
+            self.gl.FlushMappedBufferRange(
+                B::TARGET,
+                (offset * mem::size_of::<T>()) as GLintptr,
+                (len * mem::size_of::<T>()) as GLsizeiptr,
+            );
+        }
+    }
 }
 
 impl<'a, T, B> Deref for RawBoundBufferMut<'a, T, B>
@@ -0,0 +1,196 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{Buffer, BufferUsage};
+use crate::{
+    gl::{self, types::*, Gl},
+    ContextState,
+};
+
+use std::{mem, ops::Range, ptr, rc::Rc};
+
+/// A buffer split into several regions, which hands out aligned sub-ranges of one region per
+/// frame.
+///
+/// Offsets passed to `glBindBufferRange` must be multiples of
+/// [`ContextState::uniform_buffer_offset_alignment`], which makes packing per-draw uniform data
+/// into a single buffer error-prone. Every range returned by [`alloc`] starts on that alignment.
+///
+/// Once a frame's draws have been submitted, [`end_frame`] fences the current region and moves on
+/// to the next one. If the GPU is still reading from the next region, `end_frame` waits for it to
+/// finish before the region gets reused. With two or three regions, that wait should rarely block.
+///
+/// [`ContextState::uniform_buffer_offset_alignment`]: ../struct.ContextState.html#method.uniform_buffer_offset_alignment
+/// [`alloc`]: ./struct.RingBuffer.html#method.alloc
+/// [`end_frame`]: ./struct.RingBuffer.html#method.end_frame
+pub struct RingBuffer<T: 'static + Copy> {
+    buffer: Buffer<T>,
+    region_len: usize,
+    alignment: usize,
+    region: usize,
+    head: usize,
+    fences: Vec<GLsync>,
+}
+
+impl<T: 'static + Copy> RingBuffer<T> {
+    /// Creates a ring buffer with `regions` regions, each of which can hold at least `region_len`
+    /// elements. The region length gets rounded up to a multiple of [`alignment`].
+    ///
+    /// ## Panics
+    /// Panics if `regions` is `0`, or if the GPU is out of memory.
+    ///
+    /// [`alignment`]: ./struct.RingBuffer.html#method.alignment
+    pub fn new(region_len: usize, regions: usize, state: Rc<ContextState>) -> RingBuffer<T> {
+        assert!(regions > 0, "ring buffers need at least one region");
+        let byte_alignment = state.uniform_buffer_offset_alignment();
+        let size = mem::size_of::<T>().max(1);
+        let alignment = byte_alignment / gcd(byte_alignment, size);
+        let region_len = round_up(region_len, alignment);
+
+        RingBuffer {
+            buffer: Buffer::with_size(BufferUsage::StreamDraw, region_len * regions, state),
+            region_len,
+            alignment,
+            region: 0,
+            head: 0,
+            fences: vec![ptr::null(); regions],
+        }
+    }
+
+    /// Uploads `data` into the current region, returning the range of buffer elements it was
+    /// written to. The range can be passed to [`Buffer::slice`] on [`buffer`].
+    ///
+    /// Returns `None` if the current region doesn't have room left for `data`.
+    ///
+    /// [`Buffer::slice`]: ./struct.Buffer.html#method.slice
+    /// [`buffer`]: ./struct.RingBuffer.html#method.buffer
+    pub fn alloc(&mut self, data: &[T]) -> Option<Range<usize>> {
+        let start = round_up(self.head, self.alignment);
+        let end = start.checked_add(data.len())?;
+        if end > self.region_len {
+            return None;
+        }
+
+        let region_start = self.region * self.region_len;
+        self.buffer.sub_data(region_start + start, data);
+        self.head = end;
+        Some(region_start + start..region_start + end)
+    }
+
+    /// Finishes the current frame, and moves on to the next region.
+    ///
+    /// This must be called after all draws using the frame's allocations have been submitted.
+    /// Blocks if the GPU hasn't finished with the next region yet.
+    pub fn end_frame(&mut self) {
+        let gl = &self.buffer.state.gl;
+        unsafe {
+            self.fences[self.region] = gl.FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            self.region = (self.region + 1) % self.fences.len();
+            self.head = 0;
+
+            let fence = mem::replace(&mut self.fences[self.region], ptr::null());
+            if !fence.is_null() {
+                wait_fence(fence, gl);
+                gl.DeleteSync(fence);
+            }
+        }
+    }
+
+    /// The underlying buffer.
+    #[inline]
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+
+    /// The number of elements each allocation's start gets aligned to.
+    #[inline]
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// The number of elements each region can hold.
+    #[inline]
+    pub fn region_len(&self) -> usize {
+        self.region_len
+    }
+}
+
+impl<T: 'static + Copy> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let gl = &self.buffer.state.gl;
+        for &fence in &self.fences {
+            if !fence.is_null() {
+                unsafe { gl.DeleteSync(fence) };
+            }
+        }
+    }
+}
+
+unsafe fn wait_fence(fence: GLsync, gl: &Gl) {
+    let mut flags = gl::SYNC_FLUSH_COMMANDS_BIT;
+    // `WAIT_FAILED` only happens if the context got lost, in which case there's nothing to
+    // wait for.
+    while gl.ClientWaitSync(fence, flags, 1_000_000_000) == gl::TIMEOUT_EXPIRED {
+        flags = 0;
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+fn round_up(value: usize, multiple: usize) -> usize {
+    value.div_ceil(multiple) * multiple
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::CONTEXT_STATE;
+
+    #[test]
+    fn ring_buffer_alloc() {
+        CONTEXT_STATE.with(|context_state| {
+            let mut ring = RingBuffer::<u32>::new(64, 3, context_state.clone());
+            let byte_alignment = context_state.uniform_buffer_offset_alignment();
+
+            for frame in 0..6u32 {
+                let mut ranges = vec![];
+                for len in 1..4 {
+                    let data = vec![frame; len];
+                    let range = match ring.alloc(&data) {
+                        Some(range) => range,
+                        None => break,
+                    };
+                    assert_eq!(0, range.start * mem::size_of::<u32>() % byte_alignment);
+                    ranges.push((range, data));
+                }
+                assert!(!ranges.is_empty());
+
+                for (range, data) in ranges {
+                    let mut read = vec![0; range.len()];
+                    unsafe { ring.buffer().slice(range).get_data(0, &mut read) };
+                    assert_eq!(data, read);
+                }
+                assert!(ring.alloc(&vec![0; ring.region_len() + 1]).is_none());
+                ring.end_frame();
+            }
+        });
+    }
+}
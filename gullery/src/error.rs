@@ -0,0 +1,233 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OpenGL error checking.
+
+use crate::gl::{self, types::*, Gl};
+
+use std::{error::Error, fmt, mem, thread};
+
+/// The maximum number of errors drained when entering an `ErrorScope`.
+///
+/// OpenGL implementations may record multiple errors at once, but some (like a lost context)
+/// report the same error indefinitely. This keeps us from spinning forever in that case.
+const MAX_DRAINED_ERRORS: usize = 32;
+
+/// An error reported by `glGetError`.
+///
+/// Gullery checks its inputs before handing them to OpenGL, so most of these indicate a bug in
+/// Gullery or a lost context. The exception is `OutOfMemory`, which can happen with valid inputs
+/// and gets reported by functions that allocate GPU memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GLError {
+    InvalidEnum,
+    InvalidValue,
+    InvalidOperation,
+    /// The framebuffer being drawn to or read from isn't complete.
+    InvalidFramebufferOperation,
+    /// OpenGL doesn't have enough free memory to execute the command.
+    OutOfMemory,
+    StackUnderflow,
+    StackOverflow,
+    /// An error code not listed in the OpenGL specification.
+    Unknown(GLenum),
+}
+
+impl GLError {
+    /// Converts a `glGetError` return value into a `GLError`. Returns `None` for `GL_NO_ERROR`.
+    pub fn from_gl_enum(error: GLenum) -> Option<GLError> {
+        match error {
+            gl::NO_ERROR => None,
+            gl::INVALID_ENUM => Some(GLError::InvalidEnum),
+            gl::INVALID_VALUE => Some(GLError::InvalidValue),
+            gl::INVALID_OPERATION => Some(GLError::InvalidOperation),
+            gl::INVALID_FRAMEBUFFER_OPERATION => Some(GLError::InvalidFramebufferOperation),
+            gl::OUT_OF_MEMORY => Some(GLError::OutOfMemory),
+            gl::STACK_UNDERFLOW => Some(GLError::StackUnderflow),
+            gl::STACK_OVERFLOW => Some(GLError::StackOverflow),
+            _ => Some(GLError::Unknown(error)),
+        }
+    }
+
+    pub fn to_gl_enum(self) -> GLenum {
+        match self {
+            GLError::InvalidEnum => gl::INVALID_ENUM,
+            GLError::InvalidValue => gl::INVALID_VALUE,
+            GLError::InvalidOperation => gl::INVALID_OPERATION,
+            GLError::InvalidFramebufferOperation => gl::INVALID_FRAMEBUFFER_OPERATION,
+            GLError::OutOfMemory => gl::OUT_OF_MEMORY,
+            GLError::StackUnderflow => gl::STACK_UNDERFLOW,
+            GLError::StackOverflow => gl::STACK_OVERFLOW,
+            GLError::Unknown(error) => error,
+        }
+    }
+}
+
+/// Returns the next recorded OpenGL error, if there is one.
+pub(crate) unsafe fn get_error(gl: &Gl) -> Result<(), GLError> {
+    match GLError::from_gl_enum(gl.GetError()) {
+        None => Ok(()),
+        Some(error) => Err(error),
+    }
+}
+
+/// RAII guard that attributes OpenGL errors to the calls made within its lifetime.
+///
+/// Creating the guard discards any errors left over from earlier calls, so that they don't get
+/// blamed on the operation being checked. When the guard gets dropped it checks for new errors and
+/// panics if one occurred, naming the operation that caused it.
+pub(crate) struct ErrorScope<'a> {
+    gl: &'a Gl,
+    operation: &'static str,
+}
+
+impl<'a> ErrorScope<'a> {
+    #[inline]
+    pub(crate) fn new(gl: &'a Gl, operation: &'static str) -> ErrorScope<'a> {
+        unsafe { clear_errors(gl) };
+        ErrorScope { gl, operation }
+    }
+
+    /// Ends the scope, returning any error that occurred within it instead of panicking.
+    #[inline]
+    pub(crate) fn finish(self) -> Result<(), GLError> {
+        let result = unsafe { get_error(self.gl) };
+        mem::forget(self);
+        result
+    }
+}
+
+/// Discards any recorded OpenGL errors, so that the next `GetError` call only reports errors from
+/// calls made after this.
+pub(crate) unsafe fn clear_errors(gl: &Gl) {
+    for _ in 0..MAX_DRAINED_ERRORS {
+        if gl.GetError() == gl::NO_ERROR {
+            break;
+        }
+    }
+}
+
+impl<'a> Drop for ErrorScope<'a> {
+    fn drop(&mut self) {
+        // Don't check for errors while unwinding, since panicking again would abort.
+        if thread::panicking() {
+            return;
+        }
+
+        if let Err(error) = unsafe { get_error(self.gl) } {
+            panic!("OpenGL error {} in {}", error, self.operation);
+        }
+    }
+}
+
+impl Error for GLError {}
+
+impl fmt::Display for GLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            GLError::InvalidEnum => write!(f, "GL_INVALID_ENUM"),
+            GLError::InvalidValue => write!(f, "GL_INVALID_VALUE"),
+            GLError::InvalidOperation => write!(f, "GL_INVALID_OPERATION"),
+            GLError::InvalidFramebufferOperation => write!(f, "GL_INVALID_FRAMEBUFFER_OPERATION"),
+            GLError::OutOfMemory => write!(f, "GL_OUT_OF_MEMORY"),
+            GLError::StackUnderflow => write!(f, "GL_STACK_UNDERFLOW"),
+            GLError::StackOverflow => write!(f, "GL_STACK_OVERFLOW"),
+            GLError::Unknown(error) => write!(f, "unknown error 0x{:X}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        framebuffer::renderbuffer::Renderbuffer, geometry::GLVec2, image_format::Rgba,
+        test_helper::CONTEXT_STATE,
+    };
+
+    #[test]
+    fn gl_enum_round_trip() {
+        assert_eq!(None, GLError::from_gl_enum(gl::NO_ERROR));
+        for &error in &[
+            GLError::InvalidEnum,
+            GLError::InvalidValue,
+            GLError::InvalidOperation,
+            GLError::InvalidFramebufferOperation,
+            GLError::OutOfMemory,
+            GLError::StackUnderflow,
+            GLError::StackOverflow,
+            GLError::Unknown(0xBEEF),
+        ] {
+            assert_eq!(Some(error), GLError::from_gl_enum(error.to_gl_enum()));
+        }
+        assert_eq!("unknown error 0xBEEF", GLError::Unknown(0xBEEF).to_string());
+    }
+
+    #[test]
+    fn error_scope_reports_errors() {
+        CONTEXT_STATE.with(|state| unsafe {
+            let gl = &state.gl;
+            let check = |f: &dyn Fn()| {
+                let error_scope = ErrorScope::new(gl, "test");
+                f();
+                error_scope.finish()
+            };
+
+            assert_eq!(Ok(()), check(&|| gl.Enable(gl::DEPTH_TEST)));
+            gl.Disable(gl::DEPTH_TEST);
+            assert_eq!(Err(GLError::InvalidEnum), check(&|| gl.Enable(0xFFFF)));
+            assert_eq!(
+                Err(GLError::InvalidValue),
+                check(&|| gl.DrawArrays(gl::POINTS, 0, -1))
+            );
+            assert_eq!(
+                Err(GLError::InvalidOperation),
+                check(&|| gl.BindVertexArray(0xFFFF))
+            );
+
+            let mut fbo = 0;
+            gl.GenFramebuffers(1, &mut fbo);
+            gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, fbo);
+            assert_eq!(
+                Err(GLError::InvalidFramebufferOperation),
+                check(&|| gl.Clear(gl::COLOR_BUFFER_BIT))
+            );
+            gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl.DeleteFramebuffers(1, &fbo);
+
+            // Errors left over from before the scope don't get attributed to it.
+            gl.Enable(0xFFFF);
+            assert_eq!(Ok(()), check(&|| ()));
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn renderbuffer_too_many_samples() {
+        CONTEXT_STATE.with(|state| {
+            let mut max_samples = 0;
+            unsafe { state.gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples) };
+
+            let result = Renderbuffer::<Rgba<u8>>::try_new(
+                GLVec2::new(16, 16),
+                max_samples as u32 + 1,
+                state.clone(),
+            );
+            assert_eq!(Some(GLError::InvalidOperation), result.err());
+            assert!(
+                Renderbuffer::<Rgba<u8>>::try_new(GLVec2::new(16, 16), 0, state.clone()).is_ok()
+            );
+        })
+    }
+}
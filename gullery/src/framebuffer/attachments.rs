@@ -16,12 +16,12 @@
 
 use crate::{
     framebuffer::Renderbuffer,
-    geometry::Dimension,
-    image_format::{FormatType, FormatTypeTag, ImageFormatRenderable},
-    texture::{MipSelector, Texture, TextureType},
+    geometry::{Dimension, GLVec2, NonNormalized, ScalarType, TypeTagSingle, D3},
+    image_format::{FormatType, FormatTypeTag, ImageFormat, ImageFormatRenderable},
+    texture::{Dims, MipSelector, Texture, TextureType},
     GLObject, Handle,
 };
-use std::marker::PhantomData;
+use std::{error::Error, fmt, marker::PhantomData};
 
 /// A Rust type that can be used as a [`FramebufferObject`] attachment.
 ///
@@ -30,22 +30,33 @@ use std::marker::PhantomData;
 pub trait AttachmentType: GLObject {
     type Format: ?Sized + ImageFormatRenderable;
     type MipSelector: MipSelector;
+    /// The attachment this type ultimately refers to. For raw types this is `Self`; for
+    /// `&mut Attachment` it's the referenced attachment's `Resolved` type.
+    type Resolved: AttachmentType<Format = Self::Format, MipSelector = Self::MipSelector>;
 
+    /// Register the attachment with `registry`.
+    ///
+    /// `get_member` returns the resolved attachment rather than `Self`, since only the closure
+    /// that projects out the field knows the field's borrow outlives the `Attachments` borrow.
+    /// Resolve the field there: `|a| AttachmentType::resolve(&a.field)`.
     fn add_to_registry<R>(
         registry: &mut R,
         name: &str,
-        get_member: impl FnOnce(&R::Attachments) -> &Self,
+        get_member: impl FnOnce(&R::Attachments) -> &Self::Resolved,
         mip: Self::MipSelector,
     ) where
         R: AttachmentsMemberRegistry;
 
-    /// Resolve the Attachment to a pointer to the innermost type. For raw types this is a no-op,
-    /// but function overloads are used to dereference a `&mut Attachment` to the original value.
-    /// Used for input verification in some functions.
+    /// Resolve the attachment to the innermost type. For raw types this is a no-op, but
+    /// `&mut Attachment` dereferences to the original value.
+    fn resolve(&self) -> &Self::Resolved;
+
+    /// Resolve the Attachment to a pointer to the innermost type. Used for input verification in
+    /// some functions.
     #[doc(hidden)]
     #[inline(always)]
     fn resolve_reference(&self) -> *const () {
-        self as *const Self as *const ()
+        self.resolve() as *const Self::Resolved as *const ()
     }
 }
 
@@ -80,23 +91,195 @@ pub trait Attachments: Sized {
 
         let mut num = 0;
         Self::members(AMRNSImpl(MemberCounter::<Self>(&mut num, PhantomData)));
+        crate::assert_num_members::<Self>(
+            "Attachments",
+            Self::AHC::new_zeroed().as_ref().len(),
+            num,
+        );
         num
     }
 
-    fn color_attachments(&self, for_each: impl FnMut(u8)) {
-        struct AttachmentRefMatcher<'a, A: 'a, F: FnMut(u8)> {
+    /// The number of attachments with a color image format.
+    ///
+    /// Depth and stencil attachments don't get counted.
+    #[inline]
+    fn num_color_attachments() -> usize {
+        struct ColorCounter<'a, A>(&'a mut usize, PhantomData<A>);
+        impl<'a, A: Attachments> AttachmentsMemberRegistryNoSpecifics for ColorCounter<'a, A> {
+            type Attachments = A;
+            #[inline(always)]
+            fn add_member<At: AttachmentType>(
+                &mut self,
+                _: &str,
+                _: impl FnOnce(&Self::Attachments) -> &At,
+            ) {
+                if <At::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE
+                    == FormatTypeTag::Color
+                {
+                    *self.0 += 1;
+                }
+            }
+        }
+
+        let mut num = 0;
+        Self::members(AMRNSImpl(ColorCounter::<Self>(&mut num, PhantomData)));
+        num
+    }
+
+    /// Returns `true` if any of the attachments use an sRGB image format.
+    #[inline]
+    fn has_srgb_attachment() -> bool {
+        struct SrgbFinder<'a, A>(&'a mut bool, PhantomData<A>);
+        impl<'a, A: Attachments> AttachmentsMemberRegistryNoSpecifics for SrgbFinder<'a, A> {
+            type Attachments = A;
+            #[inline(always)]
+            fn add_member<At: AttachmentType>(
+                &mut self,
+                _: &str,
+                _: impl FnOnce(&Self::Attachments) -> &At,
+            ) {
+                *self.0 |= <At::Format as ImageFormatRenderable>::FormatType::IS_SRGB;
+            }
+        }
+
+        let mut has_srgb = false;
+        Self::members(AMRNSImpl(SrgbFinder::<Self>(&mut has_srgb, PhantomData)));
+        has_srgb
+    }
+
+    /// Returns the dimensions shared by all attachments, or `None` if there aren't any
+    /// attachments. Texture attachments are measured at their attached mip level.
+    ///
+    /// This is the area that can be rendered to, and is generally what the viewport should be set
+    /// to.
+    ///
+    /// ## Panics
+    /// Panics if the attachments can't be used together in a framebuffer. See [`check_complete`].
+    ///
+    /// [`check_complete`]: ./trait.Attachments.html#method.check_complete
+    fn dims(&self) -> Option<GLVec2<u32, NonNormalized>> {
+        match self.check_complete() {
+            Ok(dims) => dims,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Checks that the attachments can be used together in a framebuffer, returning the dimensions
+    /// shared by all attachments if they can.
+    ///
+    /// All attachments must have the same dimensions and the same number of samples. Mixing a
+    /// multisampled renderbuffer with a single-sample texture produces an incomplete framebuffer,
+    /// and OpenGL doesn't say which attachment is at fault, so this reports the first attachment
+    /// that doesn't match the ones before it.
+    fn check_complete(&self) -> Result<Option<GLVec2<u32, NonNormalized>>, AttachmentsError> {
+        struct CompletenessChecker<'a, A> {
+            attachments: &'a A,
+            dims: &'a mut Option<GLVec2<u32, NonNormalized>>,
+            samples: Option<u32>,
+            error: &'a mut Option<AttachmentsError>,
+        }
+        impl<'a, A> CompletenessChecker<'a, A> {
+            fn check(&mut self, name: &str, dims: GLVec2<u32, NonNormalized>, samples: u32) {
+                if self.error.is_some() {
+                    return;
+                }
+                match *self.dims {
+                    Some(expected) if expected != dims => {
+                        *self.error = Some(AttachmentsError::DimsMismatch {
+                            attachment: name.to_string(),
+                            dims,
+                            expected,
+                        });
+                        return;
+                    }
+                    _ => *self.dims = Some(dims),
+                }
+                match self.samples {
+                    Some(expected) if expected != samples => {
+                        *self.error = Some(AttachmentsError::SamplesMismatch {
+                            attachment: name.to_string(),
+                            samples,
+                            expected,
+                        });
+                    }
+                    _ => self.samples = Some(samples),
+                }
+            }
+        }
+        impl<'a, A: Attachments> AttachmentsMemberRegistry for CompletenessChecker<'a, A> {
+            type Attachments = A;
+            fn add_renderbuffer<I: ImageFormatRenderable>(
+                &mut self,
+                name: &str,
+                get_member: impl FnOnce(&A) -> &Renderbuffer<I>,
+            ) {
+                let renderbuffer = get_member(self.attachments);
+                self.check(name, renderbuffer.dims(), renderbuffer.samples());
+            }
+            fn add_texture<D, T>(
+                &mut self,
+                name: &str,
+                get_member: impl FnOnce(&A) -> &Texture<D, T>,
+                texture_level: T::MipSelector,
+            ) where
+                D: Dimension<u32>,
+                T: TextureType<D>,
+                T::Format: ImageFormatRenderable,
+            {
+                let texture = get_member(self.attachments);
+                let dims = T::mip_dims(texture.dims(), texture_level);
+                self.check(
+                    name,
+                    GLVec2::new(dims.width(), dims.height()),
+                    u32::from(texture.samples()),
+                );
+            }
+            fn add_texture_3d_slice<C>(
+                &mut self,
+                name: &str,
+                get_member: impl FnOnce(&A) -> &Texture<D3, C>,
+                _: u32,
+                texture_level: u8,
+            ) where
+                C: ImageFormatRenderable,
+            {
+                let texture = get_member(self.attachments);
+                let dims = <C as TextureType<D3>>::mip_dims(texture.dims(), texture_level);
+                self.check(name, GLVec2::new(dims.width(), dims.height()), 0);
+            }
+        }
+
+        let mut dims = None;
+        let mut error = None;
+        Self::members(CompletenessChecker {
+            attachments: self,
+            dims: &mut dims,
+            samples: None,
+            error: &mut error,
+        });
+        match error {
+            Some(error) => Err(error),
+            None => Ok(dims),
+        }
+    }
+
+    /// Calls `for_each` with the index and scalar type of each color attachment.
+    fn color_attachments(&self, for_each: impl FnMut(u8, TypeTagSingle)) {
+        struct AttachmentRefMatcher<'a, A: 'a, F: FnMut(u8, TypeTagSingle)> {
             color_index: u8,
             for_each: F,
             _marker: PhantomData<&'a A>,
         }
-        impl<'a, A: Attachments, F: FnMut(u8)> AttachmentsMemberRegistryNoSpecifics
+        impl<'a, A: Attachments, F: FnMut(u8, TypeTagSingle)> AttachmentsMemberRegistryNoSpecifics
             for AttachmentRefMatcher<'a, A, F>
         {
             type Attachments = A;
             fn add_member<At: AttachmentType>(&mut self, _: &str, _: impl FnOnce(&A) -> &At) {
                 let image_type = <At::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE;
                 if image_type == FormatTypeTag::Color {
-                    (self.for_each)(self.color_index);
+                    let scalar_type =
+                        <<At::Format as ImageFormat>::ScalarType as ScalarType>::PRIM_TAG;
+                    (self.for_each)(self.color_index, scalar_type);
                 }
 
                 if image_type == FormatTypeTag::Color {
@@ -113,6 +296,56 @@ pub trait Attachments: Sized {
     }
 }
 
+/// Error returned by [`Attachments::check_complete`] when a set of attachments can't be used
+/// together in a framebuffer.
+///
+/// [`Attachments::check_complete`]: ./trait.Attachments.html#method.check_complete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachmentsError {
+    /// The attachment's dimensions don't match the dimensions of the attachments before it.
+    DimsMismatch {
+        attachment: String,
+        dims: GLVec2<u32, NonNormalized>,
+        expected: GLVec2<u32, NonNormalized>,
+    },
+    /// The attachment's sample count doesn't match the sample count of the attachments before it.
+    /// Attachments that aren't multisampled have 0 samples.
+    SamplesMismatch {
+        attachment: String,
+        samples: u32,
+        expected: u32,
+    },
+}
+
+impl fmt::Display for AttachmentsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            AttachmentsError::DimsMismatch {
+                attachment,
+                dims,
+                expected,
+            } => write!(
+                f,
+                "Attachment {} has dimensions {}x{}, but previous attachments have dimensions \
+                 {}x{}; all framebuffer attachments must have the same dimensions",
+                attachment, dims.x, dims.y, expected.x, expected.y
+            ),
+            AttachmentsError::SamplesMismatch {
+                attachment,
+                samples,
+                expected,
+            } => write!(
+                f,
+                "Attachment {} has {} samples, but previous attachments have {} samples; all \
+                 framebuffer attachments must have the same sample count",
+                attachment, samples, expected
+            ),
+        }
+    }
+}
+
+impl Error for AttachmentsError {}
+
 /// Container of raw OpenGL attachment handles.
 ///
 /// Can generally be ignored by the end user. Is used as optimization for reducing the number
@@ -140,6 +373,18 @@ pub trait AttachmentsMemberRegistry {
         D: Dimension<u32>,
         T: TextureType<D>,
         T::Format: ImageFormatRenderable;
+    /// Attaches a single Z-slice of a 3D texture's mip level, instead of the entire mip level.
+    ///
+    /// This lets 3D textures be rendered to one slice at a time. `z` gets checked against the
+    /// depth of the mip level when the attachment is made.
+    fn add_texture_3d_slice<C>(
+        &mut self,
+        name: &str,
+        get_member: impl FnOnce(&Self::Attachments) -> &Texture<D3, C>,
+        z: u32,
+        texture_level: u8,
+    ) where
+        C: ImageFormatRenderable;
 }
 
 pub(crate) trait AttachmentsMemberRegistryNoSpecifics {
@@ -179,6 +424,18 @@ where
     {
         self.0.add_member(name, get_member);
     }
+    #[inline]
+    fn add_texture_3d_slice<C>(
+        &mut self,
+        name: &str,
+        get_member: impl FnOnce(&Self::Attachments) -> &Texture<D3, C>,
+        _: u32,
+        _: u8,
+    ) where
+        C: ImageFormatRenderable,
+    {
+        self.0.add_member(name, get_member);
+    }
 }
 
 macro_rules! impl_attachment_array {
@@ -211,6 +468,7 @@ impl Attachments for () {
 impl<I: ImageFormatRenderable> AttachmentType for Renderbuffer<I> {
     type Format = I;
     type MipSelector = ();
+    type Resolved = Self;
 
     fn add_to_registry<R>(
         registry: &mut R,
@@ -222,6 +480,11 @@ impl<I: ImageFormatRenderable> AttachmentType for Renderbuffer<I> {
     {
         registry.add_renderbuffer(name, |r| get_member(r));
     }
+
+    #[inline(always)]
+    fn resolve(&self) -> &Self {
+        self
+    }
 }
 
 impl<D, T> AttachmentType for Texture<D, T>
@@ -232,6 +495,7 @@ where
 {
     type Format = T::Format;
     type MipSelector = T::MipSelector;
+    type Resolved = Self;
 
     fn add_to_registry<R>(
         registry: &mut R,
@@ -243,37 +507,32 @@ where
     {
         registry.add_texture(name, |r| get_member(r), mip);
     }
+
+    #[inline(always)]
+    fn resolve(&self) -> &Self {
+        self
+    }
 }
 
 impl<'a, A: 'a + AttachmentType> AttachmentType for &'a mut A {
     type Format = A::Format;
     type MipSelector = A::MipSelector;
+    type Resolved = A::Resolved;
 
+    #[inline(always)]
     fn add_to_registry<R>(
         registry: &mut R,
         name: &str,
-        get_member: impl FnOnce(&R::Attachments) -> &Self,
+        get_member: impl FnOnce(&R::Attachments) -> &A::Resolved,
         mip_selector: A::MipSelector,
     ) where
         R: AttachmentsMemberRegistry,
     {
-        use std::mem;
-
-        A::add_to_registry(
-            registry,
-            name,
-            // We need to retreive a reference to C from our reference to a
-            // reference to C. Ideally, we'd use the following line:
-            // |r| &**get_member(r)
-            //
-            // But we transmute because the compiler has trouble with lifetime
-            // inference with just a plain call to `&**modify_member(r).
-            |r| unsafe { mem::transmute::<&A, &A>(&**get_member(r)) },
-            mip_selector,
-        );
+        A::add_to_registry(registry, name, get_member, mip_selector);
     }
 
-    fn resolve_reference(&self) -> *const () {
-        A::resolve_reference(self)
+    #[inline(always)]
+    fn resolve(&self) -> &A::Resolved {
+        A::resolve(self)
     }
 }
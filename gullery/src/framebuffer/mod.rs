@@ -25,19 +25,27 @@
 
 pub mod attachments;
 mod raw;
+mod render_pass;
 pub mod render_state;
 pub(crate) mod renderbuffer;
 
 use self::{attachments::*, raw::*};
-pub use self::{raw::DrawMode, renderbuffer::Renderbuffer};
+pub use self::{
+    raw::DrawMode,
+    render_pass::{ClearValues, RenderPass},
+    renderbuffer::Renderbuffer,
+};
 use std::borrow::BorrowMut;
 
-use self::render_state::RenderState;
+use self::render_state::{BlendFuncs, RenderState};
 use crate::{
+    bind::Bind,
     gl::{self, types::*, Gl},
-    geometry::{GLVec2, NonNormalized},
-    image_format::{ConcreteImageFormat, FormatType, FormatTypeTag, ImageFormatRenderable, Rgba},
-    program::Program,
+    geometry::{GLVec2, NonNormalized, ScalarType, TypeTagSingle},
+    image_format::{
+        ConcreteImageFormat, FormatType, FormatTypeTag, ImageFormat, ImageFormatRenderable, Rgba,
+    },
+    program::DrawProgram,
     uniform::Uniforms,
     vertex::{Index, Vertex, VertexArrayObject},
     ContextState, Handle,
@@ -49,8 +57,8 @@ use std::{
 };
 
 pub(crate) struct FramebufferTargets {
-    read: RawFramebufferTargetRead,
-    draw: RawFramebufferTargetDraw,
+    pub(crate) read: RawFramebufferTargetRead,
+    pub(crate) draw: RawFramebufferTargetDraw,
 }
 
 /// The default back framebuffer.
@@ -90,6 +98,47 @@ where
     pub attachments: A,
 }
 
+/// An attachment point on a framebuffer. Passed to [`Framebuffer::attachment_info`].
+///
+/// [`Framebuffer::attachment_info`]: ./trait.Framebuffer.html#method.attachment_info
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttachmentSlot {
+    /// The color attachment with the given index. For the default framebuffer, `Color(0)` is the
+    /// back buffer.
+    Color(u8),
+    Depth,
+    Stencil,
+}
+
+/// The kind of image attached to an [`AttachmentSlot`].
+///
+/// [`AttachmentSlot`]: ./enum.AttachmentSlot.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttachmentObjectType {
+    /// Nothing is attached to the slot.
+    None,
+    /// One of the default framebuffer's window-system-provided buffers.
+    Default,
+    Renderbuffer,
+    Texture,
+}
+
+/// Information about the image attached to a framebuffer's [`AttachmentSlot`], as reported by
+/// OpenGL. Returned by [`Framebuffer::attachment_info`].
+///
+/// [`AttachmentSlot`]: ./enum.AttachmentSlot.html
+/// [`Framebuffer::attachment_info`]: ./trait.Framebuffer.html#method.attachment_info
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttachmentInfo {
+    pub object_type: AttachmentObjectType,
+    /// The handle of the attached renderbuffer or texture. `None` for all other object types.
+    pub name: Option<Handle>,
+    /// The attached mip level. Always `0` for objects other than textures.
+    pub level: u32,
+    /// Whether the attached image stores its colors in the sRGB color space.
+    pub srgb: bool,
+}
+
 #[doc(hidden)]
 pub struct AttachmentsRefMut<'a, A: 'a + Attachments> {
     attachments: &'a mut A,
@@ -106,6 +155,33 @@ where
     pub fn new(fbo: F, attachments: A) -> FramebufferObjectAttached<A, F> {
         FramebufferObjectAttached { fbo, attachments }
     }
+
+    /// Swaps out the framebuffer's attachments, building the new ones from the old ones.
+    ///
+    /// Only the members whose images changed get reattached, which happens lazily the next time
+    /// the framebuffer is used. This makes ping-ponging between render targets cheap: attach
+    /// `&mut` references to the targets, and swap which target is attached between passes.
+    /// Because the attachments borrow their images, the borrow checker won't let a target get
+    /// sampled from while it's attached. A target is only released once `map` has dropped the
+    /// reference to it, and members that stay attached can be moved from the old attachments to
+    /// the new ones.
+    ///
+    /// ```rust,ignore
+    /// let fbo = fbo.map_attachments(|old| PingPong {
+    ///     depth: old.depth,
+    ///     color: &mut texture_b,
+    /// });
+    /// // `texture_a` can now be sampled from while drawing to `texture_b`.
+    /// ```
+    pub fn map_attachments<B>(self, map: impl FnOnce(A) -> B) -> FramebufferObjectAttached<B, F>
+    where
+        B: Attachments<Static = A::Static>,
+    {
+        FramebufferObjectAttached {
+            fbo: self.fbo,
+            attachments: map(self.attachments),
+        }
+    }
 }
 
 /// Exposes common framebuffer functionality.
@@ -127,11 +203,25 @@ pub trait Framebuffer {
         &ContextState,
     );
 
+    /// Returns the dimensions of the framebuffer's renderable area.
+    ///
+    /// For framebuffer objects, this is the dimensions shared by all attachments, or `None` if
+    /// there aren't any attachments. The default framebuffer's size is controlled by the windowing
+    /// system, so it always returns `None`.
+    ///
+    /// ## Panics
+    /// Panics if the attachments don't all have the same dimensions.
+    fn dims(&self) -> Option<GLVec2<u32, NonNormalized>>;
+
     /// Clears the color of all attached color buffers to `color` to the specified value.
     ///
     /// For the default framebuffer, this clears the window associated with said framebuffer. If you
     /// want to clear an individual color attachment, see [`clear_color_attachment`].
     ///
+    /// Integer attachments get cleared with `color`'s components converted to the attachment's
+    /// integer type, so `1.0` clears an integer attachment to `1`. Only integers that `f32` can
+    /// represent exactly can be used as clear values.
+    ///
     /// [`clear_color_attachment`]: ./struct.FramebufferObjectAttached.html#method.clear_color_attachment
     #[inline]
     fn clear_color_all(&mut self, color: Rgba<f32>) {
@@ -139,9 +229,28 @@ pub trait Framebuffer {
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            arm.attachments.color_attachments(|attachment_index| {
-                framebuffer_bind.clear_color_attachment(color, attachment_index);
-            });
+
+            // `glClear` goes through the cached clear color, but can't clear integer attachments.
+            let mut has_integer_attachment = false;
+            arm.attachments
+                .color_attachments(|_, scalar_type| match scalar_type {
+                    TypeTagSingle::Int | TypeTagSingle::UInt | TypeTagSingle::Bool => {
+                        has_integer_attachment = true
+                    }
+                    _ => (),
+                });
+            if has_integer_attachment {
+                arm.attachments
+                    .color_attachments(|attachment_index, scalar_type| {
+                        framebuffer_bind.clear_color_attachment(
+                            color,
+                            attachment_index,
+                            scalar_type,
+                        );
+                    });
+            } else {
+                framebuffer_bind.clear_color(color, &state.clear_color);
+            }
         }
     }
 
@@ -152,7 +261,7 @@ pub trait Framebuffer {
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            framebuffer_bind.clear_depth(depth);
+            framebuffer_bind.clear_depth(depth, &state.clear_depth);
         }
     }
 
@@ -163,7 +272,99 @@ pub trait Framebuffer {
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            framebuffer_bind.clear_stencil(stencil);
+            framebuffer_bind.clear_stencil(stencil, &state.clear_stencil);
+        }
+    }
+
+    /// Reads stencil values from the framebuffer into `data`.
+    ///
+    /// `read_range` follows the same conventions as [`FramebufferDefault::read_pixels`].
+    ///
+    /// ## Panics
+    /// Panics if `data.len()` doesn't match the number of pixels in `read_range`, or if the
+    /// framebuffer doesn't have a stencil buffer.
+    ///
+    /// [`FramebufferDefault::read_pixels`]: ./struct.FramebufferDefault.html#method.read_pixels
+    #[inline]
+    fn read_stencil<V>(&mut self, read_range: RangeInclusive<V>, data: &mut [u8])
+    where
+        V: Into<GLVec2<u32, NonNormalized>>,
+    {
+        let (raw, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.read.bind(raw, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            let (start, end) = read_range.into_inner();
+            let start: GLVec2<_, _> = start.into();
+            let end: GLVec2<_, _> = end.into();
+            framebuffer_bind.read_stencil(start, end - start, data);
+        }
+    }
+
+    /// Reads packed depth-stencil values from the framebuffer into `data`.
+    ///
+    /// Each value stores the depth as a 24-bit normalized integer in its top 24 bits, and the
+    /// stencil value in its bottom 8 bits. Use [`unpack_depth`] and [`unpack_stencil`] to split
+    /// them apart. Formats with more depth precision get converted to 24 bits.
+    ///
+    /// `read_range` follows the same conventions as [`FramebufferDefault::read_pixels`].
+    ///
+    /// ## Panics
+    /// Panics if `data.len()` doesn't match the number of pixels in `read_range`, or if the
+    /// framebuffer's depth and stencil buffers aren't a single combined depth-stencil image.
+    ///
+    /// [`unpack_depth`]: ./fn.unpack_depth.html
+    /// [`unpack_stencil`]: ./fn.unpack_stencil.html
+    /// [`FramebufferDefault::read_pixels`]: ./struct.FramebufferDefault.html#method.read_pixels
+    #[inline]
+    fn read_depth_stencil<V>(&mut self, read_range: RangeInclusive<V>, data: &mut [u32])
+    where
+        V: Into<GLVec2<u32, NonNormalized>>,
+    {
+        let (raw, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.read.bind(raw, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            let (start, end) = read_range.into_inner();
+            let start: GLVec2<_, _> = start.into();
+            let end: GLVec2<_, _> = end.into();
+            framebuffer_bind.read_depth_stencil(start, end - start, data);
+        }
+    }
+
+    /// Returns the position of sample `index` within each pixel, for multisampled framebuffers.
+    ///
+    /// Positions are in the `0.0..=1.0` range, with `(0.5, 0.5)` at the center of the pixel. This
+    /// is useful for custom multisample resolves and temporal anti-aliasing jitter, which need to
+    /// know exactly where the GPU places each sample.
+    ///
+    /// ## Panics
+    /// Panics if `index` isn't less than the framebuffer's sample count. Framebuffers that aren't
+    /// multisampled have a sample count of `0`, so this always panics for them.
+    #[inline]
+    fn sample_position(&mut self, index: u32) -> [f32; 2] {
+        let (raw_mut, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            framebuffer_bind.sample_position(index)
+        }
+    }
+
+    /// Queries OpenGL for the image attached to `slot`.
+    ///
+    /// This reports what OpenGL actually sees, which makes it useful for checking that a
+    /// framebuffer got assembled as expected.
+    ///
+    /// ## Panics
+    /// Panics if `slot` is a color attachment past the number of attachments the implementation
+    /// supports, or if it's a color attachment other than `Color(0)` on the default framebuffer.
+    fn attachment_info(&mut self, slot: AttachmentSlot) -> AttachmentInfo {
+        let (raw, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.read.bind(raw, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            framebuffer_bind.attachment_info(slot)
         }
     }
 
@@ -177,12 +378,14 @@ pub trait Framebuffer {
     /// * `program`: The compiled program used to render the vertices.
     /// * `uniform`: The uniforms used by the program. If the program has no uniforms, pass `()`.
     /// * `render_state`: The state parameters used to control rendering.
-    fn draw<R, V, I, U>(
+    ///
+    /// Drawing an empty range, or from an empty VAO, is a no-op.
+    fn draw<R, V, I, U, P>(
         &mut self,
         mode: DrawMode,
         range: R,
         vao: &VertexArrayObject<V, I>,
-        program: &Program<V, U::Static, Self::AttachmentsStatic>,
+        program: &P,
         uniforms: &U,
         render_state: &RenderState,
     ) where
@@ -190,8 +393,19 @@ pub trait Framebuffer {
         V: Vertex,
         I: Index,
         U: Uniforms,
+        P: DrawProgram<V, U::Static, Self::AttachmentsStatic>,
     {
-        self.draw_ext(mode, range, vao, program, uniforms, render_state, None, None)
+        self.draw_ext(
+            mode,
+            range,
+            vao,
+            program,
+            uniforms,
+            render_state,
+            None,
+            None,
+            None,
+        )
     }
 
     /// ## Extra parameters
@@ -199,39 +413,110 @@ pub trait Framebuffer {
     ///   https://www.khronos.org/opengl/wiki/Vertex_Rendering#Instancing for more details.
     /// * `base_index`: An offset that gets added to every accessed element in the vertex buffer.
     ///   Useful if multiple meshes are being stored in one buffer.
+    /// * `base_instance`: An offset that gets added to the instance index when fetching instanced
+    ///   vertex attributes. This lets several batches of instances get stored in one buffer
+    ///   without rebinding it for each batch. If `instance_count` is `None`, a single instance gets
+    ///   drawn. Note that the `gl_InstanceID` shader input doesn't include the offset. Shaders
+    ///   can read the offset from `gl_BaseInstance` (or `gl_BaseInstanceARB`) if
+    ///   [`ContextState::shader_draw_parameters_supported`] returns `true`.
+    ///
+    /// ## Panics
+    /// Panics if `base_instance` is `Some` and base instances aren't supported. See
+    /// [`ContextState::base_instance_supported`].
+    ///
+    /// Draws don't return OpenGL errors. The types involved rule out the invalid states OpenGL
+    /// checks for, and calling `glGetError` after every draw would stall the pipeline. Errors from
+    /// attaching the framebuffer's images cause a panic, since they indicate a bug in Gullery.
+    ///
+    /// [`ContextState::base_instance_supported`]: ../struct.ContextState.html#method.base_instance_supported
+    /// [`ContextState::shader_draw_parameters_supported`]: ../struct.ContextState.html#method.shader_draw_parameters_supported
     // TODO: DRAW_EXT IS AN AWFUL NAME
     #[inline]
-    fn draw_ext<R, V, I, U>(
+    #[allow(clippy::too_many_arguments)]
+    fn draw_ext<R, V, I, U, P>(
         &mut self,
         mode: DrawMode,
         range: R,
         vao: &VertexArrayObject<V, I>,
-        program: &Program<V, U::Static, Self::AttachmentsStatic>,
+        program: &P,
         uniforms: &U,
         render_state: &RenderState,
         instance_count: Option<usize>,
         base_index: Option<I>,
+        base_instance: Option<u32>,
     ) where
         R: RangeBounds<usize>,
         V: Vertex,
         I: Index,
         U: Uniforms,
+        P: DrawProgram<V, U::Static, Self::AttachmentsStatic>,
     {
         let (raw_mut, arm, state) = self.raw_mut();
+        if render_state.srgb
+            && render_state.blend != BlendFuncs::default()
+            && Self::Attachments::has_srgb_attachment()
+        {
+            state.srgb_blend_warning.set(true);
+        }
         render_state.upload_state(state);
         unsafe {
             let vao_bind = state.vao_target.bind(vao);
 
-            let program_bind = state.program_target.bind(program);
-            program_bind.upload_uniforms(uniforms);
+            program.bind_for_draw(uniforms);
 
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            framebuffer_bind.draw(mode, range, &vao_bind, &program_bind, instance_count, base_index);
+            framebuffer_bind.draw(
+                mode,
+                range,
+                &vao_bind,
+                instance_count,
+                base_index,
+                base_instance,
+            );
         }
     }
 }
 
+/// Extracts the depth value from a value returned by [`Framebuffer::read_depth_stencil`], as a
+/// float in the `0.0..=1.0` range.
+///
+/// [`Framebuffer::read_depth_stencil`]: ./trait.Framebuffer.html#method.read_depth_stencil
+#[inline]
+pub fn unpack_depth(depth_stencil: u32) -> f32 {
+    ((depth_stencil >> 8) as f64 / 0xFF_FFFF as f64) as f32
+}
+
+/// Extracts the stencil value from a value returned by [`Framebuffer::read_depth_stencil`].
+///
+/// [`Framebuffer::read_depth_stencil`]: ./trait.Framebuffer.html#method.read_depth_stencil
+#[inline]
+pub fn unpack_stencil(depth_stencil: u32) -> u8 {
+    depth_stencil as u8
+}
+
+/// The draw buffers that enable drawing to every color attachment in `A`.
+///
+/// Framebuffers without any color attachments (such as depth-only shadow map framebuffers) don't
+/// draw to any color buffer, which has to be explicitly specified with `GL_NONE`.
+fn all_draw_buffers<A: Attachments>() -> &'static [GLenum] {
+    match A::num_color_attachments() {
+        0 => &[gl::NONE],
+        num_colors => &DRAW_BUFFERS[..num_colors],
+    }
+}
+
+/// `GL_COLOR_ATTACHMENTi` for every attachment index, used to enable drawing to all attachments.
+const DRAW_BUFFERS: [GLenum; 32] = {
+    let mut draw_buffers = [0; 32];
+    let mut i = 0;
+    while i < draw_buffers.len() {
+        draw_buffers[i] = gl::COLOR_ATTACHMENT0 + i as GLenum;
+        i += 1;
+    }
+    draw_buffers
+};
+
 impl FramebufferDefault {
     /// Creates a handle* to the default framebuffer.
     ///
@@ -252,6 +537,20 @@ impl FramebufferDefault {
         }
     }
 
+    /// Returns whether the default framebuffer's back buffer is sRGB-capable, meaning that
+    /// enabling [`RenderState::srgb`] will encode the colors written to it.
+    ///
+    /// Whether the default framebuffer is sRGB-capable gets decided when the context is created,
+    /// and has to be requested through the windowing library (e.g. `with_srgb` in glutin). Even
+    /// then, platforms vary: some drivers always create sRGB-capable framebuffers, while others
+    /// ignore the request. Some drivers also report `false` despite performing the encoding, so
+    /// this is best used as a hint for whether the final image needs manual gamma correction.
+    ///
+    /// [`RenderState::srgb`]: ./render_state/struct.RenderState.html#structfield.srgb
+    pub fn is_srgb_capable(&mut self) -> bool {
+        self.attachment_info(AttachmentSlot::Color(0)).srgb
+    }
+
     /// Reads pixels from the default framebuffer
     #[inline]
     pub fn read_pixels<V, C>(&mut self, read_range: RangeInclusive<V>, data: &mut [C])
@@ -274,13 +573,13 @@ impl FramebufferDefault {
 impl<A: Attachments> FramebufferObject<A> {
     pub fn new(state: Rc<ContextState>) -> FramebufferObject<A> {
         let mut raw = RawFramebufferObject::new(&state.gl);
-        let mut draw_buffers = [0; 32];
-        for (i, db) in draw_buffers.iter_mut().enumerate() {
-            *db = gl::COLOR_ATTACHMENT0 + i as GLenum;
-        }
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(&mut raw, &state.gl);
-            framebuffer_bind.draw_buffers(&draw_buffers[..A::num_members()]);
+            framebuffer_bind.draw_buffers(all_draw_buffers::<A>());
+            if A::num_color_attachments() == 0 {
+                let framebuffer_bind = state.framebuffer_targets.read.bind(&raw, &state.gl);
+                framebuffer_bind.read_none();
+            }
         }
         FramebufferObject {
             raw,
@@ -373,6 +672,138 @@ where
         }
     }
 
+    /// Reads pixels from a multisampled color attachment, resolving its samples first.
+    ///
+    /// Pixels can't be read directly from multisampled images, so this blits `read_range` into a
+    /// temporary single-sampled renderbuffer, then reads the resolved pixels into `data`. This is
+    /// how to take a screenshot of a multisampled render target. `read_range` follows the same
+    /// conventions as [`read_pixels_attachment`]. Single-sampled attachments can be read with this
+    /// too, although it's more expensive than [`read_pixels_attachment`].
+    ///
+    /// ## Panics
+    /// Panics if the attachment isn't one of this framebuffer's color attachments, or if
+    /// `data.len()` doesn't match the number of pixels in `read_range`.
+    ///
+    /// [`read_pixels_attachment`]: ./struct.FramebufferObjectAttached.html#method.read_pixels_attachment
+    pub fn read_resolved<V, C, At>(
+        &mut self,
+        read_range: RangeInclusive<V>,
+        data: &mut [C],
+        get_attachment: impl FnOnce(&<Self as Framebuffer>::Attachments) -> &At,
+    ) where
+        V: Into<GLVec2<u32, NonNormalized>>,
+        C: ImageFormatRenderable + ConcreteImageFormat,
+        At: AttachmentType<Format = C>,
+    {
+        struct ResolveAttachments<C: ImageFormatRenderable> {
+            color: Renderbuffer<C>,
+        }
+        impl<C: ImageFormatRenderable> Attachments for ResolveAttachments<C> {
+            type AHC = [Option<Handle>; 1];
+            type Static = Self;
+            fn members<R>(mut reg: R)
+            where
+                R: AttachmentsMemberRegistry<Attachments = Self>,
+            {
+                reg.add_renderbuffer("color", |a| &a.color);
+            }
+        }
+
+        let color_index = self
+            .map_attachment_to_index(get_attachment(&self.attachments))
+            .expect("Provided attachment not color attachment");
+        let (start, end) = read_range.into_inner();
+        let start: GLVec2<_, _> = start.into();
+        let dims = end.into() - start;
+
+        let state = self.fbo.borrow_mut().state.clone();
+        let mut resolved = FramebufferObjectAttached {
+            fbo: FramebufferObject::new(state.clone()),
+            attachments: ResolveAttachments {
+                color: Renderbuffer::<C>::new(dims, 0, state.clone()),
+            },
+        };
+        {
+            let (raw, arm, state) = self.raw_mut();
+            let (resolved_raw, resolved_arm, _) = resolved.raw_mut();
+            unsafe {
+                let mut read_bind = state.framebuffer_targets.read.bind(raw, &state.gl);
+                read_bind.read_color_attachment(color_index);
+                read_bind.set_attachments(arm.ahc, arm.attachments);
+                let mut draw_bind = state.framebuffer_targets.draw.bind(resolved_raw, &state.gl);
+                draw_bind.set_attachments(resolved_arm.ahc, resolved_arm.attachments);
+                draw_bind.blit_color_from_read(start, dims);
+            }
+        }
+        resolved.read_pixels_attachment(GLVec2::new(0, 0)..=dims, data, |a| &a.color);
+    }
+
+    /// Selects the color attachment that reads from this framebuffer use as their source.
+    ///
+    /// `read_pixels_attachment` selects its own attachment, so this is mainly useful for
+    /// operations that read from whatever the current read buffer is, like blits.
+    ///
+    /// ## Panics
+    /// Panics if the attachment isn't one of this framebuffer's color attachments.
+    pub fn set_read_buffer<At: AttachmentType>(
+        &mut self,
+        get_attachment: impl FnOnce(&<Self as Framebuffer>::Attachments) -> &At,
+    ) {
+        let color_index = self
+            .map_attachment_to_index(get_attachment(&self.attachments))
+            .expect("Provided attachment not color attachment");
+        let (raw, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.read.bind(raw, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            framebuffer_bind.read_color_attachment(color_index);
+        }
+    }
+
+    /// Restricts draws to this framebuffer to a single color attachment.
+    ///
+    /// Fragment shader outputs for every other color attachment get discarded until
+    /// [`reset_draw_buffers`] is called.
+    ///
+    /// ## Panics
+    /// Panics if the attachment isn't one of this framebuffer's color attachments.
+    ///
+    /// [`reset_draw_buffers`]: ./struct.FramebufferObjectAttached.html#method.reset_draw_buffers
+    pub fn set_draw_buffer<At: AttachmentType>(
+        &mut self,
+        get_attachment: impl FnOnce(&<Self as Framebuffer>::Attachments) -> &At,
+    ) {
+        let color_index = self
+            .map_attachment_to_index(get_attachment(&self.attachments))
+            .expect("Provided attachment not color attachment");
+        let (raw_mut, arm, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
+            framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
+            framebuffer_bind.draw_color_attachment(color_index);
+        }
+    }
+
+    /// Re-enables drawing to all color attachments, undoing [`set_draw_buffer`].
+    ///
+    /// [`set_draw_buffer`]: ./struct.FramebufferObjectAttached.html#method.set_draw_buffer
+    pub fn reset_draw_buffers(&mut self) {
+        let (raw_mut, _, state) = self.raw_mut();
+        unsafe {
+            let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
+            framebuffer_bind.draw_buffers(all_draw_buffers::<A>());
+        }
+    }
+
+    /// Clears a single color attachment to `color`.
+    ///
+    /// Integer attachments get cleared with `color`'s components converted to integers, as in
+    /// [`Framebuffer::clear_color_all`].
+    ///
+    /// ## Panics
+    /// Panics if the attachment isn't one of this framebuffer's color attachments.
+    ///
+    /// [`Framebuffer::clear_color_all`]: ./trait.Framebuffer.html#method.clear_color_all
     pub fn clear_color_attachment<At: AttachmentType>(
         &mut self,
         color: Rgba<f32>,
@@ -381,11 +812,12 @@ where
         let color_index = self
             .map_attachment_to_index(get_attachment(&self.attachments))
             .expect("Provided attachment not color attachment");
+        let scalar_type = <<At::Format as ImageFormat>::ScalarType as ScalarType>::PRIM_TAG;
         let (raw_mut, arm, state) = self.raw_mut();
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            framebuffer_bind.clear_color_attachment(color, color_index);
+            framebuffer_bind.clear_color_attachment(color, color_index, scalar_type);
         }
     }
 }
@@ -414,11 +846,9 @@ impl FramebufferTargets {
     }
 
     unsafe fn unbind<F: RawFramebuffer>(&self, buffer: &F, gl: &Gl) {
-        if self.read.bound_buffer().get() == buffer.handle() {
-            self.read.reset_bind(gl);
-        }
-        if self.draw.bound_buffer().get() == buffer.handle() {
-            self.draw.reset_bind(gl);
+        if let Some(handle) = buffer.handle() {
+            self.read.unbind_handle(handle, gl);
+            self.draw.unbind_handle(handle, gl);
         }
     }
 }
@@ -432,6 +862,10 @@ impl Framebuffer for FramebufferDefault {
         (&self.raw, &self.state)
     }
     #[inline]
+    fn dims(&self) -> Option<GLVec2<u32, NonNormalized>> {
+        None
+    }
+    #[inline]
     fn raw_mut(&mut self) -> (&mut Self::Raw, AttachmentsRefMut<()>, &ContextState) {
         static mut EMPTY: () = ();
         (
@@ -450,7 +884,7 @@ impl Framebuffer for FramebufferDefault {
         unsafe {
             let mut framebuffer_bind = state.framebuffer_targets.draw.bind(raw_mut, &state.gl);
             framebuffer_bind.set_attachments(arm.ahc, arm.attachments);
-            framebuffer_bind.clear_color_attachment(color, 0);
+            framebuffer_bind.clear_color(color, &state.clear_color);
         }
     }
 }
@@ -469,6 +903,10 @@ where
         (&self.fbo.borrow().raw, &self.fbo.borrow().state)
     }
     #[inline]
+    fn dims(&self) -> Option<GLVec2<u32, NonNormalized>> {
+        self.attachments.dims()
+    }
+    #[inline]
     fn raw_mut(&mut self) -> (&mut Self::Raw, AttachmentsRefMut<A>, &ContextState) {
         let fbo = self.fbo.borrow_mut();
         (
@@ -481,3 +919,957 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::{Buffer, BufferUsage},
+        framebuffer::render_state::{BlendFunc, DepthStencilFunc},
+        geometry::{GLVec3, D2, D3},
+        image_format::{Depth32F, Depth32FStencil8, SRgba, Stencil8},
+        program::{Program, ProgramPipeline, SeparableProgram, Shader},
+        test_helper::{TestVertex, CONTEXT_STATE},
+        texture::Texture,
+        GLObject,
+    };
+
+    const VERTEX_SHADER: &str = r#"
+        #version 330
+
+        in vec2 pos;
+        in vec3 color;
+
+        out vec3 vertex_color;
+
+        void main() {
+            gl_Position = vec4(pos, 0.0, 1.0);
+            vertex_color = color;
+        }
+    "#;
+
+    const FRAGMENT_SHADER: &str = r#"
+        #version 330
+
+        in vec3 vertex_color;
+
+        out vec4 color;
+
+        void main() {
+            color = vec4(vertex_color, 1.0);
+        }
+    "#;
+
+    struct DepthStencilAttachments {
+        color: Renderbuffer<Rgba<u8>>,
+        depth_stencil: Renderbuffer<Depth32FStencil8>,
+    }
+
+    impl Attachments for DepthStencilAttachments {
+        type AHC = [Option<Handle>; 2];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("color", |a| &a.color);
+            reg.add_renderbuffer("depth_stencil", |a| &a.depth_stencil);
+        }
+    }
+
+    struct DepthOnlyAttachments {
+        depth: Renderbuffer<Depth32F>,
+    }
+
+    impl Attachments for DepthOnlyAttachments {
+        type AHC = [Option<Handle>; 1];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("depth", |a| &a.depth);
+        }
+    }
+
+    struct StencilAttachments {
+        color: Renderbuffer<Rgba<u8>>,
+        stencil: Renderbuffer<Stencil8>,
+    }
+
+    impl Attachments for StencilAttachments {
+        type AHC = [Option<Handle>; 2];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("color", |a| &a.color);
+            reg.add_renderbuffer("stencil", |a| &a.stencil);
+        }
+    }
+
+    struct ColorAttachments {
+        color: Renderbuffer<Rgba<u8>>,
+    }
+
+    impl Attachments for ColorAttachments {
+        type AHC = [Option<Handle>; 1];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("color", |a| &a.color);
+        }
+    }
+
+    struct RefAttachments<'a> {
+        color: &'a mut Renderbuffer<Rgba<u8>>,
+        depth: &'a mut Renderbuffer<Depth32F>,
+    }
+
+    impl<'a> Attachments for RefAttachments<'a> {
+        type AHC = [Option<Handle>; 2];
+        type Static = RefAttachments<'static>;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            <&mut Renderbuffer<_> as AttachmentType>::add_to_registry(
+                &mut reg,
+                "color",
+                |a| AttachmentType::resolve(&a.color),
+                (),
+            );
+            <&mut Renderbuffer<_> as AttachmentType>::add_to_registry(
+                &mut reg,
+                "depth",
+                |a| AttachmentType::resolve(&a.depth),
+                (),
+            );
+        }
+    }
+
+    struct PingPongAttachments<'a, 'b> {
+        stable: &'a mut Renderbuffer<Rgba<u8>>,
+        target: &'b mut Renderbuffer<Rgba<u8>>,
+    }
+
+    impl<'a, 'b> Attachments for PingPongAttachments<'a, 'b> {
+        type AHC = [Option<Handle>; 2];
+        type Static = PingPongAttachments<'static, 'static>;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            <&mut Renderbuffer<_> as AttachmentType>::add_to_registry(
+                &mut reg,
+                "stable",
+                |a| AttachmentType::resolve(&a.stable),
+                (),
+            );
+            <&mut Renderbuffer<_> as AttachmentType>::add_to_registry(
+                &mut reg,
+                "target",
+                |a| AttachmentType::resolve(&a.target),
+                (),
+            );
+        }
+    }
+
+    struct TextureAttachments {
+        color: Texture<D2, SRgba>,
+        depth: Renderbuffer<Depth32F>,
+    }
+
+    impl Attachments for TextureAttachments {
+        type AHC = [Option<Handle>; 2];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_texture("color", |a| &a.color, 1);
+            reg.add_renderbuffer("depth", |a| &a.depth);
+        }
+    }
+
+    #[test]
+    fn attachment_info() {
+        CONTEXT_STATE.with(|state| {
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: TextureAttachments {
+                    color: Texture::new_uninitialized(GLVec2::new(64, 64), 2, state.clone())
+                        .unwrap(),
+                    depth: Renderbuffer::new(GLVec2::new(32, 32), 0, state.clone()),
+                },
+            };
+            let (color_handle, depth_handle) = (
+                fbo.attachments.color.handle(),
+                fbo.attachments.depth.handle(),
+            );
+
+            assert_eq!(
+                AttachmentInfo {
+                    object_type: AttachmentObjectType::Texture,
+                    name: Some(color_handle),
+                    level: 1,
+                    srgb: true,
+                },
+                fbo.attachment_info(AttachmentSlot::Color(0))
+            );
+            assert_eq!(
+                AttachmentInfo {
+                    object_type: AttachmentObjectType::Renderbuffer,
+                    name: Some(depth_handle),
+                    level: 0,
+                    srgb: false,
+                },
+                fbo.attachment_info(AttachmentSlot::Depth)
+            );
+            let none = AttachmentInfo {
+                object_type: AttachmentObjectType::None,
+                name: None,
+                level: 0,
+                srgb: false,
+            };
+            assert_eq!(none, fbo.attachment_info(AttachmentSlot::Color(1)));
+            assert_eq!(none, fbo.attachment_info(AttachmentSlot::Stencil));
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    struct SliceAttachments {
+        volume: Texture<D3, Rgba<u8>>,
+    }
+
+    impl Attachments for SliceAttachments {
+        type AHC = [Option<Handle>; 1];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_texture_3d_slice("volume", |a| &a.volume, 2, 0);
+        }
+    }
+
+    #[test]
+    fn texture_3d_slice() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec3::new(4, 4, 4);
+            let black = vec![Rgba::new(0, 0, 0, 0); 64];
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: SliceAttachments {
+                    volume: Texture::with_image(dims, &black[..], state.clone()).unwrap(),
+                },
+            };
+            assert_eq!(Some(GLVec2::new(4, 4)), fbo.dims());
+            fbo.clear_color_all(Rgba::new(1.0, 1.0, 1.0, 1.0));
+
+            let mut pixels = black.clone();
+            fbo.attachments.volume.get_image(0, &mut pixels);
+            for (i, pixel) in pixels.iter().enumerate() {
+                let expected = match i / 16 {
+                    2 => Rgba::new(255, 255, 255, 255),
+                    _ => Rgba::new(0, 0, 0, 0),
+                };
+                assert_eq!(expected, *pixel);
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    struct MixedSampleAttachments {
+        msaa: Renderbuffer<Rgba<u8>>,
+        texture: Texture<D2, Rgba<u8>>,
+    }
+
+    impl Attachments for MixedSampleAttachments {
+        type AHC = [Option<Handle>; 2];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("msaa", |a| &a.msaa);
+            reg.add_texture("texture", |a| &a.texture, 0);
+        }
+    }
+
+    #[test]
+    fn sample_count_mismatch() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(16, 16);
+            let attachments = MixedSampleAttachments {
+                msaa: Renderbuffer::new(dims, 4, state.clone()),
+                texture: Texture::new_uninitialized(dims, 1, state.clone()).unwrap(),
+            };
+            assert_eq!(
+                Err(AttachmentsError::SamplesMismatch {
+                    attachment: "texture".to_string(),
+                    samples: 0,
+                    expected: 4,
+                }),
+                attachments.check_complete()
+            );
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    struct IntegerAttachments {
+        color: Renderbuffer<Rgba<u32, NonNormalized>>,
+    }
+
+    impl Attachments for IntegerAttachments {
+        type AHC = [Option<Handle>; 1];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("color", |a| &a.color);
+        }
+    }
+
+    #[test]
+    fn clear_integer_attachment() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(4, 4);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: IntegerAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            fbo.clear_color_all(Rgba::new(1.0, 2.0, 3.0, 4.0));
+
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 16];
+            fbo.read_pixels_attachment(GLVec2::new(0, 0)..=dims, &mut pixels, |a| &a.color);
+            for pixel in &pixels {
+                assert_eq!(Rgba::new(1, 2, 3, 4), *pixel);
+            }
+
+            fbo.clear_color_attachment(Rgba::new(5.0, 6.0, 7.0, 8.0), |a| &a.color);
+            fbo.read_pixels_attachment(GLVec2::new(0, 0)..=dims, &mut pixels, |a| &a.color);
+            for pixel in &pixels {
+                assert_eq!(Rgba::new(5, 6, 7, 8), *pixel);
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn depth32f_stencil8_complete() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(64, 64);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: DepthStencilAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                    depth_stencil: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            fbo.clear_depth(1.0);
+            fbo.clear_stencil(0);
+
+            let status = unsafe { state.gl.CheckFramebufferStatus(gl::DRAW_FRAMEBUFFER) };
+            assert_eq!(gl::FRAMEBUFFER_COMPLETE, status);
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn draw_empty_mesh() {
+        CONTEXT_STATE.with(|state| {
+            let vertex_shader = Shader::new(VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) = Program::<TestVertex, (), DepthStencilAttachments>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+
+            let dims = GLVec2::new(64, 64);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: DepthStencilAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                    depth_stencil: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            let render_state = RenderState::default();
+
+            let vertices: Buffer<TestVertex> =
+                Buffer::with_data(BufferUsage::StaticDraw, &[], state.clone());
+            let vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertices, None);
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+            fbo.draw_ext(
+                DrawMode::Triangles,
+                ..,
+                &vao,
+                &program,
+                &(),
+                &render_state,
+                Some(4),
+                None,
+                None,
+            );
+
+            let vertices: Buffer<TestVertex> =
+                Buffer::with_data(BufferUsage::StaticDraw, &[], state.clone());
+            let indices: Buffer<u16> =
+                Buffer::with_data(BufferUsage::StaticDraw, &[], state.clone());
+            let vao = VertexArrayObject::new(vertices, Some(indices));
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+            fbo.draw(DrawMode::Triangles, 0..0, &vao, &program, &(), &render_state);
+
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn srgb_blend_warning() {
+        CONTEXT_STATE.with(|state| {
+            let vertex_shader = Shader::new(VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) = Program::<TestVertex, (), TextureAttachments>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+
+            let dims = GLVec2::new(16, 16);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: TextureAttachments {
+                    color: Texture::new_uninitialized(dims, 1, state.clone()).unwrap(),
+                    depth: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            let vertices: Buffer<TestVertex> =
+                Buffer::with_data(BufferUsage::StaticDraw, &[], state.clone());
+            let vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertices, None);
+
+            let mut render_state = RenderState {
+                srgb: true,
+                ..RenderState::default()
+            };
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+            assert!(!state.srgb_blend_warning());
+
+            render_state.blend = BlendFuncs {
+                src_rgb: BlendFunc::SrcAlpha,
+                dst_rgb: BlendFunc::OneMinusSrcAlpha,
+                ..BlendFuncs::default()
+            };
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+            assert!(state.srgb_blend_warning());
+            assert!(!state.srgb_blend_warning());
+        })
+    }
+
+    #[test]
+    fn draw_without_attributes() {
+        CONTEXT_STATE.with(|state| {
+            const FULLSCREEN_VERTEX_SHADER: &str = r#"
+                #version 330
+
+                void main() {
+                    vec2 pos = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+                    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+                }
+            "#;
+            const RED_FRAGMENT_SHADER: &str = r#"
+                #version 330
+
+                out vec4 color;
+
+                void main() {
+                    color = vec4(1.0, 0.0, 0.0, 1.0);
+                }
+            "#;
+
+            let vertex_shader = Shader::new(FULLSCREEN_VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(RED_FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) =
+                Program::<(), (), ColorAttachments>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+
+            let dims = GLVec2::new(16, 16);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: ColorAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            fbo.clear_color_all(Rgba::new(0.0, 0.0, 0.0, 1.0));
+
+            let render_state = RenderState {
+                viewport: GLVec2::new(0, 0)..=dims,
+                ..RenderState::default()
+            };
+            let vertices: Buffer<()> = Buffer::with_size(BufferUsage::StaticDraw, 3, state.clone());
+            assert_eq!(3, vertices.len());
+            let vao: VertexArrayObject<(), !> = VertexArrayObject::new(vertices, None);
+            fbo.draw(DrawMode::Triangles, 0..3, &vao, &program, &(), &render_state);
+
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 16 * 16];
+            fbo.read_pixels_attachment(GLVec2::new(0, 0)..=dims, &mut pixels, |a| &a.color);
+            for pixel in pixels {
+                assert_eq!(Rgba::new(255, 0, 0, 255), pixel);
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn draw_depth_only() {
+        CONTEXT_STATE.with(|state| {
+            const DEPTH_FRAGMENT_SHADER: &str = r#"
+                #version 330
+
+                void main() {}
+            "#;
+
+            let vertex_shader = Shader::new(VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(DEPTH_FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) = Program::<TestVertex, (), DepthOnlyAttachments>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+
+            let dims = GLVec2::new(64, 64);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: DepthOnlyAttachments {
+                    depth: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            fbo.clear_depth(1.0);
+
+            let status = unsafe { state.gl.CheckFramebufferStatus(gl::DRAW_FRAMEBUFFER) };
+            assert_eq!(gl::FRAMEBUFFER_COMPLETE, status);
+
+            let render_state = RenderState {
+                depth_test: Some(DepthStencilFunc::Less),
+                viewport: GLVec2::new(0, 0)..=dims,
+                ..RenderState::default()
+            };
+            let vertex = |x, y| TestVertex {
+                pos: GLVec2::new(x, y),
+                color: GLVec3::new(0.0, 0.0, 0.0),
+            };
+            let vertices = Buffer::with_data(
+                BufferUsage::StaticDraw,
+                &[vertex(-1.0, -1.0), vertex(3.0, -1.0), vertex(-1.0, 3.0)],
+                state.clone(),
+            );
+            let vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertices, None);
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+
+            assert_eq!(fbo.fbo.raw.handle(), state.currently_bound_framebuffer());
+            assert_eq!(Some(program.handle()), state.currently_bound_program());
+            assert_eq!(Some(vao.handle()), state.currently_bound_vao());
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn draw_pipeline() {
+        CONTEXT_STATE.with(|state| {
+            const SEPARABLE_VERTEX_SHADER: &str = r#"
+                #version 330
+                #extension GL_ARB_separate_shader_objects : require
+
+                in vec2 pos;
+                in vec3 color;
+
+                out gl_PerVertex {
+                    vec4 gl_Position;
+                };
+                layout(location = 0) out vec3 vertex_color;
+
+                void main() {
+                    gl_Position = vec4(pos, 0.0, 1.0);
+                    vertex_color = color;
+                }
+            "#;
+            const SEPARABLE_FRAGMENT_SHADER: &str = r#"
+                #version 330
+                #extension GL_ARB_separate_shader_objects : require
+
+                layout(location = 0) in vec3 vertex_color;
+
+                out vec4 color;
+
+                void main() {
+                    color = vec4(vertex_color, 1.0);
+                }
+            "#;
+            const INVERT_FRAGMENT_SHADER: &str = r#"
+                #version 330
+                #extension GL_ARB_separate_shader_objects : require
+
+                layout(location = 0) in vec3 vertex_color;
+
+                out vec4 color;
+
+                void main() {
+                    color = vec4(1.0 - vertex_color, 1.0);
+                }
+            "#;
+
+            if !state.supports_extension("GL_ARB_separate_shader_objects") {
+                return;
+            }
+
+            let vertex_shader = Shader::new(SEPARABLE_VERTEX_SHADER, state.clone()).unwrap();
+            let vertex_program = Rc::new(SeparableProgram::new(&vertex_shader).unwrap().0);
+            let pipelines = [SEPARABLE_FRAGMENT_SHADER, INVERT_FRAGMENT_SHADER]
+                .iter()
+                .map(|source| {
+                    let fragment_shader = Shader::new(source, state.clone()).unwrap();
+                    let fragment_program = SeparableProgram::new(&fragment_shader).unwrap().0;
+                    ProgramPipeline::<TestVertex, (), ColorAttachments>::new(
+                        vertex_program.clone(),
+                        None,
+                        Rc::new(fragment_program),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let dims = GLVec2::new(16, 16);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: ColorAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            let render_state = RenderState {
+                viewport: GLVec2::new(0, 0)..=dims,
+                ..RenderState::default()
+            };
+            let vertex = |x, y| TestVertex {
+                pos: GLVec2::new(x, y),
+                color: GLVec3::new(1.0, 0.0, 0.0),
+            };
+            let vertices = Buffer::with_data(
+                BufferUsage::StaticDraw,
+                &[vertex(-1.0, -1.0), vertex(3.0, -1.0), vertex(-1.0, 3.0)],
+                state.clone(),
+            );
+            let vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertices, None);
+
+            for (pipeline, expected) in pipelines
+                .iter()
+                .zip(&[Rgba::new(255, 0, 0, 255), Rgba::new(0, 255, 255, 255)])
+            {
+                fbo.draw(DrawMode::Triangles, .., &vao, pipeline, &(), &render_state);
+                assert_eq!(None, state.currently_bound_program());
+
+                let mut pixels = vec![Rgba::new(0, 0, 0, 0); 4];
+                fbo.read_pixels_attachment(
+                    GLVec2::new(7, 7)..=GLVec2::new(9, 9),
+                    &mut pixels,
+                    |a| &a.color,
+                );
+                for pixel in pixels {
+                    assert_eq!(*expected, pixel);
+                }
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn ref_attachments() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(64, 64);
+            let mut color = Renderbuffer::new(dims, 0, state.clone());
+            let mut depth = Renderbuffer::new(dims, 0, state.clone());
+            let (color_handle, depth_handle) = (color.handle(), depth.handle());
+            let color_ptr = &color as *const Renderbuffer<Rgba<u8>> as *const ();
+
+            let attachments = RefAttachments {
+                color: &mut color,
+                depth: &mut depth,
+            };
+            assert_eq!(2, RefAttachments::num_members());
+            assert_eq!(1, RefAttachments::num_color_attachments());
+            assert_eq!(color_ptr, attachments.color.resolve_reference());
+            assert_eq!(color_ptr, (&attachments.color).resolve_reference());
+
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments,
+            };
+            fbo.set_draw_buffer(|a| &a.color);
+            fbo.clear_color_all(Rgba::new(1.0, 0.0, 0.0, 1.0));
+            fbo.clear_depth(1.0);
+
+            let status = unsafe { state.gl.CheckFramebufferStatus(gl::DRAW_FRAMEBUFFER) };
+            assert_eq!(gl::FRAMEBUFFER_COMPLETE, status);
+            assert_eq!(&[Some(color_handle), Some(depth_handle)], &fbo.fbo.handles);
+            assert_eq!(Ok(()), state.check_error());
+
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 4];
+            fbo.read_pixels_attachment(
+                GLVec2::new(7, 7)..=GLVec2::new(9, 9),
+                &mut pixels,
+                |a| &a.color,
+            );
+            assert!(pixels.iter().all(|&p| p == Rgba::new(255, 0, 0, 255)));
+
+            let FramebufferObjectAttached { attachments, .. } = fbo;
+            assert_eq!(color_handle, attachments.color.handle());
+            assert_eq!(
+                color_ptr,
+                attachments.color.resolve() as *const _ as *const ()
+            );
+
+            // The borrows end with the framebuffer, so the originals can be moved out again.
+            drop((color, depth));
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn ref_attachments_after_map() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(16, 16);
+            let mut stable = Renderbuffer::new(dims, 0, state.clone());
+            let mut target_a = Renderbuffer::new(dims, 0, state.clone());
+            let mut target_b = Renderbuffer::new(dims, 0, state.clone());
+            let read_range = GLVec2::new(7, 7)..=GLVec2::new(9, 9);
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 4];
+
+            let mut fbo = FramebufferObjectAttached::new(
+                FramebufferObject::new(state.clone()),
+                PingPongAttachments {
+                    stable: &mut stable,
+                    target: &mut target_a,
+                },
+            );
+            fbo.clear_color_all(Rgba::new(1.0, 0.0, 0.0, 1.0));
+
+            let mut fbo = fbo.map_attachments(|old| PingPongAttachments {
+                stable: old.stable,
+                target: &mut target_b,
+            });
+            fbo.clear_color_all(Rgba::new(0.0, 1.0, 0.0, 1.0));
+            fbo.read_pixels_attachment(read_range.clone(), &mut pixels, |a| &a.target);
+            assert!(pixels.iter().all(|&p| p == Rgba::new(0, 255, 0, 255)));
+
+            // Mapping released the borrow of `target_a`, so it can be borrowed again, and it
+            // kept the contents it was cleared to.
+            let mut fbo = fbo.map_attachments(|old| PingPongAttachments {
+                stable: old.stable,
+                target: &mut target_a,
+            });
+            fbo.read_pixels_attachment(read_range, &mut pixels, |a| &a.target);
+            assert!(pixels.iter().all(|&p| p == Rgba::new(255, 0, 0, 255)));
+            assert_eq!(Ok(()), state.check_error());
+
+            drop(fbo);
+            drop((stable, target_a, target_b));
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn cached_clear_values() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(8, 8);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: DepthStencilAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                    depth_stencil: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            let read_range = GLVec2::new(2, 2)..=GLVec2::new(6, 6);
+
+            for &(color, depth, stencil) in &[
+                (Rgba::new(1.0, 0.0, 0.0, 1.0), 0.5, 0x5A),
+                (Rgba::new(1.0, 0.0, 0.0, 1.0), 0.5, 0x5A),
+                (Rgba::new(0.0, 0.0, 1.0, 1.0), 0.25, 0x0F),
+            ] {
+                fbo.clear_color_all(color);
+                fbo.clear_depth(depth);
+                fbo.clear_stencil(stencil);
+                assert_eq!(color, state.clear_color.get());
+                assert_eq!(depth, state.clear_depth.get());
+                assert_eq!(stencil as GLint, state.clear_stencil.get());
+
+                let mut gl_color = [0.0; 4];
+                let (mut gl_depth, mut gl_stencil) = (0.0, 0);
+                unsafe {
+                    let gl = &state.gl;
+                    gl.GetFloatv(gl::COLOR_CLEAR_VALUE, gl_color.as_mut_ptr());
+                    gl.GetFloatv(gl::DEPTH_CLEAR_VALUE, &mut gl_depth);
+                    gl.GetIntegerv(gl::STENCIL_CLEAR_VALUE, &mut gl_stencil);
+                }
+                assert_eq!([color.r, color.g, color.b, color.a], gl_color);
+                assert_eq!(depth, gl_depth);
+                assert_eq!(stencil as GLint, gl_stencil);
+
+                let mut pixels = vec![Rgba::new(0, 0, 0, 0); 4 * 4];
+                fbo.read_pixels_attachment(read_range.clone(), &mut pixels, |a| &a.color);
+                let expected = Rgba::new(
+                    (color.r * 255.0) as u8,
+                    (color.g * 255.0) as u8,
+                    (color.b * 255.0) as u8,
+                    (color.a * 255.0) as u8,
+                );
+                assert!(pixels.iter().all(|&p| p == expected));
+
+                let mut data = vec![0; 4 * 4];
+                fbo.read_depth_stencil(read_range.clone(), &mut data);
+                for value in data {
+                    assert!((unpack_depth(value) - depth).abs() < 1.0e-6);
+                    assert_eq!(stencil as u8, unpack_stencil(value));
+                }
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn read_depth_stencil() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(8, 8);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: DepthStencilAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                    depth_stencil: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            fbo.clear_depth(0.5);
+            fbo.clear_stencil(0x5A);
+
+            let mut data = vec![0; 4 * 4];
+            fbo.read_depth_stencil(GLVec2::new(2, 2)..=GLVec2::new(6, 6), &mut data);
+            for value in data {
+                assert!((unpack_depth(value) - 0.5).abs() < 1.0e-6);
+                assert_eq!(0x5A, unpack_stencil(value));
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn stencil_only_attachment() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(8, 8);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: StencilAttachments {
+                    color: Renderbuffer::new(dims, 0, state.clone()),
+                    stencil: Renderbuffer::new(dims, 0, state.clone()),
+                },
+            };
+            assert_eq!(Ok(()), state.check_error());
+            fbo.clear_stencil(0x5A);
+
+            let mut data = vec![0; 4 * 4];
+            fbo.read_stencil(GLVec2::new(2, 2)..=GLVec2::new(6, 6), &mut data);
+            assert!(data.iter().all(|&stencil| stencil == 0x5A));
+            assert_eq!(
+                AttachmentObjectType::Renderbuffer,
+                fbo.attachment_info(AttachmentSlot::Stencil).object_type
+            );
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn map_attachments() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(16, 16);
+            let mut stable = Renderbuffer::new(dims, 0, state.clone());
+            let mut target_a = Renderbuffer::new(dims, 0, state.clone());
+            let mut target_b = Renderbuffer::new(dims, 0, state.clone());
+            let stable_handle = stable.handle();
+            let (a_handle, b_handle) = (target_a.handle(), target_b.handle());
+
+            let mut fbo = FramebufferObjectAttached::new(
+                FramebufferObject::new(state.clone()),
+                PingPongAttachments {
+                    stable: &mut stable,
+                    target: &mut target_a,
+                },
+            );
+            fbo.clear_color_all(Rgba::new(1.0, 0.0, 0.0, 1.0));
+
+            let mut fbo = fbo.map_attachments(|old| PingPongAttachments {
+                stable: old.stable,
+                target: &mut target_b,
+            });
+            fbo.clear_color_all(Rgba::new(0.0, 1.0, 0.0, 1.0));
+            let stable_info = fbo.attachment_info(AttachmentSlot::Color(0));
+            let target_info = fbo.attachment_info(AttachmentSlot::Color(1));
+            assert_eq!(Some(stable_handle), stable_info.name);
+            assert_eq!(Some(b_handle), target_info.name);
+
+            // `target_a` got detached when it was swapped out.
+            for i in 0..2 {
+                let info = fbo.attachment_info(AttachmentSlot::Color(i));
+                assert_ne!(Some(a_handle), info.name);
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn read_resolved_multisample() {
+        CONTEXT_STATE.with(|state| {
+            let dims = GLVec2::new(16, 16);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: ColorAttachments {
+                    color: Renderbuffer::new(dims, 4, state.clone()),
+                },
+            };
+            fbo.clear_color_all(Rgba::new(1.0, 0.0, 0.0, 1.0));
+
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 8 * 4];
+            fbo.read_resolved(
+                GLVec2::new(4, 2)..=GLVec2::new(12, 6),
+                &mut pixels,
+                |a| &a.color,
+            );
+            for pixel in pixels {
+                assert_eq!(Rgba::new(255, 0, 0, 255), pixel);
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn sample_positions() {
+        CONTEXT_STATE.with(|state| {
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(state.clone()),
+                attachments: ColorAttachments {
+                    color: Renderbuffer::new(GLVec2::new(16, 16), 4, state.clone()),
+                },
+            };
+            let samples = fbo.attachments.color.samples();
+            for index in 0..samples {
+                let [x, y] = fbo.sample_position(index);
+                assert!((0.0..=1.0).contains(&x));
+                assert!((0.0..=1.0).contains(&y));
+            }
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+}
@@ -12,18 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{attachments::*, Renderbuffer};
+use super::{attachments::*, AttachmentInfo, AttachmentObjectType, AttachmentSlot, Renderbuffer};
 use crate::{
+    bind::Bind,
+    error::ErrorScope,
     geometry::Dimension,
     gl::{self, types::*, Gl},
-    geometry::{GLVec2, NonNormalized},
+    geometry::{GLVec2, NonNormalized, TypeTagSingle, D3},
     image_format::{
         ConcreteImageFormat, FormatAttributes, FormatType, FormatTypeTag, ImageFormatRenderable,
         Rgba,
     },
-    program::BoundProgram,
     texture::{MipSelector, Texture, TextureType},
-    uniform::Uniforms,
     vertex::{vao::BoundVAO, Index, Vertex},
     ContextState, GLObject, Handle,
 };
@@ -63,6 +63,10 @@ pub struct RawFramebufferTargetDraw {
 
 /// The primitive rendering mode for the `draw` call. See [here](https://www.khronos.org/opengl/wiki/Primitive)
 /// for more information.
+///
+/// The adjacency modes provide extra vertices adjacent to each primitive, which are only visible
+/// to a geometry shader. Drawing with them is allowed without a geometry shader, but the adjacent
+/// vertices get discarded.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u32)]
 pub enum DrawMode {
@@ -70,14 +74,25 @@ pub enum DrawMode {
     LineStrip = gl::LINE_STRIP,
     LineLoop = gl::LINE_LOOP,
     Lines = gl::LINES,
+    /// Requires a geometry shader to make use of the adjacent vertices.
     LineStripAdjacency = gl::LINE_STRIP_ADJACENCY,
+    /// Requires a geometry shader to make use of the adjacent vertices.
     LinesAdjacency = gl::LINES_ADJACENCY,
     TriangleStrip = gl::TRIANGLE_STRIP,
     TriangleFan = gl::TRIANGLE_FAN,
     Triangles = gl::TRIANGLES,
+    /// Requires a geometry shader to make use of the adjacent vertices.
     TriangleStripAdjacency = gl::TRIANGLE_STRIP_ADJACENCY,
-    TrianglesAdjacency = gl::TRIANGLES_ADJACENCY, // We don't support patches because that's an OpenGL 4 feature.
-                                                  // Patches
+    /// Requires a geometry shader to make use of the adjacent vertices.
+    TrianglesAdjacency = gl::TRIANGLES_ADJACENCY,
+    /// Groups of vertices processed by tessellation shaders. The number of vertices in each patch
+    /// is set with [`ContextState::set_patch_vertices`].
+    ///
+    /// Requires OpenGL 4.0 or `GL_ARB_tessellation_shader`. Drawing patches panics if they
+    /// aren't supported, and is an OpenGL error if the program has no tessellation stage.
+    ///
+    /// [`ContextState::set_patch_vertices`]: ../struct.ContextState.html#method.set_patch_vertices
+    Patches = gl::PATCHES,
 }
 
 pub struct RawBoundFramebufferRead<'a, F>
@@ -85,6 +100,7 @@ where
     F: 'a + RawFramebuffer,
 {
     _fb: PhantomData<&'a F>,
+    is_default: bool,
     gl: &'a Gl,
 }
 
@@ -134,29 +150,27 @@ impl RawFramebufferTargetRead {
     where
         F: RawFramebuffer,
     {
-        if self.bound_fb.get() != framebuffer.handle() {
-            self.bound_fb.set(framebuffer.handle());
-            gl.BindFramebuffer(
-                gl::READ_FRAMEBUFFER,
-                framebuffer.handle().map(|h| h.get()).unwrap_or(0),
-            );
-        }
+        self.bind_handle(framebuffer.handle(), gl);
 
         RawBoundFramebufferRead {
             _fb: PhantomData,
+            is_default: framebuffer.handle().is_none(),
             gl,
         }
     }
+}
+
+impl Bind for RawFramebufferTargetRead {
+    const BINDING: GLenum = gl::READ_FRAMEBUFFER_BINDING;
 
     #[inline]
-    pub unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_fb.set(None);
-        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_fb
     }
 
     #[inline]
-    pub fn bound_buffer(&self) -> &Cell<Option<Handle>> {
-        &self.bound_fb
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, handle.map(|h| h.get()).unwrap_or(0));
     }
 }
 
@@ -177,29 +191,26 @@ impl RawFramebufferTargetDraw {
     where
         F: RawFramebuffer,
     {
-        if self.bound_fb.get() != framebuffer.handle() {
-            self.bound_fb.set(framebuffer.handle());
-            gl.BindFramebuffer(
-                gl::DRAW_FRAMEBUFFER,
-                framebuffer.handle().map(|h| h.get()).unwrap_or(0),
-            );
-        }
+        self.bind_handle(framebuffer.handle(), gl);
 
         RawBoundFramebufferDraw {
             _fb: PhantomData,
             gl,
         }
     }
+}
+
+impl Bind for RawFramebufferTargetDraw {
+    const BINDING: GLenum = gl::DRAW_FRAMEBUFFER_BINDING;
 
     #[inline]
-    pub unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_fb.set(None);
-        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_fb
     }
 
     #[inline]
-    pub fn bound_buffer(&self) -> &Cell<Option<Handle>> {
-        &self.bound_fb
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, handle.map(|h| h.get()).unwrap_or(0));
     }
 }
 
@@ -209,10 +220,90 @@ where
 {
     pub(crate) fn read_color_attachment(&self, attachment_index: u8) {
         assert!(attachment_index < 32);
+        let _error_scope = ErrorScope::new(self.gl, "glReadBuffer");
         unsafe {
             self.gl
                 .ReadBuffer(gl::COLOR_ATTACHMENT0 + attachment_index as GLenum);
-            assert_eq!(0, self.gl.GetError());
+        }
+    }
+    /// Disables reading from the framebuffer's color buffers, for framebuffers without any color
+    /// attachments.
+    pub(crate) fn read_none(&self) {
+        unsafe { self.gl.ReadBuffer(gl::NONE) }
+    }
+    pub(crate) fn attachment_info(&self, slot: AttachmentSlot) -> AttachmentInfo {
+        let attachment = match (slot, self.is_default) {
+            (AttachmentSlot::Color(0), true) => gl::BACK_LEFT,
+            (AttachmentSlot::Color(_), true) => {
+                panic!("the default framebuffer only has one color attachment")
+            }
+            (AttachmentSlot::Color(index), false) => {
+                let mut max_color_attachments = 0;
+                unsafe {
+                    self.gl
+                        .GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments)
+                };
+                assert!(
+                    (index as GLint) < max_color_attachments,
+                    "color attachment {} out of range; implementation supports {} color attachments",
+                    index,
+                    max_color_attachments
+                );
+                gl::COLOR_ATTACHMENT0 + index as GLenum
+            }
+            (AttachmentSlot::Depth, true) => gl::DEPTH,
+            (AttachmentSlot::Depth, false) => gl::DEPTH_ATTACHMENT,
+            (AttachmentSlot::Stencil, true) => gl::STENCIL,
+            (AttachmentSlot::Stencil, false) => gl::STENCIL_ATTACHMENT,
+        };
+        let parameter = |pname| {
+            let mut value = 0;
+            unsafe {
+                self.gl.GetFramebufferAttachmentParameteriv(
+                    gl::READ_FRAMEBUFFER,
+                    attachment,
+                    pname,
+                    &mut value,
+                );
+            }
+            value
+        };
+
+        let object_type = match parameter(gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) as GLenum {
+            gl::FRAMEBUFFER_DEFAULT => AttachmentObjectType::Default,
+            gl::RENDERBUFFER => AttachmentObjectType::Renderbuffer,
+            gl::TEXTURE => AttachmentObjectType::Texture,
+            _ => AttachmentObjectType::None,
+        };
+        // Querying anything other than the object type is an error if nothing is attached.
+        if object_type == AttachmentObjectType::None {
+            return AttachmentInfo {
+                object_type,
+                name: None,
+                level: 0,
+                srgb: false,
+            };
+        }
+
+        let name = match object_type {
+            AttachmentObjectType::Renderbuffer | AttachmentObjectType::Texture => {
+                Handle::new(parameter(gl::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME) as GLuint)
+            }
+            _ => None,
+        };
+        let level = match object_type {
+            AttachmentObjectType::Texture => {
+                parameter(gl::FRAMEBUFFER_ATTACHMENT_TEXTURE_LEVEL) as u32
+            }
+            _ => 0,
+        };
+        let srgb = parameter(gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING) as GLenum == gl::SRGB;
+
+        AttachmentInfo {
+            object_type,
+            name,
+            level,
+            srgb,
         }
     }
     #[inline]
@@ -249,6 +340,7 @@ where
                  FormatAttributes::Uncompressed value"
             ),
         };
+        let _error_scope = ErrorScope::new(self.gl, "glReadPixels");
         unsafe {
             self.gl.ReadPixels(
                 read_origin.x as GLint,
@@ -259,7 +351,117 @@ where
                 pixel_type,
                 data.as_mut_ptr() as *mut GLvoid,
             );
-            assert_eq!(0, self.gl.GetError());
+        }
+    }
+
+    /// The number of bits in the bound framebuffer's stencil buffer, or `0` if it has none.
+    pub(crate) fn stencil_bits(&self) -> u32 {
+        let attachment = match self.is_default {
+            true => gl::STENCIL,
+            false => gl::STENCIL_ATTACHMENT,
+        };
+        let mut object_type = 0;
+        let mut bits = 0;
+        unsafe {
+            self.gl.GetFramebufferAttachmentParameteriv(
+                gl::READ_FRAMEBUFFER,
+                attachment,
+                gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+                &mut object_type,
+            );
+            if object_type as GLenum != gl::NONE {
+                self.gl.GetFramebufferAttachmentParameteriv(
+                    gl::READ_FRAMEBUFFER,
+                    attachment,
+                    gl::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE,
+                    &mut bits,
+                );
+            }
+        }
+        bits as u32
+    }
+
+    #[inline]
+    pub(crate) fn read_stencil(
+        &self,
+        read_origin: GLVec2<u32, NonNormalized>,
+        read_dims: GLVec2<u32, NonNormalized>,
+        data: &mut [u8],
+    ) {
+        let read_len = (read_dims.x * read_dims.y) as usize;
+        assert_eq!(
+            read_len,
+            data.len(),
+            "expected buffer of length {}, but got buffer of length {}",
+            read_len,
+            data.len()
+        );
+        assert!(read_origin.x as i32 >= 0);
+        assert!(read_origin.y as i32 >= 0);
+        assert!(read_dims.x as i32 >= 0);
+        assert!(read_dims.y as i32 >= 0);
+        assert_ne!(
+            0,
+            self.stencil_bits(),
+            "attempted to read stencil values from framebuffer without stencil buffer"
+        );
+
+        // The pack alignment is set to 1 when the context state is created, so the rows in `data`
+        // don't need any padding.
+        let _error_scope = ErrorScope::new(self.gl, "glReadPixels");
+        unsafe {
+            self.gl.ReadPixels(
+                read_origin.x as GLint,
+                read_origin.y as GLint,
+                read_dims.x as GLsizei,
+                read_dims.y as GLsizei,
+                gl::STENCIL_INDEX,
+                gl::UNSIGNED_BYTE,
+                data.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+    }
+
+    #[inline]
+    pub(crate) fn read_depth_stencil(
+        &self,
+        read_origin: GLVec2<u32, NonNormalized>,
+        read_dims: GLVec2<u32, NonNormalized>,
+        data: &mut [u32],
+    ) {
+        let read_len = (read_dims.x * read_dims.y) as usize;
+        assert_eq!(
+            read_len,
+            data.len(),
+            "expected buffer of length {}, but got buffer of length {}",
+            read_len,
+            data.len()
+        );
+        assert!(read_origin.x as i32 >= 0);
+        assert!(read_origin.y as i32 >= 0);
+        assert!(read_dims.x as i32 >= 0);
+        assert!(read_dims.y as i32 >= 0);
+        let depth = self.attachment_info(AttachmentSlot::Depth);
+        let stencil = self.attachment_info(AttachmentSlot::Stencil);
+        assert!(
+            depth.object_type != AttachmentObjectType::None
+                && depth.object_type == stencil.object_type
+                && depth.name == stencil.name,
+            "attempted to read depth-stencil values from framebuffer without combined \
+             depth-stencil buffer"
+        );
+
+        let _error_scope = ErrorScope::new(self.gl, "glReadPixels");
+        unsafe {
+            self.gl.ReadPixels(
+                read_origin.x as GLint,
+                read_origin.y as GLint,
+                read_dims.x as GLsizei,
+                read_dims.y as GLsizei,
+                gl::DEPTH_STENCIL,
+                gl::UNSIGNED_INT_24_8,
+                data.as_mut_ptr() as *mut GLvoid,
+            );
         }
     }
 }
@@ -268,24 +470,131 @@ impl<'a, F> RawBoundFramebufferDraw<'a, F>
 where
     F: RawFramebuffer,
 {
+    /// Clears every color buffer being drawn to with `glClear`. `glClearColor` only gets called
+    /// if `color` differs from `cached_color`.
+    #[inline]
+    pub(crate) fn clear_color(&mut self, color: Rgba<f32>, cached_color: &Cell<Rgba<f32>>) {
+        unsafe {
+            if cached_color.get() != color {
+                self.gl.ClearColor(color.r, color.g, color.b, color.a);
+                cached_color.set(color);
+            }
+            self.gl.Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    /// Clears a color attachment, using the `glClearBuffer` variant that matches the attachment's
+    /// scalar type. Clearing integer attachments with `glClearBufferfv` leaves them undefined.
     #[inline]
-    pub(crate) fn clear_color_attachment(&mut self, color: Rgba<f32>, attachment: u8) {
-        unsafe { self.gl.ClearBufferfv(gl::COLOR, attachment as _, &color.r) }
+    pub(crate) fn clear_color_attachment(
+        &mut self,
+        color: Rgba<f32>,
+        attachment: u8,
+        scalar_type: TypeTagSingle,
+    ) {
+        unsafe {
+            match scalar_type {
+                TypeTagSingle::Int => {
+                    let color = [
+                        color.r as GLint,
+                        color.g as GLint,
+                        color.b as GLint,
+                        color.a as GLint,
+                    ];
+                    self.gl
+                        .ClearBufferiv(gl::COLOR, attachment as _, color.as_ptr())
+                }
+                TypeTagSingle::UInt | TypeTagSingle::Bool => {
+                    let color = [
+                        color.r as GLuint,
+                        color.g as GLuint,
+                        color.b as GLuint,
+                        color.a as GLuint,
+                    ];
+                    self.gl
+                        .ClearBufferuiv(gl::COLOR, attachment as _, color.as_ptr())
+                }
+                _ => self.gl.ClearBufferfv(gl::COLOR, attachment as _, &color.r),
+            }
+        }
     }
 
     #[inline]
-    pub(crate) fn clear_depth(&mut self, depth: f32) {
-        unsafe { self.gl.ClearBufferfv(gl::DEPTH, 0, &depth) }
+    pub(crate) fn clear_depth(&mut self, depth: f32, cached_depth: &Cell<f32>) {
+        unsafe {
+            if cached_depth.get() != depth {
+                self.gl.ClearDepth(depth as GLdouble);
+                cached_depth.set(depth);
+            }
+            self.gl.Clear(gl::DEPTH_BUFFER_BIT);
+        }
     }
 
     #[inline]
-    pub(crate) fn clear_stencil(&mut self, stencil: u32) {
+    pub(crate) fn clear_stencil(&mut self, stencil: u32, cached_stencil: &Cell<GLint>) {
+        let stencil = stencil as GLint;
         unsafe {
-            self.gl.ClearStencil(stencil as GLint);
+            if cached_stencil.get() != stencil {
+                self.gl.ClearStencil(stencil);
+                cached_stencil.set(stencil);
+            }
             self.gl.Clear(gl::STENCIL_BUFFER_BIT);
         }
     }
 
+    #[inline]
+    pub(crate) fn draw_color_attachment(&mut self, attachment_index: u8) {
+        assert!(attachment_index < 32);
+        let _error_scope = ErrorScope::new(self.gl, "glDrawBuffer");
+        unsafe {
+            self.gl
+                .DrawBuffer(gl::COLOR_ATTACHMENT0 + attachment_index as GLenum);
+        }
+    }
+
+    pub(crate) fn sample_position(&self, index: u32) -> [f32; 2] {
+        let mut samples = 0;
+        let mut position = [0.0; 2];
+        unsafe {
+            self.gl.GetIntegerv(gl::SAMPLES, &mut samples);
+            assert!(
+                index < samples as u32,
+                "sample index {} out of range for framebuffer with {} samples",
+                index,
+                samples
+            );
+            self.gl
+                .GetMultisamplefv(gl::SAMPLE_POSITION, index, position.as_mut_ptr());
+        }
+        position
+    }
+
+    /// Copies the `dims`-sized region of the bound read framebuffer's read buffer at `src_origin`
+    /// to this framebuffer's origin with `glBlitFramebuffer`. Multisampled source images get
+    /// resolved in the process.
+    pub(crate) fn blit_color_from_read(
+        &mut self,
+        src_origin: GLVec2<u32, NonNormalized>,
+        dims: GLVec2<u32, NonNormalized>,
+    ) {
+        let src_end = src_origin + dims;
+        let _error_scope = ErrorScope::new(self.gl, "glBlitFramebuffer");
+        unsafe {
+            self.gl.BlitFramebuffer(
+                src_origin.x as GLint,
+                src_origin.y as GLint,
+                src_end.x as GLint,
+                src_end.y as GLint,
+                0,
+                0,
+                dims.x as GLint,
+                dims.y as GLint,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+    }
+
     #[inline]
     pub(crate) fn draw_buffers(&mut self, buffer: &[GLenum]) {
         unsafe {
@@ -295,24 +604,41 @@ where
     }
 
     #[inline]
-    pub(crate) fn draw<R, V, I, U, A>(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw<R, V, I>(
         &mut self,
         mode: DrawMode,
         range: R,
         bound_vao: &BoundVAO<V, I>,
-        _bound_program: &BoundProgram<V, U, A>,
         instance_count: Option<usize>,
         base_index: Option<I>,
+        base_instance: Option<u32>,
     ) where
         R: RangeBounds<usize>,
         V: Vertex,
         I: Index,
-        U: Uniforms,
-        A: Attachments,
     {
+        if mode == DrawMode::Patches {
+            assert!(
+                self.gl.PatchParameteri.is_loaded(),
+                "patches require OpenGL 4.0 or GL_ARB_tessellation_shader"
+            );
+        }
+
         let index_type_option = I::INDEX_GL_ENUM;
         let read_offset = crate::bound_to_num_start(range.start_bound(), 0);
 
+        if base_instance.is_some() {
+            assert!(
+                self.gl.DrawArraysInstancedBaseInstance.is_loaded(),
+                "base instances require OpenGL 4.2 or GL_ARB_base_instance"
+            );
+        }
+
+        let instance_count = match (instance_count, base_instance) {
+            (None, Some(_)) => Some(1),
+            (instance_count, _) => instance_count,
+        };
         let instance_count = instance_count.map(|instance_count| {
             assert!(instance_count <= GLsizei::max_value() as usize);
             instance_count as GLsizei
@@ -328,16 +654,23 @@ where
             let mode = mode.to_gl_enum();
             let first = (read_end - read_offset) as GLsizei;
             let indices = (read_offset * mem::size_of::<I>()) as *const GLvoid;
+            if first == 0 || instance_count == Some(0) {
+                return;
+            }
 
             unsafe {
-                match (instance_count, base_index) {
-                    (Some(instance_count), Some(base_index)) =>
+                match (instance_count, base_index, base_instance) {
+                    (Some(instance_count), Some(base_index), Some(base_instance)) =>
+                        self.gl.DrawElementsInstancedBaseVertexBaseInstance(mode, first, index_type, indices, instance_count, base_index.as_glint(), base_instance),
+                    (Some(instance_count), None, Some(base_instance)) =>
+                        self.gl.DrawElementsInstancedBaseInstance(mode, first, index_type, indices, instance_count, base_instance),
+                    (Some(instance_count), Some(base_index), None) =>
                         self.gl.DrawElementsInstancedBaseVertex(mode, first, index_type, indices, instance_count, base_index.as_glint()),
-                    (None, Some(base_index)) =>
+                    (None, Some(base_index), _) =>
                         self.gl.DrawElementsBaseVertex(mode, first, index_type, indices, base_index.as_glint()),
-                    (Some(instance_count), None) =>
+                    (Some(instance_count), None, None) =>
                         self.gl.DrawElementsInstanced(mode, first, index_type, indices, instance_count),
-                    (None, None) => self.gl.DrawElements(mode, first, index_type, indices),
+                    (None, None, _) => self.gl.DrawElements(mode, first, index_type, indices),
                 }
             }
         } else {
@@ -350,11 +683,16 @@ where
             let mode = mode.to_gl_enum();
             let count = (read_end - read_offset) as GLsizei;
             let read_offset = read_offset as GLint;
+            if count == 0 || instance_count == Some(0) {
+                return;
+            }
 
             unsafe {
-                match instance_count {
-                    Some(instance_count) => self.gl.DrawArraysInstanced(mode, read_offset, count, instance_count),
-                    None => self.gl.DrawArrays(mode, read_offset, count),
+                match (instance_count, base_instance) {
+                    (Some(instance_count), Some(base_instance)) =>
+                        self.gl.DrawArraysInstancedBaseInstance(mode, read_offset, count, instance_count, base_instance),
+                    (Some(instance_count), None) => self.gl.DrawArraysInstanced(mode, read_offset, count, instance_count),
+                    (None, _) => self.gl.DrawArrays(mode, read_offset, count),
                 }
             }
         }
@@ -389,11 +727,57 @@ pub unsafe trait RawBoundFramebuffer {
         struct Attacher<'a, A: 'a + Attachments, I: Iterator<Item = &'a mut Option<Handle>>> {
             color_index: GLenum,
             depth_attachment_used: bool,
+            stencil_attachment_used: bool,
+            changed: &'a mut bool,
             gl: &'a Gl,
             handles: I,
             target: GLenum,
             attachments: &'a A,
         }
+        impl<'a, A: Attachments, I: Iterator<Item = &'a mut Option<Handle>>> Attacher<'a, A, I> {
+            /// Returns the attachment point for the next member, and whether the member needs to
+            /// be attached to it. Attachment points are assigned even for members that are
+            /// already attached, so that swapping one member out doesn't shift the color
+            /// attachment indices of the others.
+            fn next_attachment(
+                &mut self,
+                format_type: FormatTypeTag,
+                handle: Handle,
+            ) -> (GLenum, bool) {
+                let attachment = match format_type {
+                    FormatTypeTag::Color => {
+                        self.color_index += 1;
+                        gl::COLOR_ATTACHMENT0 + self.color_index - 1
+                    }
+                    FormatTypeTag::Depth | FormatTypeTag::Stencil | FormatTypeTag::DepthStencil => {
+                        let uses_depth = format_type != FormatTypeTag::Stencil;
+                        let uses_stencil = format_type != FormatTypeTag::Depth;
+                        if uses_depth && self.depth_attachment_used {
+                            panic!("Attempted to attach multiple depth images to a single FBO");
+                        }
+                        if uses_stencil && self.stencil_attachment_used {
+                            panic!("Attempted to attach multiple stencil images to a single FBO");
+                        }
+                        self.depth_attachment_used |= uses_depth;
+                        self.stencil_attachment_used |= uses_stencil;
+                        match format_type {
+                            FormatTypeTag::Depth => gl::DEPTH_ATTACHMENT,
+                            FormatTypeTag::Stencil => gl::STENCIL_ATTACHMENT,
+                            _ => gl::DEPTH_STENCIL_ATTACHMENT,
+                        }
+                    }
+                };
+
+                let cached_handle = self
+                    .handles
+                    .next()
+                    .expect("Mismatched attachment handle container length");
+                let changed = Some(handle) != *cached_handle;
+                *cached_handle = Some(handle);
+                *self.changed |= changed;
+                (attachment, changed)
+            }
+        }
         impl<'a, A: Attachments, I: Iterator<Item = &'a mut Option<Handle>>>
             AttachmentsMemberRegistry for Attacher<'a, A, I>
         {
@@ -405,29 +789,13 @@ pub unsafe trait RawBoundFramebuffer {
             ) where
                 Im: ImageFormatRenderable,
             {
-                let member = get_member(self.attachments);
-                let handle = self
-                    .handles
-                    .next()
-                    .expect("Mismatched attachment handle container length");
-                if Some(member.handle()) != *handle {
-                    *handle = Some(member.handle());
-                    let handle = member.handle();
-                    let attachment: GLenum;
-                    match <<Renderbuffer<Im> as AttachmentType>::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE {
-                        FormatTypeTag::Color => {
-                            attachment = gl::COLOR_ATTACHMENT0 + self.color_index;
-                            self.color_index += 1;
-                        },
-                        FormatTypeTag::Depth => {
-                            if self.depth_attachment_used {
-                                panic!("Attempted to attach multiple depth images to a single FBO");
-                            }
-                            self.depth_attachment_used = true;
-                            attachment = gl::DEPTH_ATTACHMENT;
-                        }
-                    }
-
+                let handle = get_member(self.attachments).handle();
+                let (attachment, changed) = self.next_attachment(
+                    <<Renderbuffer<Im> as AttachmentType>::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE,
+                    handle,
+                );
+                if changed {
+                    let _error_scope = ErrorScope::new(self.gl, "glFramebufferRenderbuffer");
                     unsafe {
                         self.gl.FramebufferRenderbuffer(
                             self.target,
@@ -435,7 +803,6 @@ pub unsafe trait RawBoundFramebuffer {
                             gl::RENDERBUFFER,
                             handle.get(),
                         );
-                        assert_eq!(0, self.gl.GetError());
                     }
                 }
             }
@@ -449,29 +816,13 @@ pub unsafe trait RawBoundFramebuffer {
                 T: TextureType<D>,
                 T::Format: ImageFormatRenderable,
             {
-                let texture = get_member(self.attachments);
-                let handle = self
-                    .handles
-                    .next()
-                    .expect("Mismatched attachment handle container length");
-                if Some(texture.handle()) != *handle {
-                    *handle = Some(texture.handle());
-                    let handle = texture.handle();
-                    let attachment: GLenum;
-                    match <<Texture<D, T> as AttachmentType>::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE {
-                        FormatTypeTag::Color => {
-                            attachment = gl::COLOR_ATTACHMENT0 + self.color_index;
-                            self.color_index += 1;
-                        },
-                        FormatTypeTag::Depth => {
-                            if self.depth_attachment_used {
-                                panic!("Attempted to attach multiple depth images to a single FBO");
-                            }
-                            self.depth_attachment_used = true;
-                            attachment = gl::DEPTH_ATTACHMENT;
-                        }
-                    }
-
+                let handle = get_member(self.attachments).handle();
+                let (attachment, changed) = self.next_attachment(
+                    <<Texture<D, T> as AttachmentType>::Format as ImageFormatRenderable>::FormatType::FORMAT_TYPE,
+                    handle,
+                );
+                if changed {
+                    let _error_scope = ErrorScope::new(self.gl, "glFramebufferTexture");
                     unsafe {
                         // TODO: HANDLE LAYERED TEXTURES
                         self.gl.FramebufferTexture(
@@ -480,22 +831,67 @@ pub unsafe trait RawBoundFramebuffer {
                             handle.get(),
                             texture_level.to_glint(),
                         );
-                        assert_eq!(0, self.gl.GetError());
+                    }
+                }
+            }
+            fn add_texture_3d_slice<C>(
+                &mut self,
+                name: &str,
+                get_member: impl FnOnce(&Self::Attachments) -> &Texture<D3, C>,
+                z: u32,
+                texture_level: u8,
+            ) where
+                C: ImageFormatRenderable,
+            {
+                let texture = get_member(self.attachments);
+                let depth = <C as TextureType<D3>>::mip_dims(texture.dims(), texture_level).z;
+                if depth <= z {
+                    panic!(
+                        "Attachment {} uses slice {}, but mip level {} of the 3D texture only has \
+                         {} slices",
+                        name, z, texture_level, depth
+                    );
+                }
+
+                let handle = texture.handle();
+                let (attachment, changed) = self.next_attachment(
+                    <C as ImageFormatRenderable>::FormatType::FORMAT_TYPE,
+                    handle,
+                );
+                if changed {
+                    let _error_scope = ErrorScope::new(self.gl, "glFramebufferTextureLayer");
+                    unsafe {
+                        self.gl.FramebufferTextureLayer(
+                            self.target,
+                            attachment,
+                            handle.get(),
+                            GLint::from(texture_level),
+                            z as GLint,
+                        );
                     }
                 }
             }
         }
 
+        // Mismatched attachment sizes make the renderable area ambiguous, and mismatched sample
+        // counts make the framebuffer incomplete, so we disallow both.
+        attachments.dims();
+
+        let mut changed = false;
         A::members(Attacher {
             color_index: 0,
             depth_attachment_used: false,
+            stencil_attachment_used: false,
+            changed: &mut changed,
             handles: handles.iter_mut(),
             gl: self.gl(),
             target: Self::TARGET,
             attachments,
         });
-        unsafe {
-            self.gl().CheckFramebufferStatus(Self::TARGET);
+        if changed {
+            unsafe {
+                self.gl().CheckFramebufferStatus(Self::TARGET);
+            }
         }
     }
 }
@@ -0,0 +1,154 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Grouping a framebuffer's clears and draws into a single pass.
+
+use super::{render_state::RenderState, DrawMode, Framebuffer};
+use crate::{
+    image_format::Rgba,
+    program::DrawProgram,
+    uniform::Uniforms,
+    vertex::{Index, Vertex, VertexArrayObject},
+};
+
+use std::ops::RangeBounds;
+
+/// The values a [`RenderPass`] clears its framebuffer to when it begins.
+///
+/// Buffers with a `None` value don't get cleared. The default value doesn't clear anything.
+///
+/// [`RenderPass`]: ./struct.RenderPass.html
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ClearValues {
+    /// The value all color attachments get cleared to.
+    pub color: Option<Rgba<f32>>,
+    pub depth: Option<f32>,
+    pub stencil: Option<u32>,
+}
+
+/// A framebuffer, bundled with the render state used to draw to it.
+///
+/// Beginning a pass clears the framebuffer, and every draw made through the pass uses the pass's
+/// render state. This is purely a convenience over calling the [`Framebuffer`] methods directly,
+/// which helps keep the state of each pass in a multi-pass pipeline separate.
+///
+/// [`Framebuffer`]: ./trait.Framebuffer.html
+pub struct RenderPass<'a, F: Framebuffer> {
+    framebuffer: &'a mut F,
+    render_state: RenderState,
+}
+
+impl<'a, F: Framebuffer> RenderPass<'a, F> {
+    /// Begins a render pass, clearing `framebuffer` with `clear`.
+    pub fn begin(
+        framebuffer: &'a mut F,
+        clear: ClearValues,
+        render_state: RenderState,
+    ) -> RenderPass<'a, F> {
+        if let Some(color) = clear.color {
+            framebuffer.clear_color_all(color);
+        }
+        if let Some(depth) = clear.depth {
+            framebuffer.clear_depth(depth);
+        }
+        if let Some(stencil) = clear.stencil {
+            framebuffer.clear_stencil(stencil);
+        }
+
+        RenderPass {
+            framebuffer,
+            render_state,
+        }
+    }
+
+    /// Performs a single draw call with the pass's render state. See [`Framebuffer::draw`].
+    ///
+    /// [`Framebuffer::draw`]: ./trait.Framebuffer.html#method.draw
+    #[inline]
+    pub fn draw<R, V, I, U, P>(
+        &mut self,
+        mode: DrawMode,
+        range: R,
+        vao: &VertexArrayObject<V, I>,
+        program: &P,
+        uniforms: &U,
+    ) where
+        R: RangeBounds<usize>,
+        V: Vertex,
+        I: Index,
+        U: Uniforms,
+        P: DrawProgram<V, U::Static, F::AttachmentsStatic>,
+    {
+        self.framebuffer
+            .draw(mode, range, vao, program, uniforms, &self.render_state)
+    }
+
+    /// Performs a single instanced draw call with the pass's render state. See
+    /// [`Framebuffer::draw_ext`].
+    ///
+    /// [`Framebuffer::draw_ext`]: ./trait.Framebuffer.html#method.draw_ext
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_ext<R, V, I, U, P>(
+        &mut self,
+        mode: DrawMode,
+        range: R,
+        vao: &VertexArrayObject<V, I>,
+        program: &P,
+        uniforms: &U,
+        instance_count: Option<usize>,
+        base_index: Option<I>,
+        base_instance: Option<u32>,
+    ) where
+        R: RangeBounds<usize>,
+        V: Vertex,
+        I: Index,
+        U: Uniforms,
+        P: DrawProgram<V, U::Static, F::AttachmentsStatic>,
+    {
+        self.framebuffer.draw_ext(
+            mode,
+            range,
+            vao,
+            program,
+            uniforms,
+            &self.render_state,
+            instance_count,
+            base_index,
+            base_instance,
+        )
+    }
+
+    #[inline]
+    pub fn render_state(&self) -> &RenderState {
+        &self.render_state
+    }
+
+    /// Mutable access to the pass's render state, for changing state between draws.
+    #[inline]
+    pub fn render_state_mut(&mut self) -> &mut RenderState {
+        &mut self.render_state
+    }
+
+    #[inline]
+    pub fn framebuffer(&mut self) -> &mut F {
+        self.framebuffer
+    }
+
+    /// Ends the pass, returning its render state so that it can be reused by later passes.
+    #[inline]
+    pub fn end(self) -> RenderState {
+        self.render_state
+    }
+}
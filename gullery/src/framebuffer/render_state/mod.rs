@@ -15,12 +15,14 @@
 mod raw;
 
 use self::raw::Capability;
+pub(crate) use self::raw::{set_clip_control, set_provoking_vertex};
 pub use self::raw::{
-    BlendFunc, BlendFuncs, ColorMask, CullFace, DepthStencilFunc, FrontFace, PolygonOffset,
-    StencilOp, StencilTest,
+    BlendFunc, BlendFuncs, ClipDepthMode, ClipOrigin, ColorMask, CullFace, DepthStencilFunc,
+    FrontFace, PolygonOffset, ProvokingVertex, StencilFace, StencilOp, StencilTest,
 };
 use crate::{
     geometry::{GLVec2, NonNormalized},
+    gl::Gl,
     ContextState,
 };
 use std::ops::RangeInclusive;
@@ -29,6 +31,16 @@ use std::ops::RangeInclusive;
 pub struct RenderState {
     pub blend: BlendFuncs,
     pub cull: Option<(CullFace, FrontFace)>,
+    /// Clamp fragment depths to the depth range instead of clipping primitives against the near
+    /// and far planes. Maps to `GL_DEPTH_CLAMP`.
+    ///
+    /// This keeps shadow casters behind the light's near plane and skyboxes past the far plane
+    /// from getting clipped away. Depths get clamped *after* the clip-space to window-space
+    /// transform, so the clamped range is `0.0..=1.0` regardless of the
+    /// [`ClipDepthMode`](./enum.ClipDepthMode.html) set with
+    /// [`ContextState::set_clip_control`](../../struct.ContextState.html#method.set_clip_control).
+    /// Clamped fragments still go through the depth test, and with reverse-Z the far plane clamps
+    /// to `0.0`.
     pub depth_clamp: bool,
     pub depth_test: Option<DepthStencilFunc>,
     pub dither: bool,
@@ -49,59 +61,75 @@ impl RenderState {
     #[inline]
     pub fn upload_state(&self, state: &ContextState) {
         let old_state = state.render_state.replace(self.clone());
-        let gl = &state.gl;
-        if self.blend != old_state.blend {
+        self.upload_fields(&state.gl, Some(&old_state));
+    }
+
+    /// Uploads every field to OpenGL, regardless of what's cached. Used when code outside of
+    /// Gullery may have changed the render state.
+    #[inline]
+    pub(crate) fn upload_all(&self, gl: &Gl) {
+        self.upload_fields(gl, None);
+    }
+
+    /// Uploads the fields that differ from `old_state`, or all of them if it's `None`.
+    fn upload_fields(&self, gl: &Gl, old_state: Option<&RenderState>) {
+        macro_rules! changed {
+            ($field:ident) => {
+                old_state.map(|old| self.$field != old.$field).unwrap_or(true)
+            };
+        }
+        if changed!(blend) {
             raw::set_gl_cap(gl, Capability::Blend(Some(self.blend)));
         }
-        if self.cull != old_state.cull {
+        if changed!(cull) {
             raw::set_gl_cap(gl, Capability::Cull(self.cull));
         }
-        if self.depth_clamp != old_state.depth_clamp {
+        if changed!(depth_clamp) {
             raw::set_gl_cap(gl, Capability::DepthClamp(self.depth_clamp));
         }
-        if self.depth_test != old_state.depth_test {
+        if changed!(depth_test) {
             raw::set_gl_cap(gl, Capability::DepthTest(self.depth_test));
         }
-        if self.dither != old_state.dither {
+        if changed!(dither) {
             raw::set_gl_cap(gl, Capability::Dither(self.dither));
         }
-        if self.srgb != old_state.srgb {
+        if changed!(srgb) {
             raw::set_gl_cap(gl, Capability::Srgb(self.srgb));
         }
-        if self.multisample != old_state.multisample {
+        if changed!(multisample) {
             raw::set_gl_cap(gl, Capability::Multisample(self.multisample));
         }
-        if self.primitive_restart_index != old_state.primitive_restart_index {
+        if changed!(primitive_restart_index) {
             raw::set_gl_cap(
                 gl,
                 Capability::PrimitiveRestart(self.primitive_restart_index),
             );
         }
-        if self.rasterizer_discard != old_state.rasterizer_discard {
+        if changed!(rasterizer_discard) {
             raw::set_gl_cap(gl, Capability::RasterizerDiscard(self.rasterizer_discard));
         }
-        if self.stencil_test != old_state.stencil_test {
+        if changed!(stencil_test) {
             raw::set_gl_cap(gl, Capability::StencilTest(self.stencil_test));
         }
-        if self.texture_cubemap_seamless != old_state.texture_cubemap_seamless {
+        if changed!(texture_cubemap_seamless) {
             raw::set_gl_cap(
                 gl,
                 Capability::TextureCubemapSeamless(self.texture_cubemap_seamless),
             );
         }
-        if self.program_point_size != old_state.program_point_size {
+        if changed!(program_point_size) {
             raw::set_gl_cap(gl, Capability::ProgramPointSize(self.program_point_size));
         }
-        if self.polygon_offset != old_state.polygon_offset {
+        if changed!(polygon_offset) {
             raw::set_gl_cap(gl, Capability::PolygonOffset(self.polygon_offset));
         }
-        if self.viewport != old_state.viewport {
+        if changed!(viewport) {
             raw::set_viewport(gl, *self.viewport.start(), *self.viewport.end());
         }
-        if self.color_mask != old_state.color_mask {
+        if changed!(color_mask) {
             raw::set_color_mask(gl, self.color_mask);
         }
-        if self.depth_mask != old_state.depth_mask {
+        if changed!(depth_mask) {
             raw::set_depth_mask(gl, self.depth_mask);
         }
     }
@@ -112,11 +112,31 @@ pub enum DepthStencilFunc {
     Always = gl::ALWAYS,
 }
 
+/// The stencil test, with separate state for front- and back-facing primitives.
+///
+/// Points, lines and polygons drawn as points or lines always use the `front` state. Use
+/// [`StencilTest::both`] to use the same state for both faces.
+///
+/// [`StencilTest::both`]: ./struct.StencilTest.html#method.both
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct StencilTest {
+    pub front: StencilFace,
+    pub back: StencilFace,
+}
+
+/// The stencil test state for a single face. The default value matches OpenGL's defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilFace {
     pub func: DepthStencilFunc,
+    /// The reference value fragments get compared against, and that [`StencilOp::Replace`]
+    /// writes.
+    ///
+    /// [`StencilOp::Replace`]: ./enum.StencilOp.html#variant.Replace
     pub frag_value: i32,
-    pub mask: u32,
+    /// The bits of the reference value and stored stencil value that get compared.
+    pub read_mask: u32,
+    /// The bits of the stencil buffer that stencil operations can write to.
+    pub write_mask: u32,
     pub stencil_fail: StencilOp,
     pub depth_fail: StencilOp,
     pub depth_pass: StencilOp,
@@ -135,6 +155,34 @@ pub enum StencilOp {
     Invert = gl::INVERT,
 }
 
+/// The window-space location of the clip-space origin.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipOrigin {
+    LowerLeft = gl::LOWER_LEFT,
+    UpperLeft = gl::UPPER_LEFT,
+}
+
+/// The clip-space depth range that gets mapped to the `0.0..=1.0` depth buffer range.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipDepthMode {
+    /// OpenGL's default convention.
+    NegativeOneToOne = gl::NEGATIVE_ONE_TO_ONE,
+    /// The convention used by Direct3D and Vulkan. Needed for precise reverse-Z rendering.
+    ZeroToOne = gl::ZERO_TO_ONE,
+}
+
+/// The vertex of each primitive whose `flat`-qualified outputs get used for the whole primitive.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvokingVertex {
+    /// The convention used by Direct3D.
+    FirstVertex = gl::FIRST_VERTEX_CONVENTION,
+    /// OpenGL's default convention.
+    LastVertex = gl::LAST_VERTEX_CONVENTION,
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct PolygonOffset {
     pub factor: f32,
@@ -204,14 +252,28 @@ pub fn set_gl_cap(gl: &Gl, cap: Capability) {
             }
             StencilTest(test_opt) => {
                 gl_capability = &[gl::STENCIL_TEST];
-                if let Some(test) = test_opt {
-                    enable = true;
-                    gl.StencilFunc(test.func.into(), test.frag_value, test.mask);
-                    gl.StencilOp(
-                        test.stencil_fail.into(),
-                        test.depth_fail.into(),
-                        test.depth_pass.into(),
-                    );
+                match test_opt {
+                    Some(test) => {
+                        enable = true;
+                        for &(face, state) in &[(gl::FRONT, test.front), (gl::BACK, test.back)] {
+                            gl.StencilFuncSeparate(
+                                face,
+                                state.func.into(),
+                                state.frag_value,
+                                state.read_mask,
+                            );
+                            gl.StencilOpSeparate(
+                                face,
+                                state.stencil_fail.into(),
+                                state.depth_fail.into(),
+                                state.depth_pass.into(),
+                            );
+                            gl.StencilMaskSeparate(face, state.write_mask);
+                        }
+                    }
+                    // The write mask also applies to stencil clears, so make sure a mask from an
+                    // earlier test doesn't stop the stencil buffer from getting cleared.
+                    None => gl.StencilMask(!0),
                 }
             }
             TextureCubemapSeamless(seamless) => {
@@ -276,6 +338,18 @@ pub fn set_depth_mask(gl: &Gl, mask: bool) {
     }
 }
 
+pub fn set_clip_control(gl: &Gl, origin: ClipOrigin, depth_mode: ClipDepthMode) {
+    unsafe {
+        gl.ClipControl(origin.into(), depth_mode.into());
+    }
+}
+
+pub fn set_provoking_vertex(gl: &Gl, provoking_vertex: ProvokingVertex) {
+    unsafe {
+        gl.ProvokingVertex(provoking_vertex.into());
+    }
+}
+
 impl From<BlendFunc> for GLenum {
     #[inline]
     fn from(func: BlendFunc) -> GLenum {
@@ -283,6 +357,27 @@ impl From<BlendFunc> for GLenum {
     }
 }
 
+impl From<ClipOrigin> for GLenum {
+    #[inline]
+    fn from(origin: ClipOrigin) -> GLenum {
+        unsafe { mem::transmute(origin) }
+    }
+}
+
+impl From<ClipDepthMode> for GLenum {
+    #[inline]
+    fn from(depth_mode: ClipDepthMode) -> GLenum {
+        unsafe { mem::transmute(depth_mode) }
+    }
+}
+
+impl From<ProvokingVertex> for GLenum {
+    #[inline]
+    fn from(provoking_vertex: ProvokingVertex) -> GLenum {
+        unsafe { mem::transmute(provoking_vertex) }
+    }
+}
+
 impl From<CullFace> for GLenum {
     #[inline]
     fn from(face: CullFace) -> GLenum {
@@ -332,6 +427,32 @@ impl Default for DepthStencilFunc {
     }
 }
 
+impl StencilTest {
+    /// Creates a stencil test that uses the same state for front- and back-facing primitives.
+    #[inline]
+    pub fn both(face: StencilFace) -> StencilTest {
+        StencilTest {
+            front: face,
+            back: face,
+        }
+    }
+}
+
+impl Default for StencilFace {
+    #[inline]
+    fn default() -> StencilFace {
+        StencilFace {
+            func: DepthStencilFunc::Always,
+            frag_value: 0,
+            read_mask: !0,
+            write_mask: !0,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            depth_pass: StencilOp::Keep,
+        }
+    }
+}
+
 impl Default for StencilOp {
     #[inline]
     fn default() -> StencilOp {
@@ -13,16 +13,19 @@
 // limitations under the License.
 
 use crate::{
-    geometry::{GLVec2, NonNormalized},
+    bind::Bind,
+    geometry::{GLVec2, GLVec3, NonNormalized},
+    gl::Gl,
     image_format::{ConcreteImageFormat, FormatAttributes, ImageFormatRenderable},
-    ContextState, GLObject, Handle,
+    object_registry::ObjectKind,
+    ContextState, GLError, GLObject, Handle,
 };
 mod raw;
 use self::raw::{RawRenderbuffer, RawRenderbufferTarget};
 
-use std::{marker::PhantomData, rc::Rc};
+use std::{any, marker::PhantomData, rc::Rc};
 
-pub(crate) struct RenderbufferTarget(RawRenderbufferTarget);
+pub(crate) struct RenderbufferTarget(pub(crate) RawRenderbufferTarget);
 
 /// GPU storage optimized for rendering.
 ///
@@ -42,6 +45,12 @@ impl RenderbufferTarget {
     pub(crate) fn new() -> RenderbufferTarget {
         RenderbufferTarget(RawRenderbufferTarget::new())
     }
+
+    /// Binds renderbuffer `0`, regardless of what Gullery thinks is bound.
+    #[inline]
+    pub(crate) fn reset_bind(&self, gl: &Gl) {
+        unsafe { self.0.reset_bind(gl) };
+    }
 }
 
 impl<I: ImageFormatRenderable> Renderbuffer<I> {
@@ -50,11 +59,35 @@ impl<I: ImageFormatRenderable> Renderbuffer<I> {
     /// ## Parameters
     /// * `dims`: The dimensions of the renderbuffer.
     /// * `samples`: The number of samples to use for multisampling to use when rendering to the renderbuffer. TODO: ACCOUNT FOR GL_MAX_SAMPLES
+    ///
+    /// ## Panics
+    /// Panics if OpenGL fails to allocate the renderbuffer's storage. Use [`try_new`] to handle
+    /// allocation failures.
+    ///
+    /// [`try_new`]: ./struct.Renderbuffer.html#method.try_new
     pub fn new(
         dims: GLVec2<u32, NonNormalized>,
         samples: u32,
         state: Rc<ContextState>,
     ) -> Renderbuffer<I>
+    where
+        I: ConcreteImageFormat,
+    {
+        match Self::try_new(dims, samples, state) {
+            Ok(renderbuffer) => renderbuffer,
+            Err(error) => panic!("OpenGL error {} in renderbuffer allocation", error),
+        }
+    }
+
+    /// Create a new `RenderBuffer`, returning an error if OpenGL fails to allocate its storage.
+    ///
+    /// This is usually `GLError::OutOfMemory`, but requesting more samples than the
+    /// implementation supports also gets reported as `GLError::InvalidOperation`.
+    pub fn try_new(
+        dims: GLVec2<u32, NonNormalized>,
+        samples: u32,
+        state: Rc<ContextState>,
+    ) -> Result<Renderbuffer<I>, GLError>
     where
         I: ConcreteImageFormat,
     {
@@ -70,18 +103,38 @@ impl<I: ImageFormatRenderable> Renderbuffer<I> {
             ),
         };
 
-        unsafe {
+        let alloc_result = unsafe {
             let mut bind = state.renderbuffer_target.0.bind_mut(&mut raw, &state.gl);
-            bind.alloc_storage(internal_format, dims, samples);
-        }
+            bind.alloc_storage(internal_format, dims, samples)
+        };
 
-        Renderbuffer {
+        let renderbuffer = Renderbuffer {
             raw,
             samples,
             dims,
             state,
             _format: PhantomData,
-        }
+        };
+        alloc_result?;
+
+        renderbuffer.state.object_registry.track(
+            ObjectKind::Renderbuffer,
+            renderbuffer.raw.handle(),
+            any::type_name::<Self>(),
+            || Some(I::bytes_for_dims(GLVec3::new(dims.x, dims.y, 1)) * samples.max(1) as usize),
+        );
+        Ok(renderbuffer)
+    }
+
+    /// Sets the renderbuffer's debug label, which graphics debuggers and driver debug messages use to
+    /// identify it. Does nothing on the OpenGL side unless the context supports OpenGL 4.3 or
+    /// `GL_KHR_debug`. With the `object-registry` feature, the label also shows up in the
+    /// renderbuffer's [`ObjectInfo`].
+    ///
+    /// [`ObjectInfo`]: ../struct.ObjectInfo.html
+    pub fn set_label(&self, label: &str) {
+        self.state
+            .set_object_label(ObjectKind::Renderbuffer, self.handle(), label);
     }
 
     /// The dimensions of the underlying renderbuffer.
@@ -110,6 +163,9 @@ impl<I: ImageFormatRenderable> GLObject for Renderbuffer<I> {
 
 impl<I: ImageFormatRenderable> Drop for Renderbuffer<I> {
     fn drop(&mut self) {
+        self.state
+            .object_registry
+            .untrack(ObjectKind::Renderbuffer, self.handle());
         unsafe {
             self.raw.delete(&self.state);
         }
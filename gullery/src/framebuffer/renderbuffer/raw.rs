@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use crate::{
+    bind::Bind,
+    error::{ErrorScope, GLError},
     gl::{self, types::*, Gl},
     geometry::{GLVec2, NonNormalized},
     ContextState, Handle,
@@ -55,9 +57,10 @@ impl RawRenderbuffer {
     }
 
     pub unsafe fn delete(&mut self, state: &ContextState) {
-        if state.renderbuffer_target.0.bound_buffer.get() == Some(self.handle) {
-            state.renderbuffer_target.0.reset_bind(&state.gl);
-        }
+        state
+            .renderbuffer_target
+            .0
+            .unbind_handle(self.handle, &state.gl);
         state.gl.DeleteRenderbuffers(1, &self.handle.get());
     }
 }
@@ -77,21 +80,26 @@ impl RawRenderbufferTarget {
         renderbuffer: &'a mut RawRenderbuffer,
         gl: &'a Gl,
     ) -> RawBoundRenderbufferMut<'a> {
-        if self.bound_buffer.get() != Some(renderbuffer.handle) {
-            self.bound_buffer.set(Some(renderbuffer.handle));
-            gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffer.handle.get());
-        }
+        self.bind_handle(Some(renderbuffer.handle), gl);
 
         RawBoundRenderbufferMut {
             gl,
             _marker: PhantomData,
         }
     }
+}
+
+impl Bind for RawRenderbufferTarget {
+    const BINDING: GLenum = gl::RENDERBUFFER_BINDING;
+
+    #[inline]
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_buffer
+    }
 
     #[inline]
-    pub unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_buffer.set(None);
-        gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.BindRenderbuffer(gl::RENDERBUFFER, handle.map(|h| h.get()).unwrap_or(0));
     }
 }
 
@@ -101,7 +109,8 @@ impl<'a> RawBoundRenderbufferMut<'a> {
         internal_format: GLenum,
         dims: GLVec2<u32, NonNormalized>,
         samples: u32,
-    ) {
+    ) -> Result<(), GLError> {
+        let error_scope = ErrorScope::new(self.gl, "glRenderbufferStorageMultisample");
         unsafe {
             self.gl.RenderbufferStorageMultisample(
                 gl::RENDERBUFFER,
@@ -110,7 +119,7 @@ impl<'a> RawBoundRenderbufferMut<'a> {
                 dims.x as GLsizei,
                 dims.y as GLsizei,
             );
-            assert_eq!(0, self.gl.GetError());
         }
+        error_scope.finish()
     }
 }
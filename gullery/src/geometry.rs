@@ -160,8 +160,8 @@ pub trait ScalarBase: 'static + Copy {
 
 /// Scalar that OpenGL can read.
 ///
-/// Implemented for `u8`, `u16`, `u32`, `i8`, `i16`, `i32`, `f32`, `bool`, and [`GLSLInt`]-wrapped
-/// integers.
+/// Implemented for `u8`, `u16`, `u32`, `i8`, `i16`, `i32`, `f32`, `f64`, `bool`, and
+/// [`GLSLInt`]-wrapped integers. `f64` requires OpenGL 4.1.
 ///
 /// [`GLSLInt`]: ./struct.GLSLInt.html
 pub unsafe trait Scalar<N: Normalization>: ScalarBase {
@@ -183,6 +183,10 @@ pub enum GLSLBool {}
 ///
 /// Used in conjunction with [`Scalar::ScalarType`](./trait.Scalar.html#associatedtype.ScalarType)
 pub enum GLSLFloat {}
+/// Marker enum for types GLSL reads as a *double*.
+///
+/// Used in conjunction with [`Scalar::ScalarType`](./trait.Scalar.html#associatedtype.ScalarType)
+pub enum GLSLDouble {}
 /// Marker enum for types GLSL reads as a *signed int*.
 ///
 /// Used in conjunction with [`Scalar::ScalarType`](./trait.Scalar.html#associatedtype.ScalarType)
@@ -223,6 +227,10 @@ unsafe impl ScalarType for GLSLFloat {
     const PRIM_TAG: TypeTagSingle = TypeTagSingle::Float;
     const IS_INTEGER: bool = false;
 }
+unsafe impl ScalarType for GLSLDouble {
+    const PRIM_TAG: TypeTagSingle = TypeTagSingle::Double;
+    const IS_INTEGER: bool = false;
+}
 unsafe impl ScalarType for GLSLBool {
     const PRIM_TAG: TypeTagSingle = TypeTagSingle::Bool;
     const IS_INTEGER: bool = true;
@@ -255,10 +263,10 @@ pub enum TypeTagSingle {
     Vec2 = gl::FLOAT_VEC2,
     Vec3 = gl::FLOAT_VEC3,
     Vec4 = gl::FLOAT_VEC4,
-    // Double = gl::DOUBLE,
-    // Dvec2 = gl::DOUBLE_VEC2,
-    // Dvec3 = gl::DOUBLE_VEC3,
-    // Dvec4 = gl::DOUBLE_VEC4,
+    Double = gl::DOUBLE,
+    DVec2 = gl::DOUBLE_VEC2,
+    DVec3 = gl::DOUBLE_VEC3,
+    DVec4 = gl::DOUBLE_VEC4,
     Int = gl::INT,
     IVec2 = gl::INT_VEC2,
     IVec3 = gl::INT_VEC3,
@@ -280,15 +288,15 @@ pub enum TypeTagSingle {
     Mat3x4 = gl::FLOAT_MAT3x4,
     Mat4x2 = gl::FLOAT_MAT4x2,
     Mat4x3 = gl::FLOAT_MAT4x3,
-    // DMat2 = gl::DOUBLE_MAT2,
-    // DMat3 = gl::DOUBLE_MAT3,
-    // DMat4 = gl::DOUBLE_MAT4,
-    // DMat2x3 = gl::DOUBLE_MAT2x3,
-    // DMat2x4 = gl::DOUBLE_MAT2x4,
-    // DMat3x2 = gl::DOUBLE_MAT3x2,
-    // DMat3x4 = gl::DOUBLE_MAT3x4,
-    // DMat4x2 = gl::DOUBLE_MAT4x2,
-    // DMat4x3 = gl::DOUBLE_MAT4x3,
+    DMat2 = gl::DOUBLE_MAT2,
+    DMat3 = gl::DOUBLE_MAT3,
+    DMat4 = gl::DOUBLE_MAT4,
+    DMat2x3 = gl::DOUBLE_MAT2x3,
+    DMat2x4 = gl::DOUBLE_MAT2x4,
+    DMat3x2 = gl::DOUBLE_MAT3x2,
+    DMat3x4 = gl::DOUBLE_MAT3x4,
+    DMat4x2 = gl::DOUBLE_MAT4x2,
+    DMat4x3 = gl::DOUBLE_MAT4x3,
     Sampler1D = gl::SAMPLER_1D,
     Sampler2D = gl::SAMPLER_2D,
     Sampler3D = gl::SAMPLER_3D,
@@ -521,6 +529,12 @@ macro_rules! matrix_struct {
             #[inline]
             fn prim_tag() -> TypeTagSingle {<f32 as Scalar<NonNormalized>>::ScalarType::PRIM_TAG.matricize($cols, $rows).unwrap()}
         }
+        unsafe impl TransparentType for $Matrix<f64> {
+            type Normalization = NonNormalized;
+            type Scalar = f64;
+            #[inline]
+            fn prim_tag() -> TypeTagSingle {<f64 as Scalar<NonNormalized>>::ScalarType::PRIM_TAG.matricize($cols, $rows).unwrap()}
+        }
     }
 }
 
@@ -630,7 +644,23 @@ impl_gl_scalar_float! {
     impl i16 = (gl::SHORT, Normalized, true);
     impl i32 = (gl::INT, Normalized, true);
     impl f32 = (gl::FLOAT, NonNormalized, true, TransparentType);
-    // impl f64 = (gl::DOUBLE, NonNormalized, true);
+}
+
+impl ScalarBase for f64 {
+    type ImageNormalization = NonNormalized;
+    const GL_ENUM: GLenum = gl::DOUBLE;
+    const SIGNED: bool = true;
+}
+unsafe impl Scalar<NonNormalized> for f64 {
+    type ScalarType = GLSLDouble;
+}
+unsafe impl TransparentType for f64 {
+    type Normalization = NonNormalized;
+    type Scalar = f64;
+    #[inline(always)]
+    fn prim_tag() -> TypeTagSingle {
+        <f64 as Scalar<NonNormalized>>::ScalarType::PRIM_TAG
+    }
 }
 
 macro_rules! impl_gl_scalar_int {
@@ -692,10 +722,10 @@ impl Display for TypeTagSingle {
             Vec2 => "vec2",
             Vec3 => "vec3",
             Vec4 => "vec4",
-            // Double => "double",
-            // Dvec2 => "dvec2",
-            // Dvec3 => "dvec3",
-            // Dvec4 => "dvec4",
+            Double => "double",
+            DVec2 => "dvec2",
+            DVec3 => "dvec3",
+            DVec4 => "dvec4",
             Int => "int",
             IVec2 => "ivec2",
             IVec3 => "ivec3",
@@ -717,15 +747,15 @@ impl Display for TypeTagSingle {
             Mat3x4 => "mat3x4",
             Mat4x2 => "mat4x2",
             Mat4x3 => "mat4x3",
-            // DMat2 => "dmat2",
-            // DMat3 => "dmat3",
-            // DMat4 => "dmat4",
-            // DMat2x3 => "dmat2x3",
-            // DMat2x4 => "dmat2x4",
-            // DMat3x2 => "dmat3x2",
-            // DMat3x4 => "dmat3x4",
-            // DMat4x2 => "dmat4x2",
-            // DMat4x3 => "dmat4x3",
+            DMat2 => "dmat2",
+            DMat3 => "dmat3",
+            DMat4 => "dmat4",
+            DMat2x3 => "dmat2x3",
+            DMat2x4 => "dmat2x4",
+            DMat3x2 => "dmat3x2",
+            DMat3x4 => "dmat3x4",
+            DMat4x2 => "dmat4x2",
+            DMat4x3 => "dmat4x3",
             Sampler1D => "sampler1D",
             Sampler2D => "sampler2D",
             Sampler3D => "sampler3D",
@@ -773,46 +803,46 @@ impl TypeTagSingle {
     pub fn len(self) -> usize {
         use self::TypeTagSingle::*;
         match self {
-            // Double |
+            Double |
             Int   |
             Float |
             UInt  |
             Bool => 1,
 
-            // Dvec2 |
+            DVec2 |
             Vec2  |
             IVec2 |
             UVec2 |
             BVec2 => 2,
 
-            // Dvec3 |
+            DVec3 |
             Vec3  |
             IVec3 |
             UVec3 |
             BVec3 => 3,
 
-            // Dvec4 |
+            DVec4 |
             Vec4  |
             IVec4 |
             UVec4 |
             BVec4 => 4,
 
-            // DMat2 |
+            DMat2 |
             Mat2 => 4,
-            // DMat3 |
+            DMat3 |
             Mat3 => 9,
-            // DMat4 |
+            DMat4 |
             Mat4 => 16,
-            // DMat2x3 |
-            // DMat3x2 |
+            DMat2x3 |
+            DMat3x2 |
             Mat3x2  |
             Mat2x3 => 6,
-            // DMat2x4 |
-            // DMat4x2 |
+            DMat2x4 |
+            DMat4x2 |
             Mat4x2  |
             Mat2x4 => 8,
-            // DMat3x4 |
-            // DMat4x3 |
+            DMat3x4 |
+            DMat4x3 |
             Mat3x4  |
             Mat4x3 => 12,
             Sampler1D |
@@ -858,23 +888,23 @@ impl TypeTagSingle {
     pub fn num_attrib_slots(self) -> usize {
         use self::TypeTagSingle::*;
         match self {
-            // DMat2x3 |
+            DMat2x3 |
             Mat2x3  |
-            // DMat2x4 |
+            DMat2x4 |
             Mat2x4  |
-            // DMat2   |
+            DMat2   |
             Mat2   => 2,
-            // DMat3x2 |
+            DMat3x2 |
             Mat3x2  |
-            // DMat3x4 |
+            DMat3x4 |
             Mat3x4  |
-            // DMat3   |
+            DMat3   |
             Mat3   => 3,
-            // DMat4x2 |
+            DMat4x2 |
             Mat4x2  |
-            // DMat4x3 |
+            DMat4x3 |
             Mat4x3  |
-            // DMat4   |
+            DMat4   |
             Mat4   => 4,
 
             Sampler1D |
@@ -913,10 +943,10 @@ impl TypeTagSingle {
             USampler2DMSArray |
             // USamplerBuffer |
             USampler2DRect |
-            // Double |
-            // Dvec2  |
-            // Dvec3  |
-            // Dvec4  |
+            Double |
+            DVec2  |
+            DVec3  |
+            DVec4  |
             Int    |
             Float  |
             UInt   |
@@ -936,6 +966,56 @@ impl TypeTagSingle {
         }
     }
 
+    /// The number of attribute locations an instance of the represented type takes up.
+    ///
+    /// This is usually the same as `num_attrib_slots`, but `dvec3` and `dvec4` columns are too big
+    /// to fit in a single location and use two consecutive locations each.
+    pub fn num_attrib_locations(self) -> usize {
+        use self::TypeTagSingle::*;
+        match self {
+            DVec3 | DVec4 => 2,
+            DMat2x3 | DMat2x4 => 4,
+            DMat3 | DMat3x4 => 6,
+            DMat4 | DMat4x3 => 8,
+            _ => self.num_attrib_slots(),
+        }
+    }
+
+    /// Whether the represented type is a sampler, which reads from a texture unit.
+    pub fn is_sampler(self) -> bool {
+        use self::TypeTagSingle::*;
+        matches!(
+            self,
+            Sampler1D
+                | Sampler2D
+                | Sampler3D
+                | SamplerCube
+                | Sampler1DArray
+                | Sampler2DArray
+                | Sampler2DMS
+                | Sampler2DMSArray
+                | Sampler2DRect
+                | ISampler1D
+                | ISampler2D
+                | ISampler3D
+                | ISamplerCube
+                | ISampler1DArray
+                | ISampler2DArray
+                | ISampler2DMS
+                | ISampler2DMSArray
+                | ISampler2DRect
+                | USampler1D
+                | USampler2D
+                | USampler3D
+                | USamplerCube
+                | USampler1DArray
+                | USampler2DArray
+                | USampler2DMS
+                | USampler2DMSArray
+                | USampler2DRect
+        )
+    }
+
     /// Turn a scalar tag into a vector tag with the given length.
     ///
     /// Returns `None` if no vector type could be found for the tag with the requested length.
@@ -962,17 +1042,24 @@ impl TypeTagSingle {
             (Bool, 3) => Some(BVec3),
             (Bool, 4) => Some(BVec4),
 
-            // (Double, 1) => Some(DVec1),
-            // (Double, 2) => Some(DVec2),
-            // (Double, 3) => Some(DVec3),
-            // (Double, 4) => Some(DVec4),
+            (Double, 1) => Some(Double),
+            (Double, 2) => Some(DVec2),
+            (Double, 3) => Some(DVec3),
+            (Double, 4) => Some(DVec4),
             _ => None,
         }
     }
 
     /// Turn a scalar tag into a matrix tag with the given dimensions.
     ///
+    /// `width` is the number of columns and `height` is the number of rows, which matches GLSL's
+    /// column-first `matCxR` naming: `Float.matricize(3, 2)` is `Mat3x2`, which has three columns
+    /// of two rows each and gets stored in a [`GLMat2r3c`]. Math libraries write that same matrix
+    /// as `2x3`.
+    ///
     /// Returns `None` if no matrix type could be found for the tag with the requested dimensions.
+    ///
+    /// [`GLMat2r3c`]: ./struct.GLMat2r3c.html
     pub fn matricize(self, width: u8, height: u8) -> Option<TypeTagSingle> {
         use self::TypeTagSingle::*;
         match (self, width, height) {
@@ -985,15 +1072,15 @@ impl TypeTagSingle {
             (Float, 3, 4) => Some(Mat3x4),
             (Float, 4, 2) => Some(Mat4x2),
             (Float, 4, 3) => Some(Mat4x3),
-            // (Double, 2, 2) => Some(DMat2),
-            // (Double, 3, 3) => Some(DMat3),
-            // (Double, 4, 4) => Some(DMat4),
-            // (Double, 2, 3) => Some(DMat2x3),
-            // (Double, 2, 4) => Some(DMat2x4),
-            // (Double, 3, 2) => Some(DMat3x2),
-            // (Double, 3, 4) => Some(DMat3x4),
-            // (Double, 4, 2) => Some(DMat4x2),
-            // (Double, 4, 3) => Some(DMat4x3),
+            (Double, 2, 2) => Some(DMat2),
+            (Double, 3, 3) => Some(DMat3),
+            (Double, 4, 4) => Some(DMat4),
+            (Double, 2, 3) => Some(DMat2x3),
+            (Double, 2, 4) => Some(DMat2x4),
+            (Double, 3, 2) => Some(DMat3x2),
+            (Double, 3, 4) => Some(DMat3x4),
+            (Double, 4, 2) => Some(DMat4x2),
+            (Double, 4, 3) => Some(DMat4x3),
             _ => None,
         }
     }
@@ -1006,10 +1093,10 @@ impl TypeTagSingle {
             gl::FLOAT_VEC2 => Some(Vec2),
             gl::FLOAT_VEC3 => Some(Vec3),
             gl::FLOAT_VEC4 => Some(Vec4),
-            // gl::DOUBLE => Some(Double),
-            // gl::DOUBLE_VEC2 => Some(Dvec2),
-            // gl::DOUBLE_VEC3 => Some(Dvec3),
-            // gl::DOUBLE_VEC4 => Some(Dvec4),
+            gl::DOUBLE => Some(Double),
+            gl::DOUBLE_VEC2 => Some(DVec2),
+            gl::DOUBLE_VEC3 => Some(DVec3),
+            gl::DOUBLE_VEC4 => Some(DVec4),
             gl::INT => Some(Int),
             gl::INT_VEC2 => Some(IVec2),
             gl::INT_VEC3 => Some(IVec3),
@@ -1025,21 +1112,21 @@ impl TypeTagSingle {
             gl::FLOAT_MAT2 => Some(Mat2),
             gl::FLOAT_MAT3 => Some(Mat3),
             gl::FLOAT_MAT4 => Some(Mat4),
-            // gl::FLOAT_MAT2x3 => Some(Mat2x3),
-            // gl::FLOAT_MAT2x4 => Some(Mat2x4),
-            // gl::FLOAT_MAT3x2 => Some(Mat3x2),
-            // gl::FLOAT_MAT3x4 => Some(Mat3x4),
-            // gl::FLOAT_MAT4x2 => Some(Mat4x2),
-            // gl::FLOAT_MAT4x3 => Some(Mat4x3),
-            // gl::DOUBLE_MAT2 => Some(DMat2),
-            // gl::DOUBLE_MAT3 => Some(DMat3),
-            // gl::DOUBLE_MAT4 => Some(DMat4),
-            // gl::DOUBLE_MAT2x3 => Some(DMat2x3),
-            // gl::DOUBLE_MAT2x4 => Some(DMat2x4),
-            // gl::DOUBLE_MAT3x2 => Some(DMat3x2),
-            // gl::DOUBLE_MAT3x4 => Some(DMat3x4),
-            // gl::DOUBLE_MAT4x2 => Some(DMat4x2),
-            // gl::DOUBLE_MAT4x3 => Some(DMat4x3),
+            gl::FLOAT_MAT2x3 => Some(Mat2x3),
+            gl::FLOAT_MAT2x4 => Some(Mat2x4),
+            gl::FLOAT_MAT3x2 => Some(Mat3x2),
+            gl::FLOAT_MAT3x4 => Some(Mat3x4),
+            gl::FLOAT_MAT4x2 => Some(Mat4x2),
+            gl::FLOAT_MAT4x3 => Some(Mat4x3),
+            gl::DOUBLE_MAT2 => Some(DMat2),
+            gl::DOUBLE_MAT3 => Some(DMat3),
+            gl::DOUBLE_MAT4 => Some(DMat4),
+            gl::DOUBLE_MAT2x3 => Some(DMat2x3),
+            gl::DOUBLE_MAT2x4 => Some(DMat2x4),
+            gl::DOUBLE_MAT3x2 => Some(DMat3x2),
+            gl::DOUBLE_MAT3x4 => Some(DMat3x4),
+            gl::DOUBLE_MAT4x2 => Some(DMat4x2),
+            gl::DOUBLE_MAT4x3 => Some(DMat4x3),
             gl::SAMPLER_1D => Some(Sampler1D),
             gl::SAMPLER_2D => Some(Sampler2D),
             gl::SAMPLER_3D => Some(Sampler3D),
@@ -1101,3 +1188,43 @@ impl<S: Copy + Add<Output = S> + Sub<Output = S> + Scalar<NonNormalized>> Dimens
 impl<S: Copy + Add<Output = S> + Sub<Output = S> + Scalar<NonNormalized>> Dimension<S> for D3 {
     type Vector = GLVec3<S, NonNormalized>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_square_matrix_tags() {
+        use self::TypeTagSingle::*;
+
+        // `GLMat{R}r{C}c` has R rows and C columns, which GLSL calls `mat{C}x{R}`.
+        let float_tags = [
+            (GLMat2r3c::<f32>::prim_tag(), Mat3x2, "mat3x2", 6, 3),
+            (GLMat2r4c::<f32>::prim_tag(), Mat4x2, "mat4x2", 8, 4),
+            (GLMat3r2c::<f32>::prim_tag(), Mat2x3, "mat2x3", 6, 2),
+            (GLMat3r4c::<f32>::prim_tag(), Mat4x3, "mat4x3", 12, 4),
+            (GLMat4r2c::<f32>::prim_tag(), Mat2x4, "mat2x4", 8, 2),
+            (GLMat4r3c::<f32>::prim_tag(), Mat3x4, "mat3x4", 12, 3),
+        ];
+        let double_tags = [
+            (GLMat2r3c::<f64>::prim_tag(), DMat3x2, "dmat3x2", 6, 3),
+            (GLMat2r4c::<f64>::prim_tag(), DMat4x2, "dmat4x2", 8, 4),
+            (GLMat3r2c::<f64>::prim_tag(), DMat2x3, "dmat2x3", 6, 2),
+            (GLMat3r4c::<f64>::prim_tag(), DMat4x3, "dmat4x3", 12, 4),
+            (GLMat4r2c::<f64>::prim_tag(), DMat2x4, "dmat2x4", 8, 2),
+            (GLMat4r3c::<f64>::prim_tag(), DMat3x4, "dmat3x4", 12, 3),
+        ];
+        for &(tag, expected, name, len, columns) in float_tags.iter().chain(&double_tags) {
+            assert_eq!(expected, tag);
+            assert_eq!(name, tag.to_string());
+            assert_eq!(len, tag.len());
+            assert_eq!(columns, tag.num_attrib_slots());
+            assert_eq!(Some(tag), TypeTagSingle::from_gl_enum(GLenum::from(tag)));
+        }
+
+        assert_eq!(Some(Mat3x2), Float.matricize(3, 2));
+        assert_eq!(Some(Mat2x3), Float.matricize(2, 3));
+        assert_eq!(Some(DMat4x3), Double.matricize(4, 3));
+        assert_eq!(None, Int.matricize(2, 3));
+    }
+}
@@ -33,7 +33,7 @@
 pub mod compressed;
 
 use crate::gl::{self, types::*};
-use std::marker::PhantomData;
+use std::{error::Error, fmt, marker::PhantomData, mem};
 
 use crate::geometry::*;
 
@@ -42,8 +42,8 @@ use crate::geometry::*;
 pub enum FormatTypeTag {
     Color,
     Depth,
-    // Stencil,
-    // DepthStencil
+    Stencil,
+    DepthStencil,
 }
 
 /// Attributes used by OpenGL to process and display images.
@@ -80,6 +80,118 @@ pub enum FormatAttributes {
     },
 }
 
+impl FormatAttributes {
+    /// The format the GPU uses internally to store pixel data.
+    #[inline]
+    pub fn internal_format(&self) -> GLenum {
+        match *self {
+            FormatAttributes::Uncompressed {
+                internal_format, ..
+            }
+            | FormatAttributes::Compressed {
+                internal_format, ..
+            } => internal_format,
+        }
+    }
+
+    /// Whether the format stores depth or stencil values.
+    #[inline]
+    fn is_depth_stencil(&self) -> bool {
+        match *self {
+            FormatAttributes::Uncompressed { pixel_format, .. } => matches!(
+                pixel_format,
+                gl::DEPTH_COMPONENT | gl::DEPTH_STENCIL | gl::STENCIL_INDEX
+            ),
+            FormatAttributes::Compressed { .. } => false,
+        }
+    }
+}
+
+/// A way an image format can be used, for checking support with [`ContextState::supports_format`].
+///
+/// [`ContextState::supports_format`]: ../struct.ContextState.html#method.supports_format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatUsage {
+    /// Storing images in a 2D texture.
+    Texture,
+    /// Storing images in a renderbuffer.
+    Renderbuffer,
+    /// Attaching a 2D texture of the format to a framebuffer and rendering to it.
+    Renderable,
+}
+
+/// Error returned when two image formats can't be blitted or copied between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatMismatch {
+    pub src_internal_format: GLenum,
+    pub dst_internal_format: GLenum,
+}
+
+/// Checks that images of format `S` can be blitted to images of format `D`, as with
+/// `glBlitFramebuffer`.
+///
+/// Color formats can be blitted between if they're both floating-point (or normalized), both
+/// signed integer, or both unsigned integer. Depth and stencil formats have to match exactly.
+pub fn check_blit_compatible<S, D>() -> Result<(), FormatMismatch>
+where
+    S: ImageFormatRenderable + ConcreteImageFormat,
+    D: ImageFormatRenderable + ConcreteImageFormat,
+{
+    let compatible = match (
+        <S::FormatType as FormatType>::FORMAT_TYPE,
+        <D::FormatType as FormatType>::FORMAT_TYPE,
+    ) {
+        (FormatTypeTag::Color, FormatTypeTag::Color) => {
+            S::ScalarType::PRIM_TAG == D::ScalarType::PRIM_TAG
+        }
+        _ => S::FORMAT.internal_format() == D::FORMAT.internal_format(),
+    };
+    format_mismatch::<S, D>(compatible)
+}
+
+/// Checks that images of format `S` can be copied to images of format `D`, as with
+/// `glCopyImageSubData`.
+///
+/// Formats are copy-compatible if they're the same, or if neither is a depth or stencil format and
+/// their pixels (or compressed blocks) are the same size.
+pub fn check_copy_compatible<S, D>() -> Result<(), FormatMismatch>
+where
+    S: ConcreteImageFormat,
+    D: ConcreteImageFormat,
+{
+    let compatible = S::FORMAT.internal_format() == D::FORMAT.internal_format()
+        || (!S::FORMAT.is_depth_stencil()
+            && !D::FORMAT.is_depth_stencil()
+            && S::BYTES_PER_PIXEL == D::BYTES_PER_PIXEL);
+    format_mismatch::<S, D>(compatible)
+}
+
+fn format_mismatch<S, D>(compatible: bool) -> Result<(), FormatMismatch>
+where
+    S: ConcreteImageFormat,
+    D: ConcreteImageFormat,
+{
+    match compatible {
+        true => Ok(()),
+        false => Err(FormatMismatch {
+            src_internal_format: S::FORMAT.internal_format(),
+            dst_internal_format: D::FORMAT.internal_format(),
+        }),
+    }
+}
+
+impl Error for FormatMismatch {}
+
+impl fmt::Display for FormatMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "incompatible image formats; can't transfer from internal format {:#x} to {:#x}",
+            self.src_internal_format, self.dst_internal_format
+        )
+    }
+}
+
 /// An image format the GPU can use to look up pixel data.
 pub unsafe trait ImageFormat: 'static {
     type ScalarType: ScalarType;
@@ -99,6 +211,15 @@ fn next_multiple_of(u: u32, m: u32) -> u32 {
 
 pub unsafe trait ConcreteImageFormat: ImageFormat + Copy {
     const FORMAT: FormatAttributes;
+    /// The size of a single pixel, in bytes. For compressed formats, this is the size of a single
+    /// compressed block.
+    ///
+    /// Pixel data gets transferred straight from slices of the format type, so this is the size
+    /// of the type rather than the channel count times the scalar size. Packed formats, where
+    /// several channels share a scalar (e.g. a 16-bit RGB565 pixel), get the packed size as long
+    /// as the type is the packed size.
+    const BYTES_PER_PIXEL: usize = mem::size_of::<Self>();
+
     fn blocks_for_dims(dims: GLVec3<u32, NonNormalized>) -> usize {
         let (x_mult, y_mult, z_mult) = match Self::FORMAT {
             FormatAttributes::Uncompressed { .. } => (1, 1, 1),
@@ -111,22 +232,92 @@ pub unsafe trait ConcreteImageFormat: ImageFormat + Copy {
             * next_multiple_of(dims.z, z_mult))
             / (x_mult * y_mult * z_mult)) as usize
     }
+
+    /// The number of bytes needed to store an image with the given dimensions.
+    fn bytes_for_dims(dims: GLVec3<u32, NonNormalized>) -> usize {
+        Self::blocks_for_dims(dims) * Self::BYTES_PER_PIXEL
+    }
+}
+
+/// Byte ordering, bit ordering and layout used when transferring pixel data between the CPU and
+/// GPU.
+///
+/// The default value matches OpenGL's defaults, which is native byte order with the most
+/// significant bit first and tightly packed image layers. Use [`ContextState::with_pixel_store`] to apply a different ordering to
+/// a set of transfers.
+///
+/// Reordering gets done by the driver on the CPU, so non-default settings can make uploads and
+/// downloads significantly slower. Prefer converting data ahead of time if it gets transferred
+/// frequently.
+///
+/// [`ContextState::with_pixel_store`]: ../struct.ContextState.html#method.with_pixel_store
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelStore {
+    /// Swap the byte order of multi-byte pixel components. Maps to `GL_PACK_SWAP_BYTES` and
+    /// `GL_UNPACK_SWAP_BYTES`.
+    pub swap_bytes: bool,
+    /// Read bits within a byte starting from the least significant bit. Only affects 1-bit pixel
+    /// data. Maps to `GL_PACK_LSB_FIRST` and `GL_UNPACK_LSB_FIRST`.
+    pub lsb_first: bool,
+    /// The number of rows between the starts of consecutive layers in 3D and array image data.
+    /// `0` means the layers are tightly packed, with a stride equal to the image's height. Maps
+    /// to `GL_PACK_IMAGE_HEIGHT` and `GL_UNPACK_IMAGE_HEIGHT`.
+    ///
+    /// This lets a sub-volume get uploaded from a slice containing a larger volume. Uploads
+    /// validate the slice's length against this stride.
+    pub image_height: u32,
+}
+
+impl PixelStore {
+    /// Applies the ordering to both pixel packing (downloads) and unpacking (uploads).
+    #[inline]
+    pub(crate) unsafe fn apply(&self, gl: &gl::Gl) {
+        let swap_bytes = self.swap_bytes as GLint;
+        let lsb_first = self.lsb_first as GLint;
+        gl.PixelStorei(gl::PACK_SWAP_BYTES, swap_bytes);
+        gl.PixelStorei(gl::UNPACK_SWAP_BYTES, swap_bytes);
+        gl.PixelStorei(gl::PACK_LSB_FIRST, lsb_first);
+        gl.PixelStorei(gl::UNPACK_LSB_FIRST, lsb_first);
+        gl.PixelStorei(gl::PACK_IMAGE_HEIGHT, self.image_height as GLint);
+        gl.PixelStorei(gl::UNPACK_IMAGE_HEIGHT, self.image_height as GLint);
+    }
 }
 
 /// Marker trait used to indicate if a format is a color, depth, or stencil format.
 pub trait FormatType {
     const FORMAT_TYPE: FormatTypeTag;
+    /// Whether the format stores color in the sRGB color space.
+    ///
+    /// When `GL_FRAMEBUFFER_SRGB` is enabled, the GPU converts shader output from linear space
+    /// to sRGB before writing to images of these formats.
+    const IS_SRGB: bool = false;
 }
 /// Marker type that indicates a color image format.
 pub enum ColorFormat {}
+/// Marker type that indicates a color image format stored in the sRGB color space.
+pub enum SRgbColorFormat {}
 /// Marker type that indicates a depth image format.
 pub enum DepthFormat {}
+/// Marker type that indicates a stencil image format.
+pub enum StencilFormat {}
+/// Marker type that indicates a combined depth-stencil image format.
+pub enum DepthStencilFormat {}
 impl FormatType for ColorFormat {
     const FORMAT_TYPE: FormatTypeTag = FormatTypeTag::Color;
 }
+impl FormatType for SRgbColorFormat {
+    const FORMAT_TYPE: FormatTypeTag = FormatTypeTag::Color;
+    const IS_SRGB: bool = true;
+}
 impl FormatType for DepthFormat {
     const FORMAT_TYPE: FormatTypeTag = FormatTypeTag::Depth;
 }
+impl FormatType for StencilFormat {
+    const FORMAT_TYPE: FormatTypeTag = FormatTypeTag::Stencil;
+}
+impl FormatType for DepthStencilFormat {
+    const FORMAT_TYPE: FormatTypeTag = FormatTypeTag::DepthStencil;
+}
 
 pub trait ColorComponents {
     type Normalization: Normalization;
@@ -137,16 +328,42 @@ pub trait ColorComponents {
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Depth16(pub u16);
-// #[repr(C)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-// pub struct Depth24(pub u32);
+/// 24-bit unsigned depth format.
+///
+/// Depth values are uploaded as 32-bit unsigned integers, with `u32::MAX` as the far plane.
+/// OpenGL only keeps the highest 24 bits.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Depth24(pub u32);
 /// 32-bit floating-point depth format.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Depth32F(pub f32);
-// #[repr(C)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-// pub struct Depth24Stencil8(pub u32);
+/// 24-bit unsigned depth format, combined with an 8-bit stencil format.
+///
+/// The depth value is stored in the highest 24 bits and the stencil value in the lowest 8 bits.
+/// This is the same layout as the values returned by [`Framebuffer::read_depth_stencil`].
+///
+/// [`Framebuffer::read_depth_stencil`]: ../framebuffer/trait.Framebuffer.html#method.read_depth_stencil
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Depth24Stencil8(pub u32);
+/// 8-bit unsigned stencil format.
+///
+/// Renderbuffers can always use this format, but textures require OpenGL 4.4 or
+/// `GL_ARB_texture_stencil8`. See [`ContextState::texture_stencil8_supported`].
+///
+/// [`ContextState::texture_stencil8_supported`]: ../struct.ContextState.html#method.texture_stencil8_supported
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stencil8(pub u8);
+/// 32-bit floating-point depth format, combined with an 8-bit stencil format.
+///
+/// The first field is the depth value. The stencil value is stored in the lowest 8 bits of the
+/// second field; the remaining 24 bits are unused.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Depth32FStencil8(pub f32, pub u32);
 
 unsafe impl ImageFormat for Depth16 {
     type ScalarType = GLSLFloat;
@@ -162,6 +379,20 @@ unsafe impl ConcreteImageFormat for Depth16 {
     };
 }
 
+unsafe impl ImageFormat for Depth24 {
+    type ScalarType = GLSLFloat;
+}
+unsafe impl ImageFormatRenderable for Depth24 {
+    type FormatType = DepthFormat;
+}
+unsafe impl ConcreteImageFormat for Depth24 {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::DEPTH_COMPONENT24,
+        pixel_format: gl::DEPTH_COMPONENT,
+        pixel_type: <u32 as ScalarBase>::GL_ENUM,
+    };
+}
+
 unsafe impl ImageFormat for Depth32F {
     type ScalarType = GLSLFloat;
 }
@@ -176,6 +407,48 @@ unsafe impl ConcreteImageFormat for Depth32F {
     };
 }
 
+unsafe impl ImageFormat for Stencil8 {
+    type ScalarType = GLSLIntUnsigned;
+}
+unsafe impl ImageFormatRenderable for Stencil8 {
+    type FormatType = StencilFormat;
+}
+unsafe impl ConcreteImageFormat for Stencil8 {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::STENCIL_INDEX8,
+        pixel_format: gl::STENCIL_INDEX,
+        pixel_type: <u8 as ScalarBase>::GL_ENUM,
+    };
+}
+
+unsafe impl ImageFormat for Depth24Stencil8 {
+    type ScalarType = GLSLFloat;
+}
+unsafe impl ImageFormatRenderable for Depth24Stencil8 {
+    type FormatType = DepthStencilFormat;
+}
+unsafe impl ConcreteImageFormat for Depth24Stencil8 {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::DEPTH24_STENCIL8,
+        pixel_format: gl::DEPTH_STENCIL,
+        pixel_type: gl::UNSIGNED_INT_24_8,
+    };
+}
+
+unsafe impl ImageFormat for Depth32FStencil8 {
+    type ScalarType = GLSLFloat;
+}
+unsafe impl ImageFormatRenderable for Depth32FStencil8 {
+    type FormatType = DepthStencilFormat;
+}
+unsafe impl ConcreteImageFormat for Depth32FStencil8 {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::DEPTH32F_STENCIL8,
+        pixel_format: gl::DEPTH_STENCIL,
+        pixel_type: gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+    };
+}
+
 /// Linear four-channel RGBA color format.
 ///
 /// If you want GLSL to take normalized integer or floating point data, `S` can be `u8`,
@@ -273,6 +546,25 @@ pub struct SRgb {
     pub b: u8,
 }
 
+/// Packed four-channel RGBA color format, with 10 bits for each color channel and 2 bits for
+/// alpha.
+///
+/// Red is stored in the lowest 10 bits, followed by green, blue, and alpha in the highest 2 bits.
+/// GLSL reads the channels as normalized floating-point data.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb10A2(pub u32);
+
+/// Packed four-channel RGBA color format, with 10 bits for each color channel and 2 bits for
+/// alpha.
+///
+/// The bit layout is the same as [`Rgb10A2`], but GLSL reads the channels as unsigned integers.
+///
+/// [`Rgb10A2`]: ./struct.Rgb10A2.html
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb10A2UI(pub u32);
+
 macro_rules! impl_color {
     ($(impl $name:ident<S>($len:expr, color: $($channel:ident),+);)*) => {$(
         impl<S: Scalar<N>, N: Normalization> $name<S, N> {
@@ -303,6 +595,20 @@ impl SRgba {
         SRgba { r, g, b, a }
     }
 
+    /// Decodes the color into linear space. Alpha isn't gamma-encoded, so it just gets
+    /// normalized.
+    ///
+    /// This is the same conversion the GPU applies when sampling sRGB textures, done on the CPU.
+    #[inline]
+    pub fn to_linear(self) -> Rgba<f32> {
+        Rgba::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a as f32 / 255.0,
+        )
+    }
+
     impl_slice_conversions!(u8);
 }
 
@@ -312,9 +618,145 @@ impl SRgb {
         SRgb { r, g, b }
     }
 
+    /// Decodes the color into linear space.
+    ///
+    /// This is the same conversion the GPU applies when sampling sRGB textures, done on the CPU.
+    #[inline]
+    pub fn to_linear(self) -> Rgb<f32> {
+        Rgb::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+        )
+    }
+
     impl_slice_conversions!(u8);
 }
 
+impl Depth24Stencil8 {
+    /// Packs a depth and stencil value into a single pixel.
+    ///
+    /// # Panics
+    /// Panics if `depth` is greater than `0xFFFFFF`.
+    #[inline]
+    pub fn new(depth: u32, stencil: u8) -> Self {
+        assert!(
+            depth <= 0xFF_FFFF,
+            "depth must be in 0..=0xFFFFFF; got {:#X}",
+            depth
+        );
+        Depth24Stencil8(depth << 8 | stencil as u32)
+    }
+
+    #[inline]
+    pub fn depth(self) -> u32 {
+        self.0 >> 8
+    }
+
+    #[inline]
+    pub fn stencil(self) -> u8 {
+        self.0 as u8
+    }
+
+    impl_slice_conversions!(u32);
+}
+
+macro_rules! impl_rgb10_a2 {
+    ($($name:ident),*) => {$(
+        impl $name {
+            /// Packs the channels into a single pixel.
+            ///
+            /// # Panics
+            /// Panics if `r`, `g`, or `b` is greater than `1023`, or if `a` is greater than `3`.
+            #[inline]
+            pub fn new(r: u16, g: u16, b: u16, a: u8) -> Self {
+                assert!(
+                    r <= 0x3FF && g <= 0x3FF && b <= 0x3FF,
+                    "color channels must be in 0..=1023; got ({}, {}, {})",
+                    r, g, b
+                );
+                assert!(a <= 0x3, "alpha channel must be in 0..=3; got {}", a);
+                $name(r as u32 | (g as u32) << 10 | (b as u32) << 20 | (a as u32) << 30)
+            }
+
+            #[inline]
+            pub fn r(self) -> u16 {
+                (self.0 & 0x3FF) as u16
+            }
+
+            #[inline]
+            pub fn g(self) -> u16 {
+                (self.0 >> 10 & 0x3FF) as u16
+            }
+
+            #[inline]
+            pub fn b(self) -> u16 {
+                (self.0 >> 20 & 0x3FF) as u16
+            }
+
+            #[inline]
+            pub fn a(self) -> u8 {
+                (self.0 >> 30) as u8
+            }
+
+            /// Unpacks the pixel into its `(r, g, b, a)` channels.
+            #[inline]
+            pub fn unpack(self) -> (u16, u16, u16, u8) {
+                (self.r(), self.g(), self.b(), self.a())
+            }
+
+            impl_slice_conversions!(u32);
+        }
+    )*};
+}
+
+impl_rgb10_a2!(Rgb10A2, Rgb10A2UI);
+
+impl Rgba<f32> {
+    /// Encodes the linear color into sRGB space. Channels outside of `0.0..=1.0` get clamped, and
+    /// alpha is stored without gamma encoding.
+    #[inline]
+    pub fn to_srgb(self) -> SRgba {
+        SRgba::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            (self.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+}
+
+impl Rgb<f32> {
+    /// Encodes the linear color into sRGB space. Channels outside of `0.0..=1.0` get clamped.
+    #[inline]
+    pub fn to_srgb(self) -> SRgb {
+        SRgb::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+        )
+    }
+}
+
+/// The sRGB electro-optical transfer function, mapping an encoded channel to linear intensity.
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// The inverse of `srgb_to_linear`.
+fn linear_to_srgb(channel: f32) -> u8 {
+    let c = channel.clamp(0.0, 1.0);
+    let encoded = match c <= 0.003_130_8 {
+        true => c * 12.92,
+        false => 1.055 * c.powf(1.0 / 2.4) - 0.055,
+    };
+    (encoded * 255.0).round() as u8
+}
+
 impl<S: ScalarNum<N>, N: Normalization> From<Rgb<S, N>> for Rgba<S, N> {
     #[inline]
     fn from(color: Rgb<S, N>) -> Rgba<S, N> {
@@ -512,7 +954,7 @@ unsafe impl ImageFormat for SRgba {
     type ScalarType = GLSLFloat;
 }
 unsafe impl ImageFormatRenderable for SRgba {
-    type FormatType = ColorFormat;
+    type FormatType = SRgbColorFormat;
 }
 unsafe impl ConcreteImageFormat for SRgba {
     const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
@@ -529,7 +971,7 @@ unsafe impl ImageFormat for SRgb {
     type ScalarType = GLSLFloat;
 }
 unsafe impl ImageFormatRenderable for SRgb {
-    type FormatType = ColorFormat;
+    type FormatType = SRgbColorFormat;
 }
 unsafe impl ConcreteImageFormat for SRgb {
     const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
@@ -556,3 +998,73 @@ unsafe impl ConcreteImageFormat for Bgra<u8, Normalized> {
         pixel_type: <u8 as ScalarBase>::GL_ENUM,
     };
 }
+
+unsafe impl ImageFormat for Rgb10A2 {
+    type ScalarType = GLSLFloat;
+}
+unsafe impl ImageFormatRenderable for Rgb10A2 {
+    type FormatType = ColorFormat;
+}
+unsafe impl ConcreteImageFormat for Rgb10A2 {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::RGB10_A2,
+        pixel_format: gl::RGBA,
+        pixel_type: gl::UNSIGNED_INT_2_10_10_10_REV,
+    };
+}
+
+unsafe impl ImageFormat for Rgb10A2UI {
+    type ScalarType = GLSLIntUnsigned;
+}
+unsafe impl ImageFormatRenderable for Rgb10A2UI {
+    type FormatType = ColorFormat;
+}
+unsafe impl ConcreteImageFormat for Rgb10A2UI {
+    const FORMAT: FormatAttributes = FormatAttributes::Uncompressed {
+        internal_format: gl::RGB10_A2UI,
+        pixel_format: gl::RGBA_INTEGER,
+        pixel_type: gl::UNSIGNED_INT_2_10_10_10_REV,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_linear_round_trip() {
+        for i in 0..=255 {
+            let color = SRgba::new(i, i, i, i);
+            assert_eq!(color, color.to_linear().to_srgb());
+        }
+        assert_eq!(Rgb::new(0.0, 1.0, 0.0), SRgb::new(0, 255, 0).to_linear());
+        assert!((SRgb::new(188, 0, 0).to_linear().r - 0.5).abs() < 0.005);
+        assert_eq!(SRgb::new(0, 255, 0), Rgb::new(-1.0, 2.0, 0.0).to_srgb());
+    }
+
+    #[test]
+    fn rgb10_a2_packing() {
+        let color = Rgb10A2::new(1023, 0, 512, 3);
+        assert_eq!(0xE000_03FF, color.0);
+        assert_eq!((1023, 0, 512, 3), color.unpack());
+
+        let raw = [Rgb10A2UI::new(1, 2, 3, 0).0, Rgb10A2UI::new(4, 5, 6, 1).0];
+        let colors = Rgb10A2UI::from_raw_slice(&raw);
+        assert_eq!((4, 5, 6, 1), colors[1].unpack());
+        assert_eq!(&raw, Rgb10A2UI::to_raw_slice(colors));
+    }
+
+    #[test]
+    fn depth24_stencil8_packing() {
+        let depth_stencil = Depth24Stencil8::new(0xAB_CDEF, 0x5A);
+        assert_eq!(0xABCD_EF5A, depth_stencil.0);
+        assert_eq!(0xAB_CDEF, depth_stencil.depth());
+        assert_eq!(0x5A, depth_stencil.stencil());
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha channel must be in 0..=3")]
+    fn rgb10_a2_alpha_out_of_range() {
+        Rgb10A2::new(0, 0, 0, 4);
+    }
+}
@@ -29,6 +29,10 @@ extern crate glutin;
 
 #[macro_use]
 mod macros;
+mod bind;
+mod error;
+mod object_registry;
+mod state_scope;
 pub mod buffer;
 pub mod framebuffer;
 pub mod geometry;
@@ -38,9 +42,25 @@ pub mod texture;
 pub mod uniform;
 pub mod vertex;
 
-use crate::gl::Gl;
+pub use self::{error::GLError, state_scope::StateScope};
+#[cfg(feature = "object-registry")]
+pub use self::object_registry::{ObjectInfo, ObjectKind};
 
-use std::{cell::Cell, ops::Bound, num::NonZeroU32, rc::Rc};
+use crate::{
+    bind::Bind,
+    geometry::{GLVec2, NonNormalized},
+    gl::{types::*, Gl},
+};
+
+use std::{
+    any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::CStr,
+    num::NonZeroU32,
+    ops::{Bound, RangeInclusive},
+    rc::{Rc, Weak},
+};
 
 pub type Handle = NonZeroU32;
 pub trait GLObject {
@@ -72,19 +92,99 @@ impl<'a, O: GLObject> GLObject for &'a mut O {
     }
 }
 
+bitflags! {
+    /// The kinds of memory accesses that get synchronized by [`ContextState::memory_barrier`].
+    ///
+    /// Each flag names the way writes get *read afterwards*, not the way they got written. For
+    /// example, if a shader stores to an image that later gets sampled as a texture, use
+    /// `TEXTURE_FETCH`.
+    ///
+    /// [`ContextState::memory_barrier`]: ./struct.ContextState.html#method.memory_barrier
+    pub struct MemoryBarrier: u32 {
+        /// Vertex attributes sourced from buffers.
+        const VERTEX_ATTRIB_ARRAY = gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT;
+        /// Indices sourced from element buffers.
+        const ELEMENT_ARRAY = gl::ELEMENT_ARRAY_BARRIER_BIT;
+        /// Uniforms sourced from buffers.
+        const UNIFORM = gl::UNIFORM_BARRIER_BIT;
+        /// Texture fetches, including from buffer textures.
+        const TEXTURE_FETCH = gl::TEXTURE_FETCH_BARRIER_BIT;
+        /// Image loads, stores and atomics in shaders.
+        const SHADER_IMAGE_ACCESS = gl::SHADER_IMAGE_ACCESS_BARRIER_BIT;
+        /// Indirect draw and dispatch commands sourced from buffers.
+        const COMMAND = gl::COMMAND_BARRIER_BIT;
+        /// Pixel transfers to and from buffers.
+        const PIXEL_BUFFER = gl::PIXEL_BUFFER_BARRIER_BIT;
+        /// Texture uploads, downloads and copies.
+        const TEXTURE_UPDATE = gl::TEXTURE_UPDATE_BARRIER_BIT;
+        /// Buffer uploads, downloads, copies and mappings.
+        const BUFFER_UPDATE = gl::BUFFER_UPDATE_BARRIER_BIT;
+        /// Framebuffer reads and writes.
+        const FRAMEBUFFER = gl::FRAMEBUFFER_BARRIER_BIT;
+        /// Transform feedback writes to buffers.
+        const TRANSFORM_FEEDBACK = gl::TRANSFORM_FEEDBACK_BARRIER_BIT;
+        /// Atomic counter buffer accesses.
+        const ATOMIC_COUNTER = gl::ATOMIC_COUNTER_BARRIER_BIT;
+        /// Shader storage buffer accesses.
+        const SHADER_STORAGE = gl::SHADER_STORAGE_BARRIER_BIT;
+        /// Client accesses to persistently-mapped buffers.
+        const CLIENT_MAPPED_BUFFER = gl::CLIENT_MAPPED_BUFFER_BARRIER_BIT;
+        /// Query result buffer accesses.
+        const QUERY_BUFFER = gl::QUERY_BUFFER_BARRIER_BIT;
+    }
+}
+
+thread_local! {
+    /// The live `ContextState`s created with `ContextState::for_context`, keyed by context ID.
+    static CONTEXT_STATES: RefCell<HashMap<usize, Weak<ContextState>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Gullery's view of an OpenGL context's state.
+///
+/// `ContextState` caches which objects are bound to the context, so that redundant binds can be
+/// skipped. The cache is only correct if every call touching the context goes through the same
+/// `ContextState`: creating two for the same context gives them separate caches, which fall out
+/// of sync as soon as one of them binds something, and the other then skips binds it still needs.
+/// There must only ever be one `ContextState` per context, which can be shared with `Rc::clone`.
+///
+/// [`for_context`] enforces this by returning the existing `ContextState` if one has already been
+/// created for the context.
+///
+/// [`for_context`]: ./struct.ContextState.html#method.for_context
 pub struct ContextState {
     buffer_binds: buffer::BufferBinds,
     program_target: program::ProgramTarget,
+    program_pipeline_target: program::ProgramPipelineTarget,
     vao_target: vertex::vao::VAOTarget,
     framebuffer_targets: framebuffer::FramebufferTargets,
     default_framebuffer_exists: Cell<bool>,
     render_state: Cell<framebuffer::render_state::RenderState>,
     image_units: texture::ImageUnits,
     renderbuffer_target: framebuffer::renderbuffer::RenderbufferTarget,
+    srgb_blend_warning: Cell<bool>,
+    context_lost: Cell<bool>,
+    provoking_vertex: Cell<framebuffer::render_state::ProvokingVertex>,
+    sample_mask: Cell<Option<u32>>,
+    clear_color: Cell<image_format::Rgba<f32>>,
+    clear_depth: Cell<f32>,
+    clear_stencil: Cell<GLint>,
+    object_registry: object_registry::ObjectRegistry,
+    direct_state_access_supported: bool,
+    debug_label_supported: bool,
     gl: Gl,
 }
 
 impl ContextState {
+    /// Creates the state for the context that's current on this thread, loading OpenGL functions
+    /// with `load_fn`.
+    ///
+    /// ## Safety
+    /// The context must be current for as long as the `ContextState` and its objects are used,
+    /// and this mustn't be called more than once per context. See [`for_context`] for a version
+    /// that checks the latter.
+    ///
+    /// [`for_context`]: ./struct.ContextState.html#method.for_context
     pub unsafe fn new<F: Fn(&str) -> *const ()>(load_fn: F) -> Rc<ContextState> {
         let gl = Gl::load_with(|s| load_fn(s) as *const _);
 
@@ -108,18 +208,851 @@ impl ContextState {
         //     gl.DebugMessageCallback(debug_callback, 0 as *mut _);
         // }
 
+        // Checked once here, since binding textures for a draw call has to know whether it can
+        // use `glBindTextures` and walking the extension list every draw would be too slow. The
+        // same goes for picking DSA paths in commonly-called functions like mipmap generation.
+        let multi_bind_supported =
+            gl_version(&gl) >= (4, 4) || supports_extension(&gl, "GL_ARB_multi_bind");
+        let direct_state_access_supported =
+            gl_version(&gl) >= (4, 5) || supports_extension(&gl, "GL_ARB_direct_state_access");
+        let debug_label_supported =
+            gl_version(&gl) >= (4, 3) || supports_extension(&gl, "GL_KHR_debug");
+
         Rc::new(ContextState {
             buffer_binds: buffer::BufferBinds::new(),
             program_target: program::ProgramTarget::new(),
+            program_pipeline_target: program::ProgramPipelineTarget::new(),
             vao_target: vertex::vao::VAOTarget::new(),
             framebuffer_targets: framebuffer::FramebufferTargets::new(),
             default_framebuffer_exists: Cell::new(false),
             render_state: Cell::new(framebuffer::render_state::RenderState::default()),
-            image_units: texture::ImageUnits::new(&gl),
+            image_units: texture::ImageUnits::new(&gl, multi_bind_supported),
             renderbuffer_target: framebuffer::renderbuffer::RenderbufferTarget::new(),
+            srgb_blend_warning: Cell::new(false),
+            context_lost: Cell::new(false),
+            provoking_vertex: Cell::new(framebuffer::render_state::ProvokingVertex::LastVertex),
+            sample_mask: Cell::new(None),
+            clear_color: Cell::new(image_format::Rgba::new(0.0, 0.0, 0.0, 0.0)),
+            clear_depth: Cell::new(1.0),
+            clear_stencil: Cell::new(0),
+            object_registry: object_registry::ObjectRegistry::new(),
+            direct_state_access_supported,
+            debug_label_supported,
             gl,
         })
     }
+
+    /// Returns the state for the context identified by `context_id`, creating it with
+    /// [`new`] if this thread doesn't have a live state for that context yet.
+    ///
+    /// `context_id` can be any value that uniquely identifies the context among the contexts
+    /// alive on this thread, such as the address of the windowing library's context object or
+    /// its raw context handle. Once every `Rc` to a state has been dropped, the next call with its
+    /// ID creates a new state.
+    ///
+    /// ## Safety
+    /// The context must be current, and must stay current for as long as the `ContextState` and
+    /// its objects are used. The state must not also have been created with [`new`].
+    ///
+    /// [`new`]: ./struct.ContextState.html#method.new
+    pub unsafe fn for_context<F: Fn(&str) -> *const ()>(
+        context_id: usize,
+        load_fn: F,
+    ) -> Rc<ContextState> {
+        CONTEXT_STATES.with(|states| {
+            let mut states = states.borrow_mut();
+            if let Some(state) = states.get(&context_id).and_then(Weak::upgrade) {
+                return state;
+            }
+
+            states.retain(|_, state| state.strong_count() > 0);
+            let state = ContextState::new(load_fn);
+            states.insert(context_id, Rc::downgrade(&state));
+            state
+        })
+    }
+
+    /// Returns every buffer, texture, renderbuffer and vertex array object created through this
+    /// context that hasn't been dropped yet.
+    ///
+    /// This is meant for debugging tools, like reports of how much GPU memory each type of object
+    /// is using, or checking that everything got cleaned up after a scene was unloaded. Requires
+    /// the `object-registry` feature, since tracking objects adds overhead to creating them.
+    #[cfg(feature = "object-registry")]
+    pub fn live_objects(&self) -> Vec<ObjectInfo> {
+        self.object_registry.objects()
+    }
+
+    /// Labels an object for graphics debuggers and driver debug messages, and records the label
+    /// in the object registry. Labels longer than `GL_MAX_LABEL_LENGTH` get truncated before
+    /// they're passed to OpenGL.
+    pub(crate) fn set_object_label(
+        &self,
+        kind: object_registry::ObjectKind,
+        handle: Handle,
+        label: &str,
+    ) {
+        self.object_registry.set_label(kind, handle, label);
+        if !self.debug_label_supported {
+            return;
+        }
+
+        let mut max_len = 0;
+        unsafe { self.gl.GetIntegerv(gl::MAX_LABEL_LENGTH, &mut max_len) };
+        let mut len = label.len().min((max_len.max(1) - 1) as usize);
+        while !label.is_char_boundary(len) {
+            len -= 1;
+        }
+        unsafe {
+            let _error_scope = error::ErrorScope::new(&self.gl, "glObjectLabel");
+            self.gl.ObjectLabel(
+                kind.gl_identifier(),
+                handle.get(),
+                len as GLsizei,
+                label.as_ptr() as *const GLchar,
+            );
+        }
+    }
+
+    /// Returns `true` if the OpenGL context has been lost due to a graphics reset.
+    ///
+    /// Contexts can get lost when the driver crashes, the GPU gets reset, or the system switches
+    /// between GPUs. Once that happens, every object created through this `ContextState` is
+    /// invalid, and the only safe things to do with them are dropping them and dropping the
+    /// `ContextState` itself. Any other operation may silently do nothing, return garbage data, or
+    /// trip an OpenGL error check. To recover, create a new context and recreate all resources.
+    ///
+    /// Reset notifications are only delivered if the context was created with robustness enabled
+    /// (`GL_LOSE_CONTEXT_ON_RESET`). This requires OpenGL 4.5 or `GL_KHR_robustness`, and always
+    /// returns `false` if neither is available. Once a loss has been detected, this keeps returning
+    /// `true`.
+    #[inline]
+    pub fn is_context_lost(&self) -> bool {
+        if !self.context_lost.get() && self.gl.GetGraphicsResetStatus.is_loaded() {
+            let status = unsafe { self.gl.GetGraphicsResetStatus() };
+            self.context_lost.set(status != gl::NO_ERROR);
+        }
+        self.context_lost.get()
+    }
+
+    /// Runs `f` with the given pixel byte and bit ordering applied to all pixel transfers, then
+    /// resets the ordering to the defaults.
+    ///
+    /// This affects every texture upload and framebuffer read made within `f`. See [`PixelStore`]
+    /// for the performance implications.
+    ///
+    /// [`PixelStore`]: ./image_format/struct.PixelStore.html
+    pub fn with_pixel_store<R>(
+        &self,
+        pixel_store: image_format::PixelStore,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        struct ResetPixelStore<'a>(&'a Gl);
+        impl<'a> Drop for ResetPixelStore<'a> {
+            fn drop(&mut self) {
+                unsafe { image_format::PixelStore::default().apply(self.0) };
+            }
+        }
+
+        unsafe { pixel_store.apply(&self.gl) };
+        let _reset = ResetPixelStore(&self.gl);
+        f()
+    }
+
+    /// Queries the range and precision of a numeric type in a shader stage.
+    ///
+    /// Desktop implementations generally give every precision qualifier full 32-bit precision,
+    /// but ES-class hardware may not support `highp` in fragment shaders. This can be used to
+    /// check for that before relying on it. See [`PrecisionFormat::is_unsupported`].
+    ///
+    /// Requires OpenGL 4.1 or `GL_ARB_ES2_compatibility`.
+    ///
+    /// ## Panics
+    /// Panics if the precision query isn't supported.
+    ///
+    /// [`PrecisionFormat::is_unsupported`]: ./program/struct.PrecisionFormat.html#method.is_unsupported
+    #[inline]
+    pub fn shader_precision(
+        &self,
+        stage: program::PrecisionStage,
+        precision_type: program::PrecisionType,
+    ) -> program::PrecisionFormat {
+        assert!(
+            self.gl.GetShaderPrecisionFormat.is_loaded(),
+            "glGetShaderPrecisionFormat requires OpenGL 4.1 or GL_ARB_ES2_compatibility"
+        );
+        program::shader_precision(&self.gl, stage, precision_type)
+    }
+
+    /// Returns the internal formats of every compressed texture format the implementation reports
+    /// through `GL_COMPRESSED_TEXTURE_FORMATS`.
+    ///
+    /// Implementations only have to list formats that are suitable for general-purpose use, so
+    /// this may leave out formats that are supported. Core profile drivers often return an empty
+    /// list.
+    pub fn supported_compressed_formats(&self) -> Vec<GLenum> {
+        unsafe {
+            let mut num_formats = 0;
+            self.gl
+                .GetIntegerv(gl::NUM_COMPRESSED_TEXTURE_FORMATS, &mut num_formats);
+            let mut formats = vec![0; num_formats.max(0) as usize];
+            if !formats.is_empty() {
+                self.gl
+                    .GetIntegerv(gl::COMPRESSED_TEXTURE_FORMATS, formats.as_mut_ptr());
+            }
+            formats.into_iter().map(|format| format as GLenum).collect()
+        }
+    }
+
+    /// Returns whether textures of the compressed format `F` can be uploaded.
+    ///
+    /// RGTC formats are part of core OpenGL 3.0, so they're always supported. Other formats are
+    /// checked against [`supported_compressed_formats`]. Returns `false` for uncompressed formats.
+    ///
+    /// [`supported_compressed_formats`]: ./struct.ContextState.html#method.supported_compressed_formats
+    pub fn supports_compressed<F: image_format::ConcreteImageFormat>(&self) -> bool {
+        match F::FORMAT {
+            image_format::FormatAttributes::Compressed {
+                internal_format, ..
+            } => match internal_format {
+                gl::COMPRESSED_RED_RGTC1
+                | gl::COMPRESSED_SIGNED_RED_RGTC1
+                | gl::COMPRESSED_RG_RGTC2
+                | gl::COMPRESSED_SIGNED_RG_RGTC2 => true,
+                _ => self
+                    .supported_compressed_formats()
+                    .contains(&internal_format),
+            },
+            image_format::FormatAttributes::Uncompressed { .. } => false,
+        }
+    }
+
+    /// Returns whether the OpenGL implementation supports the extension called `name`, e.g.
+    /// `"GL_ARB_bindless_texture"`.
+    pub fn supports_extension(&self, name: &str) -> bool {
+        unsafe { supports_extension(&self.gl, name) }
+    }
+
+    /// Returns whether textures and samplers can use the min and max [`ReductionMode`]s.
+    ///
+    /// Requires `GL_ARB_texture_filter_minmax`.
+    ///
+    /// [`ReductionMode`]: ./texture/sample_parameters/enum.ReductionMode.html
+    pub fn texture_filter_minmax_supported(&self) -> bool {
+        self.supports_extension("GL_ARB_texture_filter_minmax")
+    }
+
+    /// Returns whether textures can use the [`Stencil8`] image format.
+    ///
+    /// Requires OpenGL 4.4 or `GL_ARB_texture_stencil8`. Renderbuffers can use `Stencil8`
+    /// regardless.
+    ///
+    /// [`Stencil8`]: ./image_format/struct.Stencil8.html
+    pub fn texture_stencil8_supported(&self) -> bool {
+        let version = unsafe { gl_version(&self.gl) };
+        version >= (4, 4) || self.supports_extension("GL_ARB_texture_stencil8")
+    }
+
+    /// Returns whether textures can be created with sparse storage, which only has memory backing
+    /// the regions that get committed. See [`Texture::with_sparse_storage`].
+    ///
+    /// Requires `GL_ARB_sparse_texture`.
+    ///
+    /// [`Texture::with_sparse_storage`]: ./texture/struct.Texture.html#method.with_sparse_storage
+    pub fn sparse_texture_supported(&self) -> bool {
+        self.supports_extension("GL_ARB_sparse_texture")
+    }
+
+    /// Returns whether shaders can use the `gl_DrawID`, `gl_BaseVertex` and `gl_BaseInstance`
+    /// inputs.
+    ///
+    /// `gl_DrawID` holds the index of the current draw within a multi-draw command, which lets
+    /// GPU-driven renderers look up per-draw data without splitting the command up. Without it,
+    /// per-draw data has to be indexed some other way, e.g. with an instanced vertex attribute
+    /// and the draw's base instance. OpenGL 4.6 has these inputs in core GLSL. Otherwise, they're
+    /// provided by `GL_ARB_shader_draw_parameters`, which names them `gl_DrawIDARB`,
+    /// `gl_BaseVertexARB` and `gl_BaseInstanceARB` and has to be enabled in the shader with
+    /// `#extension GL_ARB_shader_draw_parameters : require`.
+    pub fn shader_draw_parameters_supported(&self) -> bool {
+        let version = unsafe { gl_version(&self.gl) };
+        version >= (4, 6) || self.supports_extension("GL_ARB_shader_draw_parameters")
+    }
+
+    /// Returns whether the OpenGL implementation supports querying format support with
+    /// [`supports_format`].
+    ///
+    /// [`supports_format`]: ./struct.ContextState.html#method.supports_format
+    #[inline]
+    pub fn format_query_supported(&self) -> bool {
+        self.gl.GetInternalformati64v.is_loaded()
+    }
+
+    /// Returns whether images of format `F` can be used for `usage`, as reported by
+    /// `glGetInternalformativ`.
+    ///
+    /// Support for some formats varies between implementations, so this can be used to choose a
+    /// working format at runtime instead of having a framebuffer turn out to be incomplete.
+    /// Formats that the implementation only supports with caveats, such as reduced performance,
+    /// are reported as supported.
+    ///
+    /// Requires OpenGL 4.3 or `GL_ARB_internalformat_query2`.
+    ///
+    /// ## Panics
+    /// Panics if format queries aren't supported. Check [`format_query_supported`] beforehand.
+    ///
+    /// [`format_query_supported`]: ./struct.ContextState.html#method.format_query_supported
+    pub fn supports_format<F: image_format::ConcreteImageFormat>(
+        &self,
+        usage: image_format::FormatUsage,
+    ) -> bool {
+        use crate::image_format::FormatUsage;
+        assert!(
+            self.format_query_supported(),
+            "GL_INTERNALFORMAT_SUPPORTED requires OpenGL 4.3 or GL_ARB_internalformat_query2"
+        );
+
+        let (target, pname) = match usage {
+            FormatUsage::Texture => (gl::TEXTURE_2D, gl::INTERNALFORMAT_SUPPORTED),
+            FormatUsage::Renderbuffer => (gl::RENDERBUFFER, gl::INTERNALFORMAT_SUPPORTED),
+            FormatUsage::Renderable => (gl::TEXTURE_2D, gl::FRAMEBUFFER_RENDERABLE),
+        };
+        let mut support = 0;
+        unsafe {
+            self.gl.GetInternalformativ(
+                target,
+                F::FORMAT.internal_format(),
+                pname,
+                1,
+                &mut support,
+            );
+        }
+        match pname {
+            gl::INTERNALFORMAT_SUPPORTED => support == GLint::from(gl::TRUE),
+            _ => matches!(support as GLenum, gl::FULL_SUPPORT | gl::CAVEAT_SUPPORT),
+        }
+    }
+
+    /// Returns whether the OpenGL implementation supports immutable multisampled texture storage.
+    /// See [`Texture::with_sample_count_immutable`].
+    ///
+    /// [`Texture::with_sample_count_immutable`]: ./texture/struct.Texture.html#method.with_sample_count_immutable
+    #[inline]
+    pub fn texture_storage_multisample_supported(&self) -> bool {
+        self.gl.TexStorage2DMultisample.is_loaded()
+    }
+
+    /// Returns whether the OpenGL implementation supports [`ProgramPipeline`]s.
+    ///
+    /// Requires OpenGL 4.1 or `GL_ARB_separate_shader_objects`.
+    ///
+    /// [`ProgramPipeline`]: ./program/struct.ProgramPipeline.html
+    pub fn program_pipeline_supported(&self) -> bool {
+        let version = unsafe { gl_version(&self.gl) };
+        version >= (4, 1) || self.supports_extension("GL_ARB_separate_shader_objects")
+    }
+
+    /// Returns whether the OpenGL implementation supports the `base_instance` parameter of
+    /// [`Framebuffer::draw_ext`].
+    ///
+    /// [`Framebuffer::draw_ext`]: ./framebuffer/trait.Framebuffer.html#method.draw_ext
+    #[inline]
+    pub fn base_instance_supported(&self) -> bool {
+        self.gl.DrawArraysInstancedBaseInstance.is_loaded()
+    }
+
+    /// Returns whether the OpenGL implementation supports double-precision vertex attributes and
+    /// uniforms, i.e. `f64` scalars and the vectors and matrices built from them.
+    #[inline]
+    pub fn double_precision_supported(&self) -> bool {
+        self.gl.VertexAttribLPointer.is_loaded()
+    }
+
+    /// Returns whether the OpenGL implementation supports [`set_clip_control`].
+    ///
+    /// [`set_clip_control`]: ./struct.ContextState.html#method.set_clip_control
+    #[inline]
+    pub fn clip_control_supported(&self) -> bool {
+        self.gl.ClipControl.is_loaded()
+    }
+
+    /// Sets the clip-space conventions used when mapping vertices to the framebuffer.
+    ///
+    /// This is mainly useful for reverse-Z rendering, which stores depth as `1.0` at the near
+    /// plane and `0.0` at the far plane to get much better precision out of floating-point depth
+    /// buffers. To do that:
+    ///
+    /// * Call `set_clip_control(ClipOrigin::LowerLeft, ClipDepthMode::ZeroToOne)`.
+    /// * Use a projection matrix that maps the near plane to `1.0` and the far plane to `0.0`.
+    /// * Flip the depth test to `DepthStencilFunc::Greater` (or `GEqual`).
+    /// * Clear the depth buffer to `0.0` instead of `1.0`.
+    /// * Use a floating-point depth format, like `Depth32F` or `Depth32FStencil8`.
+    ///
+    /// Requires OpenGL 4.5 or `GL_ARB_clip_control`.
+    ///
+    /// ## Panics
+    /// Panics if clip control isn't supported. Check [`clip_control_supported`] beforehand.
+    ///
+    /// [`clip_control_supported`]: ./struct.ContextState.html#method.clip_control_supported
+    #[inline]
+    pub fn set_clip_control(
+        &self,
+        origin: framebuffer::render_state::ClipOrigin,
+        depth_mode: framebuffer::render_state::ClipDepthMode,
+    ) {
+        assert!(
+            self.clip_control_supported(),
+            "glClipControl requires OpenGL 4.5 or GL_ARB_clip_control"
+        );
+        framebuffer::render_state::set_clip_control(&self.gl, origin, depth_mode);
+    }
+
+    /// Returns whether the OpenGL implementation supports [`set_provoking_vertex`].
+    ///
+    /// [`set_provoking_vertex`]: ./struct.ContextState.html#method.set_provoking_vertex
+    #[inline]
+    pub fn provoking_vertex_supported(&self) -> bool {
+        self.gl.ProvokingVertex.is_loaded()
+    }
+
+    /// Returns the current provoking vertex convention. Defaults to `LastVertex`.
+    #[inline]
+    pub fn provoking_vertex(&self) -> framebuffer::render_state::ProvokingVertex {
+        self.provoking_vertex.get()
+    }
+
+    /// Sets which vertex of each primitive provides the values of `flat`-qualified shader
+    /// outputs.
+    ///
+    /// OpenGL uses the last vertex by default, while Direct3D uses the first, so this is mainly
+    /// needed when matching the output of another renderer.
+    ///
+    /// Requires OpenGL 3.2 or `GL_ARB_provoking_vertex`.
+    ///
+    /// ## Panics
+    /// Panics if the provoking vertex can't be changed. Check [`provoking_vertex_supported`]
+    /// beforehand.
+    ///
+    /// [`provoking_vertex_supported`]: ./struct.ContextState.html#method.provoking_vertex_supported
+    #[inline]
+    pub fn set_provoking_vertex(&self, provoking_vertex: framebuffer::render_state::ProvokingVertex) {
+        assert!(
+            self.provoking_vertex_supported(),
+            "glProvokingVertex requires OpenGL 3.2 or GL_ARB_provoking_vertex"
+        );
+        if self.provoking_vertex.get() != provoking_vertex {
+            framebuffer::render_state::set_provoking_vertex(&self.gl, provoking_vertex);
+            self.provoking_vertex.set(provoking_vertex);
+        }
+    }
+
+    /// Returns whether the OpenGL implementation supports [`set_sample_mask`].
+    ///
+    /// [`set_sample_mask`]: ./struct.ContextState.html#method.set_sample_mask
+    #[inline]
+    pub fn sample_mask_supported(&self) -> bool {
+        self.gl.SampleMaski.is_loaded()
+    }
+
+    /// Returns the current sample mask, or `None` if masking is disabled.
+    #[inline]
+    pub fn sample_mask(&self) -> Option<u32> {
+        self.sample_mask.get()
+    }
+
+    /// Restricts which samples of multisampled framebuffers get written to. Bit `n` of the mask
+    /// corresponds to sample `n`; `None` disables masking, writing to every sample.
+    ///
+    /// This is useful for custom MSAA resolves and stochastic transparency. The mask gets ANDed
+    /// with the fragment's coverage, as well as with `gl_SampleMask` if the fragment shader writes
+    /// to it, so the shader can only further restrict the samples set here. The mask has no effect
+    /// when rendering to single-sampled framebuffers.
+    ///
+    /// Requires OpenGL 3.2 or `GL_ARB_texture_multisample`.
+    ///
+    /// ## Panics
+    /// Panics if sample masks aren't supported. Check [`sample_mask_supported`] beforehand.
+    ///
+    /// [`sample_mask_supported`]: ./struct.ContextState.html#method.sample_mask_supported
+    pub fn set_sample_mask(&self, mask: Option<u32>) {
+        assert!(
+            self.sample_mask_supported(),
+            "glSampleMaski requires OpenGL 3.2 or GL_ARB_texture_multisample"
+        );
+        if self.sample_mask.get() == mask {
+            return;
+        }
+        unsafe {
+            match mask {
+                Some(mask) => {
+                    self.gl.Enable(gl::SAMPLE_MASK);
+                    self.gl.SampleMaski(0, mask);
+                }
+                None => self.gl.Disable(gl::SAMPLE_MASK),
+            }
+        }
+        self.sample_mask.set(mask);
+    }
+
+    /// Sets the number of vertices that make up each patch drawn with `DrawMode::Patches`.
+    /// Defaults to `3`.
+    ///
+    /// Requires OpenGL 4.0 or `GL_ARB_tessellation_shader`.
+    ///
+    /// ## Panics
+    /// Panics if patches aren't supported, or if `count` is `0` or greater than
+    /// `GL_MAX_PATCH_VERTICES`.
+    #[inline]
+    pub fn set_patch_vertices(&self, count: u32) {
+        assert!(
+            self.gl.PatchParameteri.is_loaded(),
+            "patches require OpenGL 4.0 or GL_ARB_tessellation_shader"
+        );
+        let mut max_patch_vertices = 0;
+        unsafe {
+            self.gl
+                .GetIntegerv(gl::MAX_PATCH_VERTICES, &mut max_patch_vertices);
+        }
+        assert!(
+            0 < count && count <= max_patch_vertices as u32,
+            "patch vertex count {} out of range 1..={}",
+            count,
+            max_patch_vertices
+        );
+        unsafe {
+            self.gl.PatchParameteri(gl::PATCH_VERTICES, count as GLint);
+        }
+    }
+
+    /// Returns whether the OpenGL implementation supports [`memory_barrier`].
+    ///
+    /// [`memory_barrier`]: ./struct.ContextState.html#method.memory_barrier
+    #[inline]
+    pub fn memory_barrier_supported(&self) -> bool {
+        self.gl.MemoryBarrier.is_loaded()
+    }
+
+    /// Makes incoherent writes made by earlier shader invocations visible to the accesses in
+    /// `barriers`.
+    ///
+    /// Image stores, shader storage buffer writes and atomic counter updates aren't automatically
+    /// synchronized with later commands, so this has to be called between a draw or dispatch that
+    /// makes those writes and any command that reads the results.
+    ///
+    /// Requires OpenGL 4.2 or `GL_ARB_shader_image_load_store`.
+    ///
+    /// ## Panics
+    /// Panics if memory barriers aren't supported. Check [`memory_barrier_supported`]
+    /// beforehand.
+    ///
+    /// [`memory_barrier_supported`]: ./struct.ContextState.html#method.memory_barrier_supported
+    #[inline]
+    pub fn memory_barrier(&self, barriers: MemoryBarrier) {
+        assert!(
+            self.memory_barrier_supported(),
+            "glMemoryBarrier requires OpenGL 4.2 or GL_ARB_shader_image_load_store"
+        );
+        unsafe { self.gl.MemoryBarrier(barriers.bits()) };
+    }
+
+    /// Returns whether the OpenGL implementation supports [`texture_barrier`].
+    ///
+    /// [`texture_barrier`]: ./struct.ContextState.html#method.texture_barrier
+    #[inline]
+    pub fn texture_barrier_supported(&self) -> bool {
+        self.gl.TextureBarrier.is_loaded()
+    }
+
+    /// Makes texels written by earlier draws visible to texture fetches in later draws.
+    ///
+    /// Sampling a texture while it's attached to the framebuffer being drawn to is normally
+    /// undefined, but is allowed for programmable blending under a narrow set of rules. Each
+    /// texel may only be read by the fragment that writes to it, and only once per draw. A
+    /// texture barrier is needed between draws that touch the same texels, so that later draws
+    /// see the results of earlier ones. Anything outside of that, such as reading neighboring
+    /// texels or overlapping primitives in a single draw, is still undefined.
+    ///
+    /// Requires OpenGL 4.5 or `GL_ARB_texture_barrier`.
+    ///
+    /// ## Panics
+    /// Panics if texture barriers aren't supported. Check [`texture_barrier_supported`]
+    /// beforehand.
+    ///
+    /// [`texture_barrier_supported`]: ./struct.ContextState.html#method.texture_barrier_supported
+    #[inline]
+    pub fn texture_barrier(&self) {
+        assert!(
+            self.texture_barrier_supported(),
+            "glTextureBarrier requires OpenGL 4.5 or GL_ARB_texture_barrier"
+        );
+        unsafe { self.gl.TextureBarrier() };
+    }
+
+    /// Returns whether the OpenGL implementation supports [`set_viewport_f`].
+    ///
+    /// [`set_viewport_f`]: ./struct.ContextState.html#method.set_viewport_f
+    #[inline]
+    pub fn viewport_array_supported(&self) -> bool {
+        self.gl.ViewportIndexedf.is_loaded()
+    }
+
+    /// The number of viewports geometry shaders can select between. This is `GL_MAX_VIEWPORTS`,
+    /// or `1` if viewport arrays aren't supported.
+    #[inline]
+    pub fn max_viewports(&self) -> u32 {
+        if !self.viewport_array_supported() {
+            return 1;
+        }
+        let mut max_viewports = 0;
+        unsafe {
+            self.gl.GetIntegerv(gl::MAX_VIEWPORTS, &mut max_viewports);
+        }
+        max_viewports as u32
+    }
+
+    /// Sets viewport `index` with sub-pixel precision. `(x, y)` is the lower-left corner of the
+    /// viewport, in pixels.
+    ///
+    /// This is the floating-point counterpart to [`RenderState::viewport`], and is mainly useful
+    /// for jittering the projection by a fraction of a pixel each frame for temporal
+    /// anti-aliasing and supersampling. Viewports other than `0` only get used by geometry
+    /// shaders that write to `gl_ViewportIndex`.
+    ///
+    /// Changing `RenderState::viewport` sets every viewport, overriding what's set here. Keep the
+    /// render state's viewport the same between draws that use this.
+    ///
+    /// Requires OpenGL 4.1 or `GL_ARB_viewport_array`.
+    ///
+    /// ## Panics
+    /// Panics if viewport arrays aren't supported, or if `index` is greater than or equal to
+    /// [`max_viewports`]. Check [`viewport_array_supported`] beforehand.
+    ///
+    /// [`RenderState::viewport`]: ./framebuffer/render_state/struct.RenderState.html#structfield.viewport
+    /// [`max_viewports`]: ./struct.ContextState.html#method.max_viewports
+    /// [`viewport_array_supported`]: ./struct.ContextState.html#method.viewport_array_supported
+    #[inline]
+    pub fn set_viewport_f(&self, index: u32, x: f32, y: f32, width: f32, height: f32) {
+        assert!(
+            self.viewport_array_supported(),
+            "glViewportIndexedf requires OpenGL 4.1 or GL_ARB_viewport_array"
+        );
+        let max_viewports = self.max_viewports();
+        assert!(
+            index < max_viewports,
+            "viewport index {} out of range 0..{}",
+            index,
+            max_viewports
+        );
+        unsafe {
+            self.gl.ViewportIndexedf(index, x, y, width, height);
+        }
+    }
+
+    /// Sets the scissor rectangle for viewport `index`, or disables scissoring for the viewport if
+    /// `rect` is `None`. Fragments drawn to the viewport outside of the rectangle get discarded.
+    ///
+    /// Combined with viewport arrays, this lets geometry shaders that render to several layers
+    /// in a single pass, like cubemap or cascaded shadow map passes, clip each layer separately.
+    /// The rectangle uses the same coordinates as [`RenderState::viewport`], and scissoring is
+    /// disabled for every viewport by default.
+    ///
+    /// Requires OpenGL 4.1 or `GL_ARB_viewport_array`.
+    ///
+    /// ## Panics
+    /// Panics if viewport arrays aren't supported, or if `index` is greater than or equal to
+    /// [`max_viewports`]. Check [`viewport_array_supported`] beforehand.
+    ///
+    /// [`RenderState::viewport`]: ./framebuffer/render_state/struct.RenderState.html#structfield.viewport
+    /// [`max_viewports`]: ./struct.ContextState.html#method.max_viewports
+    /// [`viewport_array_supported`]: ./struct.ContextState.html#method.viewport_array_supported
+    pub fn set_scissor_indexed(
+        &self,
+        index: u32,
+        rect: Option<RangeInclusive<GLVec2<u32, NonNormalized>>>,
+    ) {
+        assert!(
+            self.viewport_array_supported(),
+            "glScissorIndexed requires OpenGL 4.1 or GL_ARB_viewport_array"
+        );
+        let max_viewports = self.max_viewports();
+        assert!(
+            index < max_viewports,
+            "viewport index {} out of range 0..{}",
+            index,
+            max_viewports
+        );
+        unsafe {
+            match rect {
+                Some(rect) => {
+                    let (min, max) = (*rect.start(), *rect.end());
+                    let width = max.x.saturating_sub(min.x);
+                    let height = max.y.saturating_sub(min.y);
+                    assert!(width <= GLsizei::MAX as u32);
+                    assert!(height <= GLsizei::MAX as u32);
+                    self.gl.ScissorIndexed(
+                        index,
+                        min.x as GLint,
+                        min.y as GLint,
+                        width as GLsizei,
+                        height as GLsizei,
+                    );
+                    self.gl.Enablei(gl::SCISSOR_TEST, index);
+                }
+                None => self.gl.Disablei(gl::SCISSOR_TEST, index),
+            }
+        }
+    }
+
+    /// The maximum number of components the outputs of one shader stage can pass to the inputs
+    /// of the next. This is `GL_MAX_VARYING_COMPONENTS`.
+    #[inline]
+    pub fn max_varying_components(&self) -> u32 {
+        let mut max_components = 0;
+        unsafe {
+            self.gl
+                .GetIntegerv(gl::MAX_VARYING_COMPONENTS, &mut max_components);
+        }
+        max_components as u32
+    }
+
+    /// The maximum number of image units, shader storage blocks and fragment outputs a program
+    /// can use in total, across all of its stages. This is
+    /// `GL_MAX_COMBINED_SHADER_OUTPUT_RESOURCES`, or `0` if shader storage buffers aren't
+    /// supported.
+    ///
+    /// Requires OpenGL 4.3 or `GL_ARB_shader_storage_buffer_object`.
+    #[inline]
+    pub fn max_combined_shader_output_resources(&self) -> u32 {
+        if !self.gl.ShaderStorageBlockBinding.is_loaded() {
+            return 0;
+        }
+        let mut max_resources = 0;
+        unsafe {
+            self.gl
+                .GetIntegerv(gl::MAX_COMBINED_SHADER_OUTPUT_RESOURCES, &mut max_resources);
+        }
+        max_resources as u32
+    }
+
+    /// The number of image units images can be bound to for load/store access. This is
+    /// `GL_MAX_IMAGE_UNITS`, or `0` if image load/store isn't supported.
+    ///
+    /// Requires OpenGL 4.2 or `GL_ARB_shader_image_load_store`.
+    #[inline]
+    pub fn max_image_units(&self) -> u32 {
+        if !self.gl.BindImageTexture.is_loaded() {
+            return 0;
+        }
+        let mut max_units = 0;
+        unsafe {
+            self.gl.GetIntegerv(gl::MAX_IMAGE_UNITS, &mut max_units);
+        }
+        max_units as u32
+    }
+
+    /// The number of binding points shader storage buffers can be bound to. This is
+    /// `GL_MAX_SHADER_STORAGE_BUFFER_BINDINGS`, or `0` if shader storage buffers aren't
+    /// supported.
+    ///
+    /// Requires OpenGL 4.3 or `GL_ARB_shader_storage_buffer_object`.
+    #[inline]
+    pub fn max_shader_storage_buffer_bindings(&self) -> u32 {
+        if !self.gl.ShaderStorageBlockBinding.is_loaded() {
+            return 0;
+        }
+        let mut max_bindings = 0;
+        unsafe {
+            self.gl
+                .GetIntegerv(gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS, &mut max_bindings);
+        }
+        max_bindings as u32
+    }
+
+    /// The alignment, in bytes, that offsets passed to `glBindBufferRange` must have when binding
+    /// uniform buffer ranges. This is `GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT`.
+    ///
+    /// [`RingBuffer`] aligns its allocations to this automatically.
+    ///
+    /// [`RingBuffer`]: ./buffer/struct.RingBuffer.html
+    #[inline]
+    pub fn uniform_buffer_offset_alignment(&self) -> usize {
+        let mut alignment = 0;
+        unsafe {
+            self.gl
+                .GetIntegerv(gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, &mut alignment);
+        }
+        (alignment as usize).max(1)
+    }
+
+    /// Unbinds the current renderbuffer.
+    ///
+    /// Gullery binds renderbuffers lazily and leaves them bound, which can interfere with code
+    /// outside of Gullery that assumes no renderbuffer is bound. Renderbuffers get unbound
+    /// automatically when they're dropped, so this is only needed when interleaving with such
+    /// code.
+    #[inline]
+    pub fn reset_renderbuffer_bind(&self) {
+        self.renderbuffer_target.reset_bind(&self.gl);
+    }
+
+    /// The handle of the vertex array object Gullery last bound, or `None` if none is bound.
+    ///
+    /// This and the other `currently_bound_*` functions return Gullery's cached bindings without
+    /// querying OpenGL, so they won't reflect binds made by code outside of Gullery. They're meant
+    /// for debugging and for asserting state in tests.
+    #[inline]
+    pub fn currently_bound_vao(&self) -> Option<Handle> {
+        self.vao_target.0.bound_handle().get()
+    }
+
+    /// The handle of the program Gullery last bound, or `None` if none is bound.
+    #[inline]
+    pub fn currently_bound_program(&self) -> Option<Handle> {
+        self.program_target.0.bound_handle().get()
+    }
+
+    /// The number of texture units draw calls can bind textures to.
+    ///
+    /// This is one less than `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS`, since Gullery reserves the last
+    /// unit for uploading texture data.
+    #[inline]
+    pub fn max_texture_units(&self) -> u32 {
+        self.image_units.0.num_units() - 1
+    }
+
+    /// The handle of the framebuffer object Gullery last bound for drawing, or `None` if the
+    /// default framebuffer is bound.
+    #[inline]
+    pub fn currently_bound_framebuffer(&self) -> Option<Handle> {
+        self.framebuffer_targets.draw.bound_handle().get()
+    }
+
+    /// The handle of the framebuffer object Gullery last bound for reading, or `None` if the
+    /// default framebuffer is bound.
+    #[inline]
+    pub fn currently_bound_read_framebuffer(&self) -> Option<Handle> {
+        self.framebuffer_targets.read.bound_handle().get()
+    }
+
+    /// Returns whether a draw call has blended into an sRGB attachment with sRGB conversion
+    /// enabled since the last time this was called.
+    ///
+    /// When sRGB conversion is enabled, blending against an sRGB attachment happens in linear
+    /// space. That's usually what you want, but it's a frequent source of confusion, so this lets
+    /// applications warn about it through whatever logging they use.
+    #[inline]
+    pub fn srgb_blend_warning(&self) -> bool {
+        self.srgb_blend_warning.replace(false)
+    }
+
+    /// Returns the oldest OpenGL error that hasn't been checked yet, if there is one.
+    ///
+    /// Gullery reports errors from its own calls through the functions that make them, so this is
+    /// mostly useful for checking raw OpenGL calls made alongside Gullery. Errors get reported one
+    /// at a time, so call this in a loop to drain every recorded error.
+    #[inline]
+    pub fn check_error(&self) -> Result<(), GLError> {
+        unsafe { error::get_error(&self.gl) }
+    }
 }
 
 #[cfg(test)]
@@ -132,10 +1065,10 @@ mod test_helper {
     use glutin::{Api, Context, ContextBuilder, EventsLoop, GlContext, GlRequest};
     use quickcheck::{Arbitrary, Gen};
 
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub struct TestVertex {
-        pos: GLVec2<f32>,
-        color: GLVec3<f32>,
+        pub pos: GLVec2<f32>,
+        pub color: GLVec3<f32>,
     }
 
     impl Vertex for TestVertex {
@@ -159,7 +1092,7 @@ mod test_helper {
 
     thread_local! {
         static EVENT_LOOP: EventsLoop = EventsLoop::new();
-        static CONTEXT: Context = {
+        pub static CONTEXT: Context = {
             EVENT_LOOP.with(|el| {
                 let context = Context::new(
                     &*el,
@@ -172,11 +1105,61 @@ mod test_helper {
             })
         };
         pub static CONTEXT_STATE: Rc<ContextState> = CONTEXT.with(|context| unsafe {
-            ContextState::new(|s| context.get_proc_address(s))
+            let context_id = context as *const Context as usize;
+            ContextState::for_context(context_id, |s| context.get_proc_address(s))
         });
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::{CONTEXT, CONTEXT_STATE};
+    use glutin::{Context, GlContext};
+
+    #[test]
+    fn one_state_per_context() {
+        CONTEXT_STATE.with(|state| {
+            CONTEXT.with(|context| {
+                let context_id = context as *const Context as usize;
+                let shared = unsafe {
+                    ContextState::for_context(context_id, |s| context.get_proc_address(s))
+                };
+                assert!(Rc::ptr_eq(state, &shared));
+            })
+        })
+    }
+
+    #[cfg(feature = "object-registry")]
+    #[test]
+    fn object_registry() {
+        use crate::buffer::{Buffer, BufferUsage};
+
+        CONTEXT_STATE.with(|state| {
+            let buffer = Buffer::with_data(BufferUsage::StaticDraw, &[0u32; 16], state.clone());
+            let handle = buffer.handle();
+            let info = state
+                .live_objects()
+                .into_iter()
+                .find(|o| o.kind == ObjectKind::Buffer && o.handle == handle)
+                .unwrap();
+            assert_eq!(Some(64), info.size);
+            assert_eq!(None, info.label);
+
+            buffer.set_label("vertices");
+            let info = state
+                .live_objects()
+                .into_iter()
+                .find(|o| o.kind == ObjectKind::Buffer && o.handle == handle)
+                .unwrap();
+            assert_eq!(Some("vertices".to_string()), info.label);
+
+            drop(buffer);
+            assert!(state.live_objects().iter().all(|o| o.handle != handle));
+        })
+    }
+}
+
 /// Free-floating function used in a couple of submodules that really has no proper place in this
 /// library, but isn't in std so it needs to go somewhere.
 #[inline]
@@ -196,3 +1179,48 @@ fn bound_to_num_end(bound: Bound<&usize>, unbounded: usize) -> usize {
         Bound::Unbounded => unbounded,
     }
 }
+
+/// Returns the `(major, minor)` version of the current context.
+unsafe fn gl_version(gl: &Gl) -> (GLint, GLint) {
+    let (mut major, mut minor) = (0, 0);
+    gl.GetIntegerv(gl::MAJOR_VERSION, &mut major);
+    gl.GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    (major, minor)
+}
+
+/// Returns whether the current context supports the extension called `name`. See
+/// `ContextState::supports_extension`.
+unsafe fn supports_extension(gl: &Gl, name: &str) -> bool {
+    let mut num_extensions = 0;
+    gl.GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions);
+    (0..num_extensions.max(0) as GLuint).any(|i| {
+        let extension = gl.GetStringi(gl::EXTENSIONS, i);
+        !extension.is_null() && CStr::from_ptr(extension as *const _).to_bytes() == name.as_bytes()
+    })
+}
+
+/// Checks that a `members` impl registered as many members as its type has fields. Catches
+/// hand-written impls that forget to register a field.
+#[inline]
+fn assert_num_members<T: ?Sized>(trait_name: &str, expected: usize, actual: usize) {
+    if expected != actual {
+        panic!(
+            "`<{} as {}>::members` registered {} members, but expected {}; check that every field \
+             gets added to the registry",
+            any::type_name::<T>(),
+            trait_name,
+            actual,
+            expected
+        );
+    }
+}
+
+/// Panics if `f64` attributes and uniforms can't be used, rather than calling into functions the
+/// driver didn't load.
+#[inline]
+fn assert_double_precision_supported(gl: &Gl) {
+    assert!(
+        gl.VertexAttribLPointer.is_loaded(),
+        "double-precision attributes and uniforms require OpenGL 4.1 or GL_ARB_vertex_attrib_64bit"
+    );
+}
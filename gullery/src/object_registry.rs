@@ -0,0 +1,157 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracking of live OpenGL objects, for debugging tools.
+//!
+//! Only does anything with the `object-registry` feature enabled. Otherwise, the registry is a
+//! zero-sized type and tracking objects compiles down to nothing.
+
+use crate::{
+    gl::{self, types::GLenum},
+    Handle,
+};
+
+/// The kind of OpenGL object an [`ObjectInfo`] describes.
+///
+/// [`ObjectInfo`]: ./struct.ObjectInfo.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    Buffer,
+    Texture,
+    Renderbuffer,
+    VertexArray,
+}
+
+impl ObjectKind {
+    /// The identifier `glObjectLabel` uses for this kind of object.
+    pub(crate) fn gl_identifier(self) -> GLenum {
+        match self {
+            ObjectKind::Buffer => gl::BUFFER,
+            ObjectKind::Texture => gl::TEXTURE,
+            ObjectKind::Renderbuffer => gl::RENDERBUFFER,
+            ObjectKind::VertexArray => gl::VERTEX_ARRAY,
+        }
+    }
+}
+
+/// A live OpenGL object, as returned by [`ContextState::live_objects`].
+///
+/// [`ContextState::live_objects`]: ./struct.ContextState.html#method.live_objects
+#[cfg(feature = "object-registry")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub kind: ObjectKind,
+    pub handle: Handle,
+    /// The name of the Rust type that owns the object, e.g. `gullery::buffer::Buffer<f32>`.
+    pub type_name: &'static str,
+    /// An estimate of the GPU memory used by the object, in bytes. Doesn't include driver
+    /// overhead or padding, and is `None` for objects that don't own any storage.
+    ///
+    /// Texture sizes cover the mip levels the texture was created with, so they don't include
+    /// levels allocated by generating mipmaps. Multisampled
+    /// textures are counted as having a single sample. Sparse textures are `None`, since their
+    /// memory use depends on which regions are committed.
+    pub size: Option<usize>,
+    /// The debug label set with the object's `set_label` method, if any.
+    pub label: Option<String>,
+}
+
+#[cfg(feature = "object-registry")]
+pub(crate) struct ObjectRegistry {
+    objects: ::std::cell::RefCell<Vec<ObjectInfo>>,
+}
+
+#[cfg(not(feature = "object-registry"))]
+pub(crate) struct ObjectRegistry;
+
+#[cfg(feature = "object-registry")]
+impl ObjectRegistry {
+    pub fn new() -> ObjectRegistry {
+        ObjectRegistry {
+            objects: ::std::cell::RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds an object to the registry, or updates its entry if it's already been added.
+    pub fn track(
+        &self,
+        kind: ObjectKind,
+        handle: Handle,
+        type_name: &'static str,
+        size: impl FnOnce() -> Option<usize>,
+    ) {
+        let mut info = ObjectInfo {
+            kind,
+            handle,
+            type_name,
+            size: size(),
+            label: None,
+        };
+        let mut objects = self.objects.borrow_mut();
+        match objects
+            .iter_mut()
+            .find(|o| o.kind == kind && o.handle == handle)
+        {
+            Some(object) => {
+                info.label = object.label.take();
+                *object = info;
+            }
+            None => objects.push(info),
+        }
+    }
+
+    pub fn set_label(&self, kind: ObjectKind, handle: Handle, label: &str) {
+        let mut objects = self.objects.borrow_mut();
+        if let Some(object) = objects
+            .iter_mut()
+            .find(|o| o.kind == kind && o.handle == handle)
+        {
+            object.label = Some(label.to_string());
+        }
+    }
+
+    pub fn untrack(&self, kind: ObjectKind, handle: Handle) {
+        self.objects
+            .borrow_mut()
+            .retain(|o| o.kind != kind || o.handle != handle);
+    }
+
+    pub fn objects(&self) -> Vec<ObjectInfo> {
+        self.objects.borrow().clone()
+    }
+}
+
+#[cfg(not(feature = "object-registry"))]
+impl ObjectRegistry {
+    #[inline(always)]
+    pub fn new() -> ObjectRegistry {
+        ObjectRegistry
+    }
+
+    #[inline(always)]
+    pub fn track(
+        &self,
+        _: ObjectKind,
+        _: Handle,
+        _: &'static str,
+        _: impl FnOnce() -> Option<usize>,
+    ) {
+    }
+
+    #[inline(always)]
+    pub fn set_label(&self, _: ObjectKind, _: Handle, _: &str) {}
+
+    #[inline(always)]
+    pub fn untrack(&self, _: ObjectKind, _: Handle) {}
+}
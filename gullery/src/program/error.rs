@@ -14,7 +14,7 @@
 
 //! Program and shader errors.
 
-use crate::geometry::TypeTag;
+use crate::geometry::{TypeTag, TypeTagSingle};
 
 use std::{
     error::Error,
@@ -39,6 +39,15 @@ pub struct MismatchedTypeError {
     pub rust_ty: TypeTag,
 }
 
+/// A vertex member has a type that can't be used as a vertex attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedAttribError {
+    pub ident: String,
+    pub rust_ty: TypeTagSingle,
+    /// Why the type can't be used as a vertex attribute.
+    pub reason: &'static str,
+}
+
 /// Error that occurred during program compilation.
 #[derive(Debug, Clone)]
 pub enum ProgramError {
@@ -49,6 +58,19 @@ pub enum ProgramError {
     /// Technically, OpenGL's API allow this to compile successfully. However it's also undefined
     /// behavior so a well-formed program *should* never do this.
     MismatchedTypeError(Vec<MismatchedTypeError>),
+    /// Linking failed, and the fragment shader's inputs use more components than
+    /// `GL_MAX_VARYING_COMPONENTS` allows.
+    ///
+    /// The component count is estimated from the shader source, without accounting for how the
+    /// driver packs varyings, so it's only reported when the driver rejects the program. `log`
+    /// holds the driver's link log, in case the estimate isn't what actually caused the failure.
+    VaryingLimitExceeded {
+        components: u32,
+        max: u32,
+        log: String,
+    },
+    /// A member of the program's vertex type can't be used as a vertex attribute.
+    UnsupportedAttribType(UnsupportedAttribError),
 }
 
 /// Error detected by Gullery that could indicate a misbehaved program.
@@ -90,6 +112,18 @@ impl Display for MismatchedTypeError {
     }
 }
 
+impl Display for UnsupportedAttribError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "Unsupported type in vertex attribute {}; Rust repr has {}, but {}",
+            self.ident, self.rust_ty, self.reason
+        )
+    }
+}
+
+impl Error for UnsupportedAttribError {}
+
 impl Error for ProgramError {}
 
 impl Display for ProgramError {
@@ -107,6 +141,17 @@ impl Display for ProgramError {
                 }
                 Ok(())
             }
+            VaryingLimitExceeded {
+                components,
+                max,
+                ref log,
+            } => write!(
+                f,
+                "fragment shader inputs use {} varying components, but at most {} are \
+                 supported; link log:\n{}",
+                components, max, log
+            ),
+            UnsupportedAttribType(ref e) => write!(f, "{}", e),
         }
     }
 }
@@ -38,18 +38,31 @@ pub mod error;
 mod raw;
 
 use self::{
-    error::{ProgramError, ProgramWarning, ShaderError},
-    raw::{RawBoundProgram, RawProgram, RawProgramTarget, RawShader},
+    error::{LinkError, ProgramError, ProgramWarning, ShaderError},
+    raw::{
+        RawBoundProgram, RawProgram, RawProgramPipeline, RawProgramPipelineTarget,
+        RawProgramTarget, RawShader,
+    },
 };
 
 use crate::{
-    framebuffer::attachments::Attachments, uniform::Uniforms, vertex::Vertex, ContextState,
-    GLObject, Handle,
+    bind::Bind,
+    error::ErrorScope,
+    framebuffer::attachments::Attachments,
+    geometry::GLVec4,
+    gl::types::{GLint, GLuint},
+    uniform::{TextureUniformBinder, UniformType, Uniforms},
+    vertex::Vertex,
+    ContextState, GLObject, Handle,
 };
 
-use std::{marker::PhantomData, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, rc::Rc};
 
-pub use self::raw::{FragmentStage, GeometryStage, ShaderStage, VertexStage};
+pub(crate) use self::raw::shader_precision;
+pub use self::raw::{
+    FragmentStage, GeometryStage, PrecisionFormat, PrecisionStage, PrecisionType, ShaderStage,
+    VertexStage,
+};
 
 /// User-defined code that represents a single stage of the rendering pipeline.
 ///
@@ -89,11 +102,63 @@ where
 {
     raw: RawProgram,
     uniform_locs: U::ULC,
+    named_uniform_locs: RefCell<HashMap<String, GLint>>,
+    attrib_defaults: Vec<(GLuint, GLVec4<f32>)>,
     state: Rc<ContextState>,
     _marker: PhantomData<(*const V, *const A)>,
 }
 
-pub(crate) struct ProgramTarget(RawProgramTarget);
+/// A program linked out of a single shader stage, which can be combined with programs for the
+/// other stages in a [`ProgramPipeline`].
+///
+/// Requires OpenGL 4.1 or `GL_ARB_separate_shader_objects`.
+///
+/// [`ProgramPipeline`]: ./struct.ProgramPipeline.html
+pub struct SeparableProgram<S: ShaderStage, U: 'static + Uniforms = ()> {
+    raw: RawProgram,
+    uniform_locs: U::ULC,
+    state: Rc<ContextState>,
+    _marker: PhantomData<*const S>,
+}
+
+/// A set of [`SeparableProgram`]s, one for each shader stage, that can be drawn with like a
+/// single [`Program`].
+///
+/// Unlike a [`Program`], stage programs can be shared between pipelines, so one vertex program can
+/// be paired with many fragment programs without relinking it each time. Every stage uploads the
+/// uniforms it uses out of the same `U`.
+///
+/// Requires OpenGL 4.1 or `GL_ARB_separate_shader_objects`.
+///
+/// [`SeparableProgram`]: ./struct.SeparableProgram.html
+/// [`Program`]: ./struct.Program.html
+pub struct ProgramPipeline<V, U = (), A = ()>
+where
+    V: Vertex,
+    U: 'static + Uniforms,
+    A: 'static + Attachments,
+{
+    raw: RawProgramPipeline,
+    vert: Rc<SeparableProgram<VertexStage<V>, U>>,
+    geom: Option<Rc<SeparableProgram<GeometryStage, U>>>,
+    frag: Rc<SeparableProgram<FragmentStage<A>, U>>,
+    state: Rc<ContextState>,
+}
+
+/// A program that can be drawn with. Implemented by [`Program`] and [`ProgramPipeline`].
+///
+/// [`Program`]: ./struct.Program.html
+/// [`ProgramPipeline`]: ./struct.ProgramPipeline.html
+pub trait DrawProgram<V: Vertex, U: Uniforms, A: Attachments> {
+    /// Makes the program current, and uploads `uniforms` to it.
+    #[doc(hidden)]
+    unsafe fn bind_for_draw<N>(&self, uniforms: &N)
+    where
+        N: Uniforms<ULC = U::ULC, Static = U>;
+}
+
+pub(crate) struct ProgramTarget(pub(crate) RawProgramTarget);
+pub(crate) struct ProgramPipelineTarget(pub(crate) RawProgramPipelineTarget);
 pub(crate) struct BoundProgram<'a, V: 'a + Vertex, U: 'static + Uniforms, A: 'static + Attachments>
 {
     raw: RawBoundProgram<'a>,
@@ -111,13 +176,33 @@ impl<S: ShaderStage> Shader<S> {
             state,
         })
     }
+
+    /// Create a new shader from the provided source code, adding a `#version` directive if the
+    /// source doesn't already have one.
+    ///
+    /// The added directive declares the newest GLSL version the context supports, using the core
+    /// profile (e.g. `#version 330 core` on an OpenGL 3.3 context). Sources that already contain a
+    /// `#version` directive get compiled unmodified.
+    pub fn with_version(source: &str, state: Rc<ContextState>) -> Result<Shader<S>, ShaderError> {
+        match raw::has_version_directive(source) {
+            true => Shader::new(source, state),
+            false => {
+                let source = raw::prepend_version_directive(source, raw::glsl_version(&state.gl));
+                Shader::new(&source, state)
+            }
+        }
+    }
 }
 
 impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
     /// Create a new program by linking together the provided shaders.
     ///
     /// Returns `Ok(program)` if compilation succeeded. If it didn't, returns `Err(program_err)` with
-    /// the reason for failure.
+    /// the reason for failure. Link failures caused by the fragment shader taking more inputs than
+    /// the context supports get reported as [`ProgramError::VaryingLimitExceeded`], which also
+    /// carries the driver's link log.
+    ///
+    /// [`ProgramError::VaryingLimitExceeded`]: ./error/enum.ProgramError.html#variant.VaryingLimitExceeded
     pub fn new(
         vert: &Shader<VertexStage<V>>,
         geom: Option<&Shader<GeometryStage>>,
@@ -132,6 +217,8 @@ impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
             panic!("Shaders passed to Program creation are parts of different contexts!");
         }
 
+        V::check_attribs().map_err(ProgramError::UnsupportedAttribType)?;
+
         let (raw, mut warnings) = RawProgram::new::<_, U>(
             |mut rpsa| {
                 rpsa.attach_shader(&vert.raw);
@@ -141,12 +228,29 @@ impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
                 rpsa.attach_shader(&frag.raw);
             },
             &vert.state.gl,
-        )?;
+        )
+        .map_err(|err| match err {
+            ProgramError::LinkError(LinkError(log)) => {
+                let components = raw::input_components(&frag.raw.source(&frag.state.gl));
+                let max = frag.state.max_varying_components();
+                match components > max {
+                    true => ProgramError::VaryingLimitExceeded {
+                        components,
+                        max,
+                        log,
+                    },
+                    false => ProgramError::LinkError(LinkError(log)),
+                }
+            }
+            err => err,
+        })?;
 
         let uniform_locs = raw.get_uniform_locations::<U>(&vert.state.gl, &mut warnings);
         Ok((
             Program {
                 uniform_locs,
+                named_uniform_locs: RefCell::new(HashMap::new()),
+                attrib_defaults: Vec::new(),
                 raw,
                 state: vert.state.clone(),
                 _marker: PhantomData,
@@ -156,6 +260,280 @@ impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
     }
 }
 
+impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
+    /// Sets the value the vertex attribute `name` takes when the vertex type doesn't provide it.
+    ///
+    /// This lets a single program draw meshes whose vertex types are missing some of the
+    /// program's inputs. For example, a program that multiplies by a per-vertex color can draw
+    /// meshes without vertex colors by defaulting the color to white. Attributes the vertex type
+    /// *does* provide ignore the default.
+    ///
+    /// Constant attribute values are shared by every program in the context, so attributes
+    /// without a default take whichever value was last uploaded to their location.
+    ///
+    /// The value is converted to the attribute's GLSL type like a floating-point vertex attribute,
+    /// so this shouldn't be used for integer attributes. If the program has no active attribute
+    /// named `name`, this does nothing.
+    pub fn set_attrib_default(&mut self, name: &str, value: impl Into<GLVec4<f32>>) {
+        let loc = match self.raw.attrib_location(name, &self.state.gl) {
+            Some(loc) => loc,
+            None => return,
+        };
+        let value = value.into();
+        match self.attrib_defaults.iter_mut().find(|(l, _)| *l == loc) {
+            Some(default) => default.1 = value,
+            None => self.attrib_defaults.push((loc, value)),
+        }
+    }
+}
+
+impl<V: Vertex, U: Uniforms, A: Attachments> Program<V, U, A> {
+    /// Sets the uniform named `name` to `value`.
+    ///
+    /// This is for uniforms that aren't members of the program's [`Uniforms`] type. The uniform's
+    /// location gets looked up the first time it's set and cached afterwards, so setting it every
+    /// frame doesn't go through `glGetUniformLocation` each time. Uniforms the program doesn't
+    /// have, including ones the compiler optimized out, get cached too and are silently ignored.
+    ///
+    /// The value stays set until it's set again, since draw calls only upload the members of the
+    /// program's `Uniforms` type.
+    ///
+    /// ## Panics
+    /// Panics if `value`'s type doesn't match the uniform's GLSL type. Also panics if `value` is
+    /// a texture, since draw calls reassign texture units. Pass textures through the program's
+    /// `Uniforms` type instead.
+    ///
+    /// [`Uniforms`]: ../uniform/trait.Uniforms.html
+    pub fn set_uniform<T: UniformType>(&self, name: &str, value: T) {
+        let loc = *self
+            .named_uniform_locs
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert_with(|| self.raw.uniform_location(name, &self.state.gl));
+        if loc != -1 {
+            self.upload_uniform(loc, value);
+        }
+    }
+
+    /// Sets the uniform at `location` to `value`.
+    ///
+    /// This is for shaders that declare their uniforms' locations with `layout(location = N)`,
+    /// which skips looking the location up by name entirely. Like [`set_uniform`], the value stays
+    /// set until it's set again.
+    ///
+    /// Explicit uniform locations require OpenGL 4.3 or `GL_ARB_explicit_uniform_location`.
+    ///
+    /// ## Panics
+    /// Panics if `location` doesn't fit in a `GLint`, if `value`'s type doesn't match the
+    /// uniform's GLSL type, or if `value` is a texture.
+    ///
+    /// [`set_uniform`]: ./struct.Program.html#method.set_uniform
+    pub fn set_uniform_at<T: UniformType>(&self, location: u32, value: T) {
+        assert!(
+            location as GLint >= 0,
+            "uniform location {} out of range",
+            location
+        );
+        self.upload_uniform(location as GLint, value);
+    }
+
+    fn upload_uniform<T: UniformType>(&self, loc: GLint, value: T) {
+        let mut binds = Vec::new();
+        unsafe {
+            let _program_bind = self.state.program_target.bind(self);
+            let _error_scope = ErrorScope::new(&self.state.gl, "glUniform");
+            let mut binder = TextureUniformBinder { binds: &mut binds };
+            value.upload(loc, &mut binder, &self.state.gl);
+        }
+        assert!(
+            binds.is_empty(),
+            "texture uniforms can't be set individually; add them to the program's Uniforms instead"
+        );
+    }
+
+    /// Returns the texture unit each of the program's active sampler uniforms reads from, as
+    /// `(name, unit)` pairs. Elements of sampler arrays are listed individually, e.g. as
+    /// `"shadow_maps[1]"`.
+    ///
+    /// Draw calls assign units to the textures in the program's [`Uniforms`], so this reflects the
+    /// most recent draw. Samplers that haven't been assigned a unit yet read from unit `0`. The
+    /// number of samplers can be checked against [`ContextState::max_texture_units`].
+    ///
+    /// Queries OpenGL for each sampler, so this is meant for diagnostics rather than per-frame use.
+    ///
+    /// [`Uniforms`]: ../uniform/trait.Uniforms.html
+    /// [`ContextState::max_texture_units`]: ../struct.ContextState.html#method.max_texture_units
+    pub fn sampler_units(&self) -> Vec<(String, u32)> {
+        self.raw.sampler_units(&self.state.gl)
+    }
+
+    /// Returns the index of the active uniform block named `name`, or `None` if the program has no
+    /// such active block.
+    #[inline]
+    pub fn uniform_block_index(&self, name: &str) -> Option<u32> {
+        self.raw.uniform_block_index(name, &self.state.gl)
+    }
+
+    /// Returns the uniform buffer binding point the uniform block at `index` reads from.
+    ///
+    /// This is `0` unless it was set in the shader with `layout(binding = N)`, so buffers can be
+    /// bound to the binding points the shader expects instead of overriding them.
+    ///
+    /// ## Panics
+    /// Panics if `index` isn't less than the program's number of active uniform blocks.
+    pub fn uniform_block_binding(&self, index: u32) -> u32 {
+        let num_blocks = self.raw.num_uniform_blocks(&self.state.gl);
+        assert!(
+            index < num_blocks,
+            "uniform block index {} out of range for program with {} active uniform blocks",
+            index,
+            num_blocks
+        );
+        self.raw.uniform_block_binding(index, &self.state.gl)
+    }
+}
+
+impl<S: 'static + ShaderStage, U: Uniforms> SeparableProgram<S, U> {
+    /// Create a new separable program out of the provided shader.
+    ///
+    /// Since every stage in a pipeline shares the same uniforms type, uniforms that are only used
+    /// by other stages get reported as `UnusedUniform` warnings.
+    ///
+    /// ## Panics
+    /// Panics if program pipelines aren't supported. Check
+    /// [`ContextState::program_pipeline_supported`] beforehand.
+    ///
+    /// [`ContextState::program_pipeline_supported`]: ../struct.ContextState.html#method.program_pipeline_supported
+    pub fn new(
+        shader: &Shader<S>,
+    ) -> Result<(SeparableProgram<S, U>, Vec<ProgramWarning>), ProgramError> {
+        assert!(
+            shader.state.program_pipeline_supported(),
+            "program pipelines require OpenGL 4.1 or GL_ARB_separate_shader_objects"
+        );
+        let (raw, mut warnings) = RawProgram::new::<_, U>(
+            |mut rpsa| {
+                rpsa.set_separable();
+                rpsa.attach_shader(&shader.raw);
+            },
+            &shader.state.gl,
+        )?;
+
+        let uniform_locs = raw.get_uniform_locations::<U>(&shader.state.gl, &mut warnings);
+        Ok((
+            SeparableProgram {
+                raw,
+                uniform_locs,
+                state: shader.state.clone(),
+                _marker: PhantomData,
+            },
+            warnings,
+        ))
+    }
+}
+
+impl<V: Vertex, U: Uniforms, A: Attachments> ProgramPipeline<V, U, A> {
+    /// Create a new pipeline out of the provided stage programs.
+    ///
+    /// ## Panics
+    /// Panics if the programs are parts of different contexts.
+    pub fn new(
+        vert: Rc<SeparableProgram<VertexStage<V>, U>>,
+        geom: Option<Rc<SeparableProgram<GeometryStage, U>>>,
+        frag: Rc<SeparableProgram<FragmentStage<A>, U>>,
+    ) -> ProgramPipeline<V, U, A> {
+        let vsp = vert.state.as_ref() as *const _;
+        let fsp = frag.state.as_ref() as *const _;
+        let gsp = geom.as_ref().map(|g| g.state.as_ref() as *const _).unwrap_or(vsp);
+
+        if vsp != fsp || fsp != gsp {
+            panic!("Programs passed to ProgramPipeline creation are parts of different contexts!");
+        }
+
+        let state = vert.state.clone();
+        let mut raw = RawProgramPipeline::new(&state.gl);
+        raw.use_program_stages(VertexStage::<V>::STAGE_BIT, &vert.raw, &state.gl);
+        if let Some(ref geom) = geom {
+            raw.use_program_stages(GeometryStage::STAGE_BIT, &geom.raw, &state.gl);
+        }
+        raw.use_program_stages(FragmentStage::<A>::STAGE_BIT, &frag.raw, &state.gl);
+
+        ProgramPipeline {
+            raw,
+            vert,
+            geom,
+            frag,
+            state,
+        }
+    }
+
+    #[inline]
+    pub fn vertex_program(&self) -> &Rc<SeparableProgram<VertexStage<V>, U>> {
+        &self.vert
+    }
+
+    #[inline]
+    pub fn geometry_program(&self) -> Option<&Rc<SeparableProgram<GeometryStage, U>>> {
+        self.geom.as_ref()
+    }
+
+    #[inline]
+    pub fn fragment_program(&self) -> &Rc<SeparableProgram<FragmentStage<A>, U>> {
+        &self.frag
+    }
+}
+
+impl<V: Vertex, U: Uniforms, A: Attachments> DrawProgram<V, U, A> for Program<V, U, A> {
+    unsafe fn bind_for_draw<N>(&self, uniforms: &N)
+    where
+        N: Uniforms<ULC = U::ULC, Static = U>,
+    {
+        let program_bind = self.state.program_target.bind(self);
+        program_bind.upload_uniforms(uniforms);
+        program_bind.upload_attrib_defaults();
+    }
+}
+
+impl<V: Vertex, U: Uniforms, A: Attachments> DrawProgram<V, U, A> for ProgramPipeline<V, U, A> {
+    unsafe fn bind_for_draw<N>(&self, uniforms: &N)
+    where
+        N: Uniforms<ULC = U::ULC, Static = U>,
+    {
+        let ContextState {
+            ref program_target,
+            ref program_pipeline_target,
+            ref image_units,
+            ref gl,
+            ..
+        } = *self.state;
+
+        // Programs bound with `glUseProgram` take priority over the bound pipeline.
+        program_target.0.bind_handle(None, gl);
+        let pipeline_bind = program_pipeline_target.0.bind(&self.raw, gl);
+
+        let stages = [
+            Some((&self.vert.raw, self.vert.uniform_locs.as_ref())),
+            self.geom
+                .as_ref()
+                .map(|geom| (&geom.raw, geom.uniform_locs.as_ref())),
+            Some((&self.frag.raw, self.frag.uniform_locs.as_ref())),
+        ];
+        let mut binds = image_units.bind_buffer();
+        for (raw, locs) in stages.iter().flatten() {
+            pipeline_bind.set_active_program(raw, gl);
+            raw::upload_uniform_values(uniforms, locs, &mut binds, gl);
+        }
+        image_units.bind_units(0, binds, gl);
+    }
+}
+
+impl ProgramPipelineTarget {
+    #[inline]
+    pub(crate) fn new() -> ProgramPipelineTarget {
+        ProgramPipelineTarget(RawProgramPipelineTarget::new())
+    }
+}
+
 impl ProgramTarget {
     #[inline]
     pub(crate) fn new() -> ProgramTarget {
@@ -197,6 +575,13 @@ where
             &self.program.state.gl,
         )
     }
+
+    /// Uploads the constant values set with `Program::set_attrib_default`.
+    #[inline]
+    pub fn upload_attrib_defaults(&self) {
+        self.raw
+            .upload_attrib_defaults(&self.program.attrib_defaults, &self.program.state.gl)
+    }
 }
 
 impl<S: ShaderStage> GLObject for Shader<S> {
@@ -226,6 +611,33 @@ where
     }
 }
 
+impl<S: ShaderStage, U: Uniforms> GLObject for SeparableProgram<S, U> {
+    #[inline]
+    fn handle(&self) -> Handle {
+        self.raw.handle()
+    }
+    #[inline]
+    fn state(&self) -> &Rc<ContextState> {
+        &self.state
+    }
+}
+
+impl<V, U, A> GLObject for ProgramPipeline<V, U, A>
+where
+    V: Vertex,
+    U: Uniforms,
+    A: Attachments,
+{
+    #[inline]
+    fn handle(&self) -> Handle {
+        self.raw.handle()
+    }
+    #[inline]
+    fn state(&self) -> &Rc<ContextState> {
+        &self.state
+    }
+}
+
 impl<S: ShaderStage> Drop for Shader<S> {
     fn drop(&mut self) {
         unsafe {
@@ -247,14 +659,35 @@ where
     }
 }
 
+impl<S: ShaderStage, U: Uniforms> Drop for SeparableProgram<S, U> {
+    fn drop(&mut self) {
+        unsafe {
+            self.raw.delete(&self.state);
+        }
+    }
+}
+
+impl<V, U, A> Drop for ProgramPipeline<V, U, A>
+where
+    V: Vertex,
+    U: Uniforms,
+    A: Attachments,
+{
+    fn drop(&mut self) {
+        unsafe {
+            self.raw.delete(&self.state);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        gl::types::*,
-        geometry::GLVec3,
+        geometry::{GLMat2r3c, GLMat3r2c, GLVec3},
+        gl,
         test_helper::{TestVertex, CONTEXT_STATE},
-        uniform::{Uniforms, UniformsMemberRegistry},
+        uniform::{Transposed, UniformType, Uniforms, UniformsMemberRegistry},
     };
 
     const VERTEX_SHADER: &str = r#"
@@ -304,6 +737,178 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Copy)]
+    struct MissingMemberUniforms {
+        color_tint: GLVec3<f32>,
+        _offset: GLVec3<f32>,
+    }
+
+    impl Uniforms for MissingMemberUniforms {
+        type ULC = [GLint; 2];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: UniformsMemberRegistry<Uniforms = MissingMemberUniforms>,
+        {
+            reg.add_member("color_tint", |t| t.color_tint);
+        }
+    }
+
+    #[test]
+    fn version_directive_detection() {
+        assert!(raw::has_version_directive(VERTEX_SHADER));
+        assert!(raw::has_version_directive("// comment\n  #  version 450\nvoid main() {}"));
+        assert!(!raw::has_version_directive("void main() {}"));
+
+        let source = raw::prepend_version_directive("void main() {}", 330);
+        assert_eq!("#version 330 core\n#line 1\nvoid main() {}", source);
+        assert!(raw::has_version_directive(&source));
+        assert_eq!(
+            "#version 140\n#line 1\n",
+            raw::prepend_version_directive("", 140)
+        );
+    }
+
+    #[test]
+    fn input_component_estimate() {
+        assert_eq!(4, raw::input_components(FRAGMENT_SHADER));
+        assert_eq!(0, raw::input_components("void main() { in_color = vec4(1.0); }"));
+
+        let source = r#"
+            #version 410
+            // in vec4 commented_out;
+            layout(location = 0) flat in ivec2 a, b[3];
+            in mat3x2 c; /* in float commented_out; */
+            out vec4 not_an_input;
+            uniform vec4 also_not_an_input;
+            in Block {
+                noperspective vec3 d;
+                dvec2 e;
+            } block[2];
+            void f(in vec4 parameter) {}
+        "#;
+        assert_eq!(2 + 6 + 6 + (3 + 4) * 2, raw::input_components(source));
+    }
+
+    #[test]
+    fn uniform_block_bindings() {
+        const BLOCK_VERTEX_SHADER: &str = r#"
+            #version 330
+            #extension GL_ARB_shading_language_420pack : require
+
+            in vec2 pos;
+            in vec3 color;
+
+            layout(std140) uniform Unbound {
+                vec4 offset;
+            };
+            layout(std140, binding = 3) uniform Bound {
+                vec4 scale;
+            };
+
+            smooth out vec4 vertex_color;
+
+            void main() {
+                gl_Position = vec4(pos, 0.0, 1.0) * scale + offset;
+                vertex_color = vec4(color, 1.0);
+            }
+        "#;
+
+        CONTEXT_STATE.with(|state| {
+            if !state.supports_extension("GL_ARB_shading_language_420pack") {
+                return;
+            }
+
+            let vertex_shader = Shader::new(BLOCK_VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) =
+                Program::<TestVertex, (), ()>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+
+            let unbound = program.uniform_block_index("Unbound").unwrap();
+            let bound = program.uniform_block_index("Bound").unwrap();
+            assert_eq!(None, program.uniform_block_index("Missing"));
+            assert_eq!(0, program.uniform_block_binding(unbound));
+            assert_eq!(3, program.uniform_block_binding(bound));
+        })
+    }
+
+    #[test]
+    fn transposed_matrix_uniform() {
+        #[derive(Clone, Copy)]
+        struct MatrixUniforms<M: UniformType> {
+            matrix: M,
+        }
+
+        impl<M: 'static + UniformType> Uniforms for MatrixUniforms<M> {
+            type ULC = [GLint; 1];
+            type Static = Self;
+            fn members<R>(mut reg: R)
+            where
+                R: UniformsMemberRegistry<Uniforms = Self>,
+            {
+                reg.add_member("matrix", |t| t.matrix);
+            }
+        }
+
+        const MATRIX_VERTEX_SHADER: &str = r#"
+            #version 330
+
+            in vec2 pos;
+            in vec3 color;
+
+            // 2 rows, 3 columns.
+            uniform mat3x2 matrix;
+
+            smooth out vec4 vertex_color;
+
+            void main() {
+                gl_Position = vec4(matrix * color, 0.0, 1.0);
+                vertex_color = vec4(color, 1.0);
+            }
+        "#;
+
+        CONTEXT_STATE.with(|state| {
+            let vertex_shader = Shader::new(MATRIX_VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+
+            let row_major = mint::RowMatrix2x3 {
+                x: mint::Vector3 { x: 1.0, y: 2.0, z: 3.0 },
+                y: mint::Vector3 { x: 4.0, y: 5.0, z: 6.0 },
+            };
+            let transposed: Transposed<GLMat3r2c<f32>> = row_major.into();
+            assert_eq!(GLVec3::new(1.0, 2.0, 3.0), transposed.0.x);
+            assert_eq!(GLVec3::new(4.0, 5.0, 6.0), transposed.0.y);
+
+            Program::<TestVertex, MatrixUniforms<Transposed<GLMat3r2c<f32>>>, ()>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+            Program::<TestVertex, MatrixUniforms<GLMat2r3c<f32>>, ()>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+            match Program::<TestVertex, MatrixUniforms<GLMat3r2c<f32>>, ()>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            ) {
+                Err(ProgramError::MismatchedTypeError(_)) => (),
+                _ => panic!("mismatched matrix uniform passed type checking"),
+            }
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "registered 1 members, but expected 2")]
+    fn uniforms_missing_member() {
+        MissingMemberUniforms::num_members();
+    }
+
     #[test]
     fn build_normal_program() {
         CONTEXT_STATE.with(|state| {
@@ -327,4 +932,162 @@ mod tests {
             })
         })
     }
+
+    #[test]
+    fn set_uniform() {
+        CONTEXT_STATE.with(|state| {
+            let vertex_shader = Shader::new(VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) =
+                Program::<TestVertex, (), ()>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+
+            for _ in 0..2 {
+                program.set_uniform("offset", GLVec3::new(0.0, 1.0, 2.0));
+                program.set_uniform("not_a_uniform", 1.0f32);
+            }
+            let locs = program.named_uniform_locs.borrow();
+            assert_eq!(2, locs.len());
+            assert_eq!(-1, locs["not_a_uniform"]);
+
+            let mut value = [0.0; 3];
+            unsafe {
+                state.gl.GetUniformfv(
+                    program.raw.handle().get(),
+                    locs["offset"],
+                    value.as_mut_ptr(),
+                )
+            };
+            assert_eq!([0.0, 1.0, 2.0], value);
+        })
+    }
+
+    #[test]
+    fn sampler_units() {
+        const SAMPLER_FRAGMENT_SHADER: &str = r#"
+            #version 330
+
+            smooth in vec4 vertex_color;
+
+            uniform sampler2D albedo;
+            uniform usampler2D masks[2];
+
+            out vec4 frag_color;
+
+            void main() {
+                uvec4 mask = texture(masks[0], vec2(0.0)) + texture(masks[1], vec2(0.0));
+                frag_color = vertex_color * texture(albedo, vec2(0.0)) * vec4(mask);
+            }
+        "#;
+
+        CONTEXT_STATE.with(|state| {
+            let vertex_shader = Shader::new(VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(SAMPLER_FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) =
+                Program::<TestVertex, (), ()>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+
+            unsafe {
+                let _program_bind = state.program_target.bind(&program);
+                let gl = &state.gl;
+                gl.Uniform1i(program.raw.uniform_location("albedo", gl), 3);
+                gl.Uniform1i(program.raw.uniform_location("masks[1]", gl), 5);
+            }
+
+            let mut units = program.sampler_units();
+            units.sort();
+            assert_eq!(
+                vec![
+                    ("albedo".to_string(), 3),
+                    ("masks[0]".to_string(), 0),
+                    ("masks[1]".to_string(), 5),
+                ],
+                units
+            );
+            assert!(units.len() as u32 <= state.max_texture_units());
+        })
+    }
+
+    #[test]
+    fn set_uniform_at() {
+        const LOCATION_VERTEX_SHADER: &str = r#"
+            #version 330
+            #extension GL_ARB_explicit_uniform_location : require
+
+            in vec2 pos;
+            in vec3 color;
+
+            layout(location = 7) uniform vec3 offset;
+
+            smooth out vec4 vertex_color;
+
+            void main() {
+                gl_Position = vec4(vec3(pos, 0.0) + offset, 1.0);
+                vertex_color = vec4(color, 1.0);
+            }
+        "#;
+
+        CONTEXT_STATE.with(|state| {
+            if !state.supports_extension("GL_ARB_explicit_uniform_location") {
+                return;
+            }
+
+            let vertex_shader = Shader::new(LOCATION_VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+            let (program, _) =
+                Program::<TestVertex, (), ()>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+
+            program.set_uniform_at(7, GLVec3::new(3.0, 4.0, 5.0));
+            let mut value = [0.0; 3];
+            unsafe {
+                state
+                    .gl
+                    .GetUniformfv(program.raw.handle().get(), 7, value.as_mut_ptr())
+            };
+            assert_eq!([3.0, 4.0, 5.0], value);
+        })
+    }
+
+    #[test]
+    fn attrib_default() {
+        CONTEXT_STATE.with(|state| {
+            const TINTED_VERTEX_SHADER: &str = r#"
+                #version 330
+
+                in vec2 pos;
+                in vec3 color;
+                in vec4 tint;
+
+                smooth out vec4 vertex_color;
+
+                void main() {
+                    gl_Position = vec4(pos, 0.0, 1.0);
+                    vertex_color = vec4(color, 1.0) * tint;
+                }
+            "#;
+
+            let vertex_shader = Shader::new(TINTED_VERTEX_SHADER, state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, state.clone()).unwrap();
+
+            let (mut program, _) =
+                Program::<TestVertex, (), ()>::new(&vertex_shader, None, &fragment_shader)
+                    .unwrap();
+            program.set_attrib_default("tint", GLVec4::new(1.0, 0.5, 0.25, 1.0));
+            program.set_attrib_default("not_an_attrib", GLVec4::new(0.0, 0.0, 0.0, 0.0));
+            assert_eq!(1, program.attrib_defaults.len());
+
+            let program_bind = unsafe { state.program_target.bind(&program) };
+            program_bind.upload_attrib_defaults();
+
+            let loc = program.raw.attrib_location("tint", &state.gl).unwrap();
+            let mut value = [0.0; 4];
+            unsafe {
+                state
+                    .gl
+                    .GetVertexAttribfv(loc, gl::CURRENT_VERTEX_ATTRIB, value.as_mut_ptr())
+            };
+            assert_eq!([1.0, 0.5, 0.25, 1.0], value);
+        })
+    }
 }
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use crate::{
+    bind::Bind,
+    error::ErrorScope,
     framebuffer::attachments::{
         AMRNSImpl, AttachmentType, Attachments, AttachmentsMemberRegistryNoSpecifics,
     },
@@ -22,12 +24,12 @@ use crate::{
 
 use super::error::{LinkError, MismatchedTypeError, ProgramError, ProgramWarning};
 use crate::{
-    geometry::{TransparentType, TypeTag, TypeTagSingle},
-    texture::ImageUnits,
+    geometry::{GLVec4, TransparentType, TypeTag, TypeTagSingle},
+    texture::{ImageUnits, UnitBind},
     uniform::{
         TextureUniformBinder, UniformLocContainer, UniformType, Uniforms, UniformsMemberRegistry,
     },
-    vertex::{Vertex, VertexMemberRegistry},
+    vertex::{AttribConversion, Vertex, VertexMemberRegistry},
     ContextState, Handle,
 };
 
@@ -50,6 +52,18 @@ pub struct RawProgramTarget {
 
 pub struct RawBoundProgram<'a>(PhantomData<&'a RawProgram>);
 
+pub struct RawProgramPipeline {
+    handle: Handle,
+    _sendsync_optout: PhantomData<*const ()>,
+}
+
+pub struct RawProgramPipelineTarget {
+    bound_pipeline: Cell<Option<Handle>>,
+    _sendsync_optout: PhantomData<*const ()>,
+}
+
+pub struct RawBoundProgramPipeline<'a>(&'a RawProgramPipeline);
+
 struct AttachedShader {
     handle: Handle,
     post_link_hook:
@@ -68,6 +82,8 @@ pub struct RawProgramShaderAttacher<'a, 'b> {
 /// Identifies shader stages and provides pre/post-linking hooks.
 pub unsafe trait ShaderStage: Sized {
     const SHADER_TYPE_ENUM: GLenum;
+    /// The `glUseProgramStages` bit for the stage.
+    const STAGE_BIT: GLbitfield;
 
     #[inline]
     unsafe fn program_pre_link_hook(_: &RawProgram, _: &Gl) {}
@@ -100,6 +116,273 @@ pub enum FragmentStage<A: Attachments> {
     _Unused(!, A),
 }
 
+/// Shader stage whose numeric precision can be queried with
+/// [`ContextState::shader_precision`].
+///
+/// [`ContextState::shader_precision`]: ../struct.ContextState.html#method.shader_precision
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrecisionStage {
+    Vertex = gl::VERTEX_SHADER,
+    Fragment = gl::FRAGMENT_SHADER,
+}
+
+/// GLSL precision qualifier and numeric type pair.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrecisionType {
+    LowFloat = gl::LOW_FLOAT,
+    MediumFloat = gl::MEDIUM_FLOAT,
+    HighFloat = gl::HIGH_FLOAT,
+    LowInt = gl::LOW_INT,
+    MediumInt = gl::MEDIUM_INT,
+    HighInt = gl::HIGH_INT,
+}
+
+/// The range and precision of a numeric type in a shader stage.
+///
+/// Ranges are given as base-2 logarithms of the absolute value of the smallest and largest
+/// representable numbers, so a `range_max` of `127` means values up to `2^127` can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrecisionFormat {
+    pub range_min: i32,
+    pub range_max: i32,
+    /// The number of bits of precision, as a base-2 logarithm. This is `0` for integer types.
+    pub precision: i32,
+}
+
+impl PrecisionFormat {
+    /// Returns `true` if the type isn't supported by the stage at all.
+    ///
+    /// Implementations without `highp` support in fragment shaders report all-zero formats for
+    /// `HighFloat` and `HighInt`.
+    #[inline]
+    pub fn is_unsupported(&self) -> bool {
+        self.range_min == 0 && self.range_max == 0 && self.precision == 0
+    }
+}
+
+impl From<PrecisionStage> for GLenum {
+    #[inline]
+    fn from(stage: PrecisionStage) -> GLenum {
+        unsafe { mem::transmute(stage) }
+    }
+}
+
+impl From<PrecisionType> for GLenum {
+    #[inline]
+    fn from(precision_type: PrecisionType) -> GLenum {
+        unsafe { mem::transmute(precision_type) }
+    }
+}
+
+pub fn shader_precision(
+    gl: &Gl,
+    stage: PrecisionStage,
+    precision_type: PrecisionType,
+) -> PrecisionFormat {
+    let mut range = [0; 2];
+    let mut precision = 0;
+    unsafe {
+        gl.GetShaderPrecisionFormat(
+            stage.into(),
+            precision_type.into(),
+            range.as_mut_ptr(),
+            &mut precision,
+        );
+    }
+    PrecisionFormat {
+        range_min: range[0],
+        range_max: range[1],
+        precision,
+    }
+}
+
+/// Returns the newest GLSL version supported by the context, as it would appear in a `#version`
+/// directive.
+pub fn glsl_version(gl: &Gl) -> u32 {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl.GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl.GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    match (major, minor) {
+        // GLSL versions only started matching the OpenGL version in 3.3.
+        (3, 0) => 130,
+        (3, 1) => 140,
+        (3, 2) => 150,
+        _ => (major * 100 + minor * 10) as u32,
+    }
+}
+
+/// Returns `true` if the source contains a `#version` directive.
+pub fn has_version_directive(source: &str) -> bool {
+    source.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with('#') && line[1..].trim_start().starts_with("version")
+    })
+}
+
+/// Prepends a `#version` directive for `glsl_version` to the source. A `#line` directive is added
+/// after it, so that line numbers in compile errors still match the original source.
+pub fn prepend_version_directive(source: &str, glsl_version: u32) -> String {
+    let profile = match glsl_version >= 150 {
+        true => " core",
+        false => "",
+    };
+    format!("#version {}{}\n#line 1\n{}", glsl_version, profile, source)
+}
+
+/// Estimates the number of varying components used by the global `in` declarations in the
+/// source, including the members of `in` interface blocks.
+///
+/// This doesn't account for the driver's packing or for inputs that get optimized out, and
+/// inputs of struct type are ignored, so it's only meant for explaining link failures.
+pub fn input_components(source: &str) -> u32 {
+    let tokens = glsl_tokens(source);
+    let mut components = 0;
+    let mut statement = Vec::new();
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            "{" => depth += 1,
+            "}" => depth -= 1,
+            _ => (),
+        }
+        statement.push(token);
+
+        // Function definitions end at their closing brace instead of at a semicolon.
+        let function_end = token == "}" && depth == 0 && statement.contains(&")");
+        if depth == 0 && (token == ";" || function_end) {
+            components += statement_input_components(&statement);
+            statement.clear();
+        }
+    }
+    components
+}
+
+/// Splits GLSL source into identifier, number and punctuation tokens, skipping comments and
+/// preprocessor directives.
+fn glsl_tokens(source: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line_start = true;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("//") || (line_start && c == '#') {
+            rest = rest.find('\n').map(|i| &rest[i..]).unwrap_or("");
+        } else if rest.starts_with("/*") {
+            rest = rest[2..].find("*/").map(|i| &rest[i + 4..]).unwrap_or("");
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(&rest[..len]);
+            rest = &rest[len..];
+            line_start = false;
+        } else {
+            if c == '\n' {
+                line_start = true;
+            } else if !c.is_whitespace() {
+                tokens.push(&rest[..c.len_utf8()]);
+                line_start = false;
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    tokens
+}
+
+fn statement_input_components(statement: &[&str]) -> u32 {
+    let mut tokens = statement;
+    let mut is_input = false;
+    loop {
+        match tokens.first() {
+            Some(&"layout") => {
+                let close = tokens.iter().position(|&t| t == ")").unwrap_or(tokens.len() - 1);
+                tokens = &tokens[close + 1..];
+            }
+            Some(&"in") => {
+                is_input = true;
+                tokens = &tokens[1..];
+            }
+            Some(&"flat") | Some(&"smooth") | Some(&"noperspective") | Some(&"centroid")
+            | Some(&"sample") | Some(&"invariant") | Some(&"precise") | Some(&"highp")
+            | Some(&"mediump") | Some(&"lowp") => tokens = &tokens[1..],
+            _ => break,
+        }
+    }
+    if !is_input {
+        return 0;
+    }
+
+    match tokens.get(1) {
+        // Interface block: `in Block { members } instance[len];`
+        Some(&"{") => {
+            let close = tokens.iter().rposition(|&t| t == "}").unwrap_or(tokens.len());
+            let members = tokens[2..close]
+                .split(|&t| t == ";")
+                .map(|member| {
+                    let member = member
+                        .iter()
+                        .position(|&t| type_components(t).is_some())
+                        .map(|ty| &member[ty..])
+                        .unwrap_or(&[]);
+                    declaration_components(member)
+                })
+                .sum::<u32>();
+            let instance_len = match tokens.get(close + 1..) {
+                Some(instance) if instance.len() > 1 => array_len(&instance[1..]),
+                _ => 1,
+            };
+            members * instance_len
+        }
+        _ => declaration_components(tokens),
+    }
+}
+
+/// The components used by `type name[len], name, ...;`.
+fn declaration_components(tokens: &[&str]) -> u32 {
+    let per_element = match tokens.first().and_then(|&ty| type_components(ty)) {
+        Some(components) => components,
+        None => return 0,
+    };
+    tokens[1..]
+        .split(|&t| t == "," || t == ";")
+        .filter(|declarator| !declarator.is_empty())
+        .map(|declarator| per_element * array_len(&declarator[1..]))
+        .sum()
+}
+
+/// The length of a `[len]` array suffix, or `1` if there isn't one or its length isn't a literal.
+fn array_len(tokens: &[&str]) -> u32 {
+    match tokens {
+        ["[", len, "]", ..] => len.parse().unwrap_or(1),
+        _ => 1,
+    }
+}
+
+fn type_components(ty: &str) -> Option<u32> {
+    let (scale, ty) = match ty {
+        "double" => return Some(2),
+        _ if ty.starts_with("dvec") || ty.starts_with("dmat") => (2, &ty[1..]),
+        _ => (1, ty),
+    };
+    let dim = |n: &str| n.parse::<u32>().ok().filter(|n| (2..=4).contains(n));
+    let components = match ty {
+        "float" | "int" | "uint" | "bool" => 1,
+        _ if ty.starts_with("vec") => dim(&ty[3..])?,
+        _ if ty.starts_with("ivec") || ty.starts_with("uvec") || ty.starts_with("bvec") => {
+            dim(&ty[4..])?
+        }
+        _ if ty.starts_with("mat") => match ty[3..].split_once('x') {
+            Some((columns, rows)) => dim(columns)? * dim(rows)?,
+            None => dim(&ty[3..])?.pow(2),
+        },
+        _ => return None,
+    };
+    Some(components * scale)
+}
+
 impl<S: ShaderStage> RawShader<S> {
     pub fn new(source: &str, gl: &Gl) -> Result<RawShader<S>, String> {
         unsafe {
@@ -153,6 +436,30 @@ impl<S: ShaderStage> RawShader<S> {
         self.handle
     }
 
+    /// Retrieves the source code the shader was compiled from.
+    pub fn source(&self, gl: &Gl) -> String {
+        unsafe {
+            let mut source_length = 0;
+            gl.GetShaderiv(
+                self.handle.get(),
+                gl::SHADER_SOURCE_LENGTH,
+                &mut source_length,
+            );
+
+            let mut source: Vec<u8> = vec![0; source_length as usize];
+            gl.GetShaderSource(
+                self.handle.get(),
+                source_length,
+                ptr::null_mut(),
+                source.as_mut_ptr() as *mut GLchar,
+            );
+
+            // Delete the null terminator
+            source.pop();
+            String::from_utf8_lossy(&source).into_owned()
+        }
+    }
+
     pub unsafe fn delete(&mut self, gl: &Gl) {
         gl.DeleteShader(self.handle.get());
     }
@@ -266,10 +573,10 @@ impl RawProgram {
 
                 let loc: GLint;
                 unsafe {
+                    let _error_scope = ErrorScope::new(self.gl, "glGetUniformLocation");
                     loc = self
                         .gl
                         .GetUniformLocation(self.program.handle.get(), cstr.as_ptr());
-                    assert_eq!(0, self.gl.GetError());
 
                     if loc == -1 {
                         self.warnings
@@ -285,6 +592,7 @@ impl RawProgram {
             }
         }
 
+        U::num_members();
         let mut locs = U::ULC::new_zeroed();
         U::members(UniformsLocGetter {
             locs: &mut locs,
@@ -322,15 +630,99 @@ impl RawProgram {
         // }
     }
 
+    /// The location of the uniform named `name`, or `-1` if the program has no such active
+    /// uniform.
+    pub fn uniform_location(&self, name: &str, gl: &Gl) -> GLint {
+        let cstr = CString::new(name).expect("Null terminator in uniform name string");
+        unsafe { gl.GetUniformLocation(self.handle.get(), cstr.as_ptr()) }
+    }
+
+    /// The texture unit each active sampler uniform reads from, as `(name, unit)` pairs. Elements
+    /// of sampler arrays are listed individually.
+    pub fn sampler_units(&self, gl: &Gl) -> Vec<(String, GLuint)> {
+        let uniforms = unsafe {
+            build_info_buffer(
+                self,
+                gl,
+                gl::ACTIVE_UNIFORMS,
+                gl::ACTIVE_UNIFORM_MAX_LENGTH,
+                Gl::GetActiveUniform,
+            )
+        };
+
+        let mut units = Vec::new();
+        for (name, ty) in uniforms {
+            let elements = match ty {
+                TypeTag::Single(tag) if tag.is_sampler() => vec![name],
+                TypeTag::Array(tag, len) if tag.is_sampler() => {
+                    // Arrays get reported under the name of their first element.
+                    let base = name.trim_end_matches("[0]");
+                    (0..len).map(|i| format!("{}[{}]", base, i)).collect()
+                }
+                _ => continue,
+            };
+
+            for element in elements {
+                let mut unit = 0;
+                unsafe {
+                    let loc = self.uniform_location(&element, gl);
+                    gl.GetUniformiv(self.handle.get(), loc, &mut unit);
+                }
+                units.push((element, unit as GLuint));
+            }
+        }
+        units
+    }
+
+    /// The location of the active vertex attribute named `name`, or `None` if the program has no
+    /// such active attribute.
+    pub fn attrib_location(&self, name: &str, gl: &Gl) -> Option<GLuint> {
+        let cstr = CString::new(name).expect("Null terminator in attribute name string");
+        let loc = unsafe { gl.GetAttribLocation(self.handle.get(), cstr.as_ptr()) };
+        if loc < 0 {
+            None
+        } else {
+            Some(loc as GLuint)
+        }
+    }
+
+    /// The index of the active uniform block named `name`, or `None` if the program has no such
+    /// active block.
+    pub fn uniform_block_index(&self, name: &str, gl: &Gl) -> Option<GLuint> {
+        let cstr = CString::new(name).expect("Null terminator in uniform block name string");
+        let index = unsafe { gl.GetUniformBlockIndex(self.handle.get(), cstr.as_ptr()) };
+        match index {
+            gl::INVALID_INDEX => None,
+            _ => Some(index),
+        }
+    }
+
+    pub fn num_uniform_blocks(&self, gl: &Gl) -> GLuint {
+        let mut num_blocks = 0;
+        unsafe { gl.GetProgramiv(self.handle.get(), gl::ACTIVE_UNIFORM_BLOCKS, &mut num_blocks) };
+        num_blocks as GLuint
+    }
+
+    pub fn uniform_block_binding(&self, index: GLuint, gl: &Gl) -> GLuint {
+        let mut binding = 0;
+        unsafe {
+            gl.GetActiveUniformBlockiv(
+                self.handle.get(),
+                index,
+                gl::UNIFORM_BLOCK_BINDING,
+                &mut binding,
+            );
+        }
+        binding as GLuint
+    }
+
     pub fn handle(&self) -> Handle {
         self.handle
     }
 
     pub unsafe fn delete(&mut self, state: &ContextState) {
         state.gl.DeleteProgram(self.handle.get());
-        if state.program_target.0.bound_program.get() == Some(self.handle) {
-            state.program_target.0.reset_bind(&state.gl);
-        }
+        state.program_target.0.unbind_handle(self.handle, &state.gl);
     }
 }
 
@@ -345,18 +737,22 @@ impl RawProgramTarget {
 
     #[inline]
     pub unsafe fn bind<'a>(&'a self, program: &'a RawProgram, gl: &Gl) -> RawBoundProgram<'a> {
-        if self.bound_program.get() != Some(program.handle) {
-            self.bound_program.set(Some(program.handle));
-            gl.UseProgram(program.handle.get());
-        }
-
+        self.bind_handle(Some(program.handle), gl);
         RawBoundProgram(PhantomData)
     }
+}
+
+impl Bind for RawProgramTarget {
+    const BINDING: GLenum = gl::CURRENT_PROGRAM;
+
+    #[inline]
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_program
+    }
 
     #[inline]
-    pub unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_program.set(None);
-        gl.UseProgram(0);
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.UseProgram(handle.map(|h| h.get()).unwrap_or(0));
     }
 }
 
@@ -373,9 +769,103 @@ impl<'a, 'b> RawProgramShaderAttacher<'a, 'b> {
             });
         }
     }
+
+    /// Marks the program as separable, so that it can be used in a program pipeline.
+    #[inline]
+    pub fn set_separable(&mut self) {
+        unsafe {
+            self.gl.ProgramParameteri(
+                self.program.handle.get(),
+                gl::PROGRAM_SEPARABLE,
+                gl::TRUE as GLint,
+            );
+        }
+    }
+}
+
+impl RawProgramPipeline {
+    pub fn new(gl: &Gl) -> RawProgramPipeline {
+        unsafe {
+            let mut handle = 0;
+            gl.GenProgramPipelines(1, &mut handle);
+            RawProgramPipeline {
+                handle: Handle::new(handle).expect("Invalid handle returned from OpenGL"),
+                _sendsync_optout: PhantomData,
+            }
+        }
+    }
+
+    /// Uses `program` for the shader stages in `stages`.
+    #[inline]
+    pub fn use_program_stages(&mut self, stages: GLbitfield, program: &RawProgram, gl: &Gl) {
+        unsafe { gl.UseProgramStages(self.handle.get(), stages, program.handle.get()) };
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    pub unsafe fn delete(&mut self, state: &ContextState) {
+        state.gl.DeleteProgramPipelines(1, &self.handle.get());
+        state
+            .program_pipeline_target
+            .0
+            .unbind_handle(self.handle, &state.gl);
+    }
+}
+
+impl RawProgramPipelineTarget {
+    #[inline]
+    pub fn new() -> RawProgramPipelineTarget {
+        RawProgramPipelineTarget {
+            bound_pipeline: Cell::new(None),
+            _sendsync_optout: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub unsafe fn bind<'a>(
+        &'a self,
+        pipeline: &'a RawProgramPipeline,
+        gl: &Gl,
+    ) -> RawBoundProgramPipeline<'a> {
+        self.bind_handle(Some(pipeline.handle), gl);
+        RawBoundProgramPipeline(pipeline)
+    }
+}
+
+impl Bind for RawProgramPipelineTarget {
+    const BINDING: GLenum = gl::PROGRAM_PIPELINE_BINDING;
+
+    #[inline]
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_pipeline
+    }
+
+    #[inline]
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.BindProgramPipeline(handle.map(|h| h.get()).unwrap_or(0));
+    }
+}
+
+impl<'a> RawBoundProgramPipeline<'a> {
+    /// Makes `program` the target of `glUniform*` calls. The pipeline must not be overridden by a
+    /// program bound with `glUseProgram`.
+    #[inline]
+    pub(crate) fn set_active_program(&self, program: &RawProgram, gl: &Gl) {
+        unsafe { gl.ActiveShaderProgram(self.0.handle.get(), program.handle.get()) };
+    }
 }
 
 impl<'a> RawBoundProgram<'a> {
+    /// Sets the constant value of each attribute location in `defaults`. Locations that have an
+    /// enabled vertex array in the bound VAO ignore the value.
+    pub(crate) fn upload_attrib_defaults(&self, defaults: &[(GLuint, GLVec4<f32>)], gl: &Gl) {
+        for &(loc, value) in defaults {
+            unsafe { gl.VertexAttrib4f(loc, value.x, value.y, value.z, value.w) };
+        }
+    }
+
     pub(crate) fn upload_uniforms<U: Uniforms>(
         &self,
         uniforms: &U,
@@ -383,42 +873,55 @@ impl<'a> RawBoundProgram<'a> {
         image_units: &ImageUnits,
         gl: &Gl,
     ) {
-        struct UniformsUploader<'a, U: Uniforms> {
-            locs: &'a [GLint],
-            loc_index: usize,
-            unit: u32,
-            image_units: &'a ImageUnits,
-            gl: &'a Gl,
-            uniforms: &'a U,
-        }
-        impl<'a, U: Uniforms> UniformsMemberRegistry for UniformsUploader<'a, U> {
-            type Uniforms = U;
-            fn add_member<T: UniformType>(&mut self, _: &str, get_member: fn(&U) -> T) {
-                let loc = self.locs[self.loc_index];
-                if loc != -1 {
-                    let mut binder = TextureUniformBinder {
-                        image_units: &self.image_units,
-                        unit: &mut self.unit,
-                    };
-                    unsafe {
-                        get_member(self.uniforms).upload(loc, &mut binder, self.gl);
-                    }
-                }
+        let mut binds = image_units.bind_buffer();
+        upload_uniform_values(uniforms, locs, &mut binds, gl);
+        unsafe { image_units.bind_units(0, binds, gl) };
+    }
+}
 
-                debug_assert_eq!(0, unsafe { self.gl.GetError() });
-                self.loc_index += 1;
+/// Uploads `uniforms` to the active program, adding the textures used by the uniforms to `binds`.
+/// Texture uniforms get set to the unit their texture will occupy once `binds` gets bound.
+pub(crate) fn upload_uniform_values<U: Uniforms>(
+    uniforms: &U,
+    locs: &[GLint],
+    binds: &mut Vec<UnitBind>,
+    gl: &Gl,
+) {
+    struct UniformsUploader<'a, U: Uniforms> {
+        locs: &'a [GLint],
+        loc_index: usize,
+        binds: &'a mut Vec<UnitBind>,
+        gl: &'a Gl,
+        uniforms: &'a U,
+    }
+    impl<'a, U: Uniforms> UniformsMemberRegistry for UniformsUploader<'a, U> {
+        type Uniforms = U;
+        fn add_member<T: UniformType>(&mut self, _: &str, get_member: fn(&U) -> T) {
+            // Checking for errors stalls the pipeline, so uniform uploads made while drawing only
+            // get checked in debug builds.
+            let _error_scope =
+                cfg!(debug_assertions).then(|| ErrorScope::new(self.gl, "uniform upload"));
+            let loc = self.locs[self.loc_index];
+            if loc != -1 {
+                let mut binder = TextureUniformBinder {
+                    binds: self.binds,
+                };
+                unsafe {
+                    get_member(self.uniforms).upload(loc, &mut binder, self.gl);
+                }
             }
-        }
 
-        U::members(UniformsUploader {
-            locs,
-            loc_index: 0,
-            unit: 0,
-            image_units,
-            gl,
-            uniforms,
-        })
+            self.loc_index += 1;
+        }
     }
+
+    U::members(UniformsUploader {
+        locs,
+        loc_index: 0,
+        binds,
+        gl,
+        uniforms,
+    });
 }
 
 pub unsafe fn build_info_buffer(
@@ -517,6 +1020,20 @@ impl<'a, V: Vertex> VertexMemberRegistry for AttribTypeChecker<'a, V> {
     {
         self.check_type(name, TypeTag::Single(T::prim_tag()));
     }
+    fn add_vertex_attrib_normalized<T>(&mut self, name: &str, _: fn(*const V) -> *const T)
+    where
+        T: TransparentType,
+    {
+        let tag = AttribConversion::Normalized.glsl_type::<T>(name);
+        self.check_type(name, TypeTag::Single(tag));
+    }
+    fn add_vertex_attrib_integer<T>(&mut self, name: &str, _: fn(*const V) -> *const T)
+    where
+        T: TransparentType,
+    {
+        let tag = AttribConversion::Integer.glsl_type::<T>(name);
+        self.check_type(name, TypeTag::Single(tag));
+    }
 }
 impl<'a, U: Uniforms> UniformsMemberRegistry for AttribTypeChecker<'a, U> {
     type Uniforms = U;
@@ -527,6 +1044,7 @@ impl<'a, U: Uniforms> UniformsMemberRegistry for AttribTypeChecker<'a, U> {
 
 unsafe impl<V: Vertex> ShaderStage for VertexStage<V> {
     const SHADER_TYPE_ENUM: GLenum = gl::VERTEX_SHADER;
+    const STAGE_BIT: GLbitfield = gl::VERTEX_SHADER_BIT;
 
     unsafe fn program_pre_link_hook(program: &RawProgram, gl: &Gl) {
         struct VertexAttribLocBinder<'a, V: Vertex> {
@@ -557,19 +1075,19 @@ unsafe impl<V: Vertex> ShaderStage for VertexStage<V> {
                 let cstr = CString::new(cstr_bytes).expect("Null terminator in member name string");
 
                 unsafe {
+                    let _error_scope = ErrorScope::new(self.gl, "glBindAttribLocation");
                     self.gl.BindAttribLocation(
                         self.program.handle.get(),
                         self.location,
                         cstr.as_ptr(),
                     );
-                    assert_eq!(0, self.gl.GetError());
                 }
 
                 let mut cstr_bytes = cstr.into_bytes();
                 cstr_bytes.clear();
 
                 mem::swap(&mut cstr_bytes, &mut self.cstr_bytes);
-                self.location += T::prim_tag().num_attrib_slots() as u32;
+                self.location += T::prim_tag().num_attrib_locations() as u32;
             }
         }
 
@@ -609,9 +1127,11 @@ unsafe impl<V: Vertex> ShaderStage for VertexStage<V> {
 }
 unsafe impl ShaderStage for GeometryStage {
     const SHADER_TYPE_ENUM: GLenum = gl::GEOMETRY_SHADER;
+    const STAGE_BIT: GLbitfield = gl::GEOMETRY_SHADER_BIT;
 }
 unsafe impl<A: Attachments> ShaderStage for FragmentStage<A> {
     const SHADER_TYPE_ENUM: GLenum = gl::FRAGMENT_SHADER;
+    const STAGE_BIT: GLbitfield = gl::FRAGMENT_SHADER_BIT;
     unsafe fn program_pre_link_hook(program: &RawProgram, gl: &Gl) {
         struct FragDataBinder<'a, A: Attachments> {
             cstr_bytes: Vec<u8>,
@@ -645,12 +1165,12 @@ unsafe impl<A: Attachments> ShaderStage for FragmentStage<A> {
                         CString::new(cstr_bytes).expect("Null terminator in member name string");
 
                     unsafe {
+                        let _error_scope = ErrorScope::new(self.gl, "glBindFragDataLocation");
                         self.gl.BindFragDataLocation(
                             self.program.handle.get(),
                             self.location,
                             cstr.as_ptr(),
                         );
-                        assert_eq!(0, self.gl.GetError());
                     }
 
                     let mut cstr_bytes = cstr.into_bytes();
@@ -708,6 +1228,7 @@ unsafe impl<A: Attachments> ShaderStage for FragmentStage<A> {
                         CString::new(cstr_bytes).expect("Null terminator in member name string");
 
                     unsafe {
+                        let _error_scope = ErrorScope::new(self.gl, "glGetFragDataLocation");
                         let data_location = self
                             .gl
                             .GetFragDataLocation(self.program.handle.get(), cstr.as_ptr());
@@ -715,7 +1236,6 @@ unsafe impl<A: Attachments> ShaderStage for FragmentStage<A> {
                             self.warnings
                                 .push(ProgramWarning::UnusedColorAttachment(name.to_string()));
                         }
-                        assert_eq!(0, self.gl.GetError());
                     }
 
                     let mut cstr_bytes = cstr.into_bytes();
@@ -0,0 +1,228 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Saving and restoring OpenGL state around Gullery rendering.
+
+use crate::{
+    bind::Bind,
+    gl::{self, types::*, Gl},
+    ContextState, Handle,
+};
+
+/// Guard that saves OpenGL state on creation and restores it when dropped.
+///
+/// This is meant for embedding Gullery into a host renderer that shares its OpenGL context. Wrap
+/// Gullery's rendering in a `StateScope`, and the host's state gets left as it was found.
+///
+/// The following state gets saved and restored:
+///
+/// * The bound vertex array object, program, read and draw framebuffers, renderbuffer, and
+///   `ARRAY_BUFFER`, `COPY_READ_BUFFER` and `COPY_WRITE_BUFFER` buffers.
+/// * The active texture unit. *Texture and sampler bindings aren't restored.*
+/// * Blending, face culling, depth testing and stencil testing enables, along with the blend
+///   functions, cull face, front face and depth function.
+/// * The depth mask, color mask and viewport.
+/// * The pixel pack and unpack alignments and image heights.
+/// * The provoking vertex, if it's supported.
+///
+/// Creating the scope also resynchronizes Gullery's cached state with OpenGL, since the host may
+/// have changed it. Gullery's render state cache goes stale once the host's state gets restored,
+/// so all Gullery rendering should happen within a scope when sharing a context.
+pub struct StateScope<'a> {
+    state: &'a ContextState,
+    saved: SavedState,
+}
+
+struct SavedState {
+    vao: Option<Handle>,
+    program: Option<Handle>,
+    read_framebuffer: Option<Handle>,
+    draw_framebuffer: Option<Handle>,
+    renderbuffer: Option<Handle>,
+    copy_read_buffer: Option<Handle>,
+    copy_write_buffer: Option<Handle>,
+    array_buffer: GLint,
+    active_unit: u32,
+
+    blend: bool,
+    blend_funcs: [GLint; 4],
+    cull: bool,
+    cull_face: GLint,
+    front_face: GLint,
+    depth_test: bool,
+    depth_func: GLint,
+    stencil_test: bool,
+    stencil_write_masks: [GLint; 2],
+    depth_mask: GLboolean,
+    color_mask: [GLboolean; 4],
+    viewport: [GLint; 4],
+    pack_alignment: GLint,
+    unpack_alignment: GLint,
+    pack_image_height: GLint,
+    unpack_image_height: GLint,
+    provoking_vertex: Option<GLint>,
+}
+
+impl<'a> StateScope<'a> {
+    pub fn new(state: &'a ContextState) -> StateScope<'a> {
+        let gl = &state.gl;
+        let saved = unsafe {
+            let get_int = |pname| {
+                let mut value = 0;
+                gl.GetIntegerv(pname, &mut value);
+                value
+            };
+
+            let mut blend_funcs = [0; 4];
+            for (func, &pname) in blend_funcs.iter_mut().zip(&[
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ]) {
+                *func = get_int(pname);
+            }
+            let mut depth_mask = 0;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+            let mut color_mask = [0; 4];
+            gl.GetBooleanv(gl::COLOR_WRITEMASK, color_mask.as_mut_ptr());
+            let mut viewport = [0; 4];
+            gl.GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+            SavedState {
+                vao: state.vao_target.0.sync_bind(gl),
+                program: state.program_target.0.sync_bind(gl),
+                read_framebuffer: state.framebuffer_targets.read.sync_bind(gl),
+                draw_framebuffer: state.framebuffer_targets.draw.sync_bind(gl),
+                renderbuffer: state.renderbuffer_target.0.sync_bind(gl),
+                copy_read_buffer: state.buffer_binds.copy_read.sync_bind(gl),
+                copy_write_buffer: state.buffer_binds.copy_write.sync_bind(gl),
+                array_buffer: get_int(gl::ARRAY_BUFFER_BINDING),
+                active_unit: state.image_units.0.invalidate(gl),
+
+                blend: gl.IsEnabled(gl::BLEND) != 0,
+                blend_funcs,
+                cull: gl.IsEnabled(gl::CULL_FACE) != 0,
+                cull_face: get_int(gl::CULL_FACE_MODE),
+                front_face: get_int(gl::FRONT_FACE),
+                depth_test: gl.IsEnabled(gl::DEPTH_TEST) != 0,
+                depth_func: get_int(gl::DEPTH_FUNC),
+                stencil_test: gl.IsEnabled(gl::STENCIL_TEST) != 0,
+                stencil_write_masks: [
+                    get_int(gl::STENCIL_WRITEMASK),
+                    get_int(gl::STENCIL_BACK_WRITEMASK),
+                ],
+                depth_mask,
+                color_mask,
+                viewport,
+                pack_alignment: get_int(gl::PACK_ALIGNMENT),
+                unpack_alignment: get_int(gl::UNPACK_ALIGNMENT),
+                pack_image_height: get_int(gl::PACK_IMAGE_HEIGHT),
+                unpack_image_height: get_int(gl::UNPACK_IMAGE_HEIGHT),
+                provoking_vertex: match state.provoking_vertex_supported() {
+                    true => Some(get_int(gl::PROVOKING_VERTEX)),
+                    false => None,
+                },
+            }
+        };
+
+        unsafe {
+            gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl.PixelStorei(gl::PACK_IMAGE_HEIGHT, 0);
+            gl.PixelStorei(gl::UNPACK_IMAGE_HEIGHT, 0);
+            if saved.provoking_vertex.is_some() {
+                gl.ProvokingVertex(state.provoking_vertex().into());
+            }
+        }
+        // The host may have changed any of the render state since gullery last uploaded it, so
+        // upload all of it instead of just the fields that differ from the cache.
+        let render_state = state.render_state.take();
+        render_state.upload_all(gl);
+        state.render_state.set(render_state);
+
+        StateScope { state, saved }
+    }
+}
+
+impl<'a> Drop for StateScope<'a> {
+    fn drop(&mut self) {
+        let state = self.state;
+        let gl = &state.gl;
+        let saved = &self.saved;
+        unsafe {
+            state.vao_target.0.force_bind(saved.vao, gl);
+            state.program_target.0.force_bind(saved.program, gl);
+            state
+                .framebuffer_targets
+                .read
+                .force_bind(saved.read_framebuffer, gl);
+            state
+                .framebuffer_targets
+                .draw
+                .force_bind(saved.draw_framebuffer, gl);
+            state
+                .renderbuffer_target
+                .0
+                .force_bind(saved.renderbuffer, gl);
+            state
+                .buffer_binds
+                .copy_read
+                .force_bind(saved.copy_read_buffer, gl);
+            state
+                .buffer_binds
+                .copy_write
+                .force_bind(saved.copy_write_buffer, gl);
+            gl.BindBuffer(gl::ARRAY_BUFFER, saved.array_buffer as GLuint);
+            state.image_units.0.set_active_unit(saved.active_unit, gl);
+
+            set_cap(gl, gl::BLEND, saved.blend);
+            let [src_rgb, dst_rgb, src_alpha, dst_alpha] = saved.blend_funcs;
+            gl.BlendFuncSeparate(
+                src_rgb as GLenum,
+                dst_rgb as GLenum,
+                src_alpha as GLenum,
+                dst_alpha as GLenum,
+            );
+            set_cap(gl, gl::CULL_FACE, saved.cull);
+            gl.CullFace(saved.cull_face as GLenum);
+            gl.FrontFace(saved.front_face as GLenum);
+            set_cap(gl, gl::DEPTH_TEST, saved.depth_test);
+            gl.DepthFunc(saved.depth_func as GLenum);
+            set_cap(gl, gl::STENCIL_TEST, saved.stencil_test);
+            let [front_mask, back_mask] = saved.stencil_write_masks;
+            gl.StencilMaskSeparate(gl::FRONT, front_mask as GLuint);
+            gl.StencilMaskSeparate(gl::BACK, back_mask as GLuint);
+            gl.DepthMask(saved.depth_mask);
+            let [r, g, b, a] = saved.color_mask;
+            gl.ColorMask(r, g, b, a);
+            let [x, y, width, height] = saved.viewport;
+            gl.Viewport(x, y, width, height);
+            gl.PixelStorei(gl::PACK_ALIGNMENT, saved.pack_alignment);
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, saved.unpack_alignment);
+            gl.PixelStorei(gl::PACK_IMAGE_HEIGHT, saved.pack_image_height);
+            gl.PixelStorei(gl::UNPACK_IMAGE_HEIGHT, saved.unpack_image_height);
+            if let Some(provoking_vertex) = saved.provoking_vertex {
+                gl.ProvokingVertex(provoking_vertex as GLenum);
+            }
+        }
+    }
+}
+
+unsafe fn set_cap(gl: &Gl, cap: GLenum, enable: bool) {
+    match enable {
+        true => gl.Enable(cap),
+        false => gl.Disable(cap),
+    }
+}
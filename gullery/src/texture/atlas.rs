@@ -0,0 +1,289 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Texture atlases, for packing many small images into a single texture.
+
+use super::{Texture, TextureCreateError};
+use crate::{
+    geometry::{GLVec2, NonNormalized, D2},
+    image_format::ConcreteImageFormat,
+    ContextState,
+};
+
+use std::{error::Error, fmt, rc::Rc};
+
+/// Algorithm used by an [`Atlas`] to decide where images get placed.
+///
+/// [`Atlas`]: ./struct.Atlas.html
+pub trait AtlasPacker {
+    /// Reserves a region of the given size, returning the offset of its lower-left corner.
+    ///
+    /// Returns `None` if there isn't enough free space for the region.
+    fn pack(&mut self, dims: GLVec2<u32, NonNormalized>) -> Option<GLVec2<u32, NonNormalized>>;
+
+    /// Frees all reserved regions.
+    fn clear(&mut self);
+}
+
+/// Simple packer that places images left-to-right in horizontal shelves.
+///
+/// Each image goes into the shelf that wastes the least vertical space, with a new shelf getting
+/// opened if none of the existing ones fit. This works well when images have similar heights, like
+/// glyphs of a single font.
+#[derive(Debug, Clone)]
+pub struct ShelfPacker {
+    dims: GLVec2<u32, NonNormalized>,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// The first free horizontal position in the shelf.
+    x: u32,
+}
+
+/// A region of an [`Atlas`] containing an inserted image.
+///
+/// [`Atlas`]: ./struct.Atlas.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRect {
+    /// The region's offset into the texture, in pixels.
+    pub offset: GLVec2<u32, NonNormalized>,
+    /// The region's dimensions, in pixels.
+    pub dims: GLVec2<u32, NonNormalized>,
+    /// The texture coordinates of the region's lower-left corner.
+    pub uv_min: GLVec2<f32>,
+    /// The texture coordinates of the region's upper-right corner.
+    pub uv_max: GLVec2<f32>,
+}
+
+/// Error returned when an image doesn't fit in the free space of an [`Atlas`].
+///
+/// [`Atlas`]: ./struct.Atlas.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasFull {
+    pub dims: GLVec2<u32, NonNormalized>,
+}
+
+/// A 2D texture with images packed into it at runtime.
+///
+/// Images get uploaded with `sub_image` as they're inserted, and each insertion returns the
+/// region of the texture the image was placed in. This is mainly useful for font and sprite
+/// rendering, where drawing many images out of one texture saves on texture binds.
+///
+/// Placement is handled by an [`AtlasPacker`], which defaults to [`ShelfPacker`].
+///
+/// [`AtlasPacker`]: ./trait.AtlasPacker.html
+/// [`ShelfPacker`]: ./struct.ShelfPacker.html
+pub struct Atlas<F, P = ShelfPacker>
+where
+    F: ConcreteImageFormat,
+    P: AtlasPacker,
+{
+    texture: Texture<D2, F>,
+    packer: P,
+}
+
+impl ShelfPacker {
+    pub fn new(dims: GLVec2<u32, NonNormalized>) -> ShelfPacker {
+        ShelfPacker {
+            dims,
+            shelves: Vec::new(),
+        }
+    }
+}
+
+impl AtlasPacker for ShelfPacker {
+    fn pack(&mut self, dims: GLVec2<u32, NonNormalized>) -> Option<GLVec2<u32, NonNormalized>> {
+        if dims.x > self.dims.x || dims.y > self.dims.y {
+            return None;
+        }
+
+        let atlas_width = self.dims.x;
+        let best_shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| dims.y <= shelf.height && dims.x <= atlas_width - shelf.x)
+            .min_by_key(|shelf| shelf.height - dims.y);
+
+        let shelf = match best_shelf {
+            Some(shelf) => shelf,
+            None => {
+                let y = self.shelves.last().map(|s| s.y + s.height).unwrap_or(0);
+                if dims.y > self.dims.y - y {
+                    return None;
+                }
+                self.shelves.push(Shelf {
+                    y,
+                    height: dims.y,
+                    x: 0,
+                });
+                self.shelves.last_mut().unwrap()
+            }
+        };
+
+        let offset = GLVec2::new(shelf.x, shelf.y);
+        shelf.x += dims.x;
+        Some(offset)
+    }
+
+    fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+impl<F: ConcreteImageFormat> Atlas<F> {
+    /// Creates an empty atlas with the given dimensions, using a [`ShelfPacker`].
+    ///
+    /// [`ShelfPacker`]: ./struct.ShelfPacker.html
+    pub fn new(
+        dims: GLVec2<u32, NonNormalized>,
+        state: Rc<ContextState>,
+    ) -> Result<Atlas<F>, TextureCreateError<D2, F>> {
+        Atlas::with_packer(dims, ShelfPacker::new(dims), state)
+    }
+}
+
+impl<F, P> Atlas<F, P>
+where
+    F: ConcreteImageFormat,
+    P: AtlasPacker,
+{
+    /// Creates an empty atlas with the given dimensions and packer.
+    ///
+    /// The packer must place regions within `dims`.
+    pub fn with_packer(
+        dims: GLVec2<u32, NonNormalized>,
+        packer: P,
+        state: Rc<ContextState>,
+    ) -> Result<Atlas<F, P>, TextureCreateError<D2, F>> {
+        Ok(Atlas {
+            texture: Texture::with_mip_count(dims, 1, state)?,
+            packer,
+        })
+    }
+
+    /// Packs an image into the atlas and uploads it, returning the region it was placed in.
+    ///
+    /// ## Panics
+    /// Panics if `image` doesn't contain `dims.x * dims.y` pixels, or if the packer places the
+    /// image outside of the texture.
+    pub fn insert(
+        &mut self,
+        dims: GLVec2<u32, NonNormalized>,
+        image: &[F],
+    ) -> Result<AtlasRect, AtlasFull> {
+        let offset = self.packer.pack(dims).ok_or(AtlasFull { dims })?;
+
+        let atlas_dims = self.texture.dims();
+        assert!(
+            offset.x + dims.x <= atlas_dims.x && offset.y + dims.y <= atlas_dims.y,
+            "packer placed image outside of atlas"
+        );
+        if dims.x != 0 && dims.y != 0 {
+            self.texture.sub_image(0, offset, dims, image);
+        }
+
+        let (width, height) = (atlas_dims.x as f32, atlas_dims.y as f32);
+        Ok(AtlasRect {
+            offset,
+            dims,
+            uv_min: GLVec2::new(offset.x as f32 / width, offset.y as f32 / height),
+            uv_max: GLVec2::new(
+                (offset.x + dims.x) as f32 / width,
+                (offset.y + dims.y) as f32 / height,
+            ),
+        })
+    }
+
+    /// Frees all regions of the atlas, so that new images can be inserted over the old ones.
+    ///
+    /// This doesn't modify the texture's contents.
+    pub fn clear(&mut self) {
+        self.packer.clear();
+    }
+
+    #[inline]
+    pub fn texture(&self) -> &Texture<D2, F> {
+        &self.texture
+    }
+
+    #[inline]
+    pub fn packer(&self) -> &P {
+        &self.packer
+    }
+
+    #[inline]
+    pub fn into_texture(self) -> Texture<D2, F> {
+        self.texture
+    }
+}
+
+impl Error for AtlasFull {}
+
+impl fmt::Display for AtlasFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "no free space for {}x{} image in atlas",
+            self.dims.x, self.dims.y
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn shelf_packer_no_overlap(sizes: Vec<(u8, u8)>) -> bool {
+            let atlas_dims = GLVec2::new(256, 256);
+            let mut packer = ShelfPacker::new(atlas_dims);
+            let mut rects: Vec<(GLVec2<u32, NonNormalized>, GLVec2<u32, NonNormalized>)> = Vec::new();
+
+            for (w, h) in sizes {
+                let dims = GLVec2::new(w as u32 + 1, h as u32 + 1);
+                if let Some(offset) = packer.pack(dims) {
+                    if offset.x + dims.x > atlas_dims.x || offset.y + dims.y > atlas_dims.y {
+                        return false;
+                    }
+                    let overlaps = rects.iter().any(|&(o, d)| {
+                        offset.x < o.x + d.x && o.x < offset.x + dims.x &&
+                        offset.y < o.y + d.y && o.y < offset.y + dims.y
+                    });
+                    if overlaps {
+                        return false;
+                    }
+                    rects.push((offset, dims));
+                }
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn shelf_packer_full() {
+        let mut packer = ShelfPacker::new(GLVec2::new(4, 4));
+        assert_eq!(Some(GLVec2::new(0, 0)), packer.pack(GLVec2::new(4, 2)));
+        assert_eq!(Some(GLVec2::new(0, 2)), packer.pack(GLVec2::new(2, 2)));
+        assert_eq!(Some(GLVec2::new(2, 2)), packer.pack(GLVec2::new(2, 2)));
+        assert_eq!(None, packer.pack(GLVec2::new(1, 1)));
+        assert_eq!(None, packer.pack(GLVec2::new(5, 1)));
+
+        packer.clear();
+        assert_eq!(Some(GLVec2::new(0, 0)), packer.pack(GLVec2::new(1, 1)));
+    }
+}
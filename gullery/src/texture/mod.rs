@@ -87,15 +87,17 @@
 
 #[macro_use]
 pub mod sample_parameters;
+mod atlas;
 mod raw;
 
-use crate::gl::{types::*, Gl};
+use crate::gl::{self, types::*, Gl};
 
 use self::{raw::*, sample_parameters::*};
 use crate::{
-    geometry::{Dimension, D1, D2, D3},
-    image_format::{ConcreteImageFormat, ImageFormat},
-    ContextState, GLObject, Handle,
+    geometry::{Dimension, GLVec2, GLVec3, NonNormalized, D1, D2, D3},
+    image_format::{ConcreteImageFormat, ImageFormat, ImageFormatRenderable},
+    object_registry::ObjectKind,
+    ContextState, GLError, GLObject, Handle,
 };
 
 use crate::{
@@ -103,12 +105,14 @@ use crate::{
     uniform::{TextureUniformBinder, UniformType},
 };
 
-use std::{cell::Cell, error::Error, fmt, io, mem, rc::Rc};
+use std::{any, cell::Cell, error::Error, fmt, io, mem, rc::Rc};
 
+pub use self::atlas::{Atlas, AtlasFull, AtlasPacker, AtlasRect, ShelfPacker};
 pub use self::raw::{
     types, Dims, DimsSquare, Image, MipSelector, TextureType, TextureTypeBasicImage,
     TextureTypeRenderable,
 };
+pub(crate) use self::raw::UnitBind;
 
 /// OpenGL Texture object.
 // This is repr C in order to guarantee that the `to_dyn` casts work.
@@ -119,6 +123,7 @@ where
     T: ?Sized + TextureType<D>,
 {
     raw: RawTexture<D, T>,
+    sample_parameters: Cell<SampleParameters>,
     state: Rc<ContextState>,
 }
 
@@ -143,6 +148,16 @@ where
     pub texture: &'a Texture<D, T>,
 }
 
+/// A bindless handle to a [`Texture`], as provided by the `GL_ARB_bindless_texture` extension.
+///
+/// The handle can be uploaded as a `uvec2` uniform, which the shader can then convert to a
+/// sampler with a constructor like `sampler2D(handle)`. A handle must be made resident with
+/// [`Texture::make_resident`] before a shader reads from it.
+///
+/// [`Texture::make_resident`]: ./struct.Texture.html#method.make_resident
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(u64);
+
 #[derive(Debug, Clone)]
 pub enum TextureCreateError<D, T>
 where
@@ -150,12 +165,65 @@ where
     T: TextureType<D>,
 {
     DimsExceedMax { requested: T::Dims, max: T::Dims },
+    SamplesExceedMax { requested: u8, max: u32 },
+    /// Sparse storage was requested, but the OpenGL implementation doesn't support
+    /// `GL_ARB_sparse_texture`, or doesn't support sparse textures with this format and type.
+    SparseUnsupported,
+    /// Sparse storage was requested with dimensions that aren't a multiple of the virtual page
+    /// size.
+    DimsNotPageAligned {
+        requested: T::Dims,
+        page_size: GLVec3<u32, NonNormalized>,
+    },
+    /// OpenGL reported an error while allocating the texture's storage. This is usually
+    /// `GLError::OutOfMemory`.
+    GLError(GLError),
+}
+
+/// Error returned when committing or decommitting a region of a sparse texture fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseCommitError {
+    /// The texture wasn't created with sparse storage.
+    NotSparse,
+    /// The region doesn't start on a page boundary, or doesn't end on one without reaching the
+    /// edge of the mip level.
+    NotPageAligned {
+        page_size: GLVec3<u32, NonNormalized>,
+    },
+}
+
+/// Error returned by [`Texture::set_sample_parameters`] when the parameters can't be used with the
+/// texture.
+///
+/// [`Texture::set_sample_parameters`]: ./struct.Texture.html#method.set_sample_parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleParametersError {
+    /// The minification filter samples from mipmaps, but the texture only has a base level.
+    MipFilterWithoutMips(FilterMin),
+    /// The texture is missing a mip level that the minification filter samples from.
+    Incomplete(TextureIncomplete),
+    /// A reduction mode was set, but the OpenGL implementation doesn't support
+    /// `GL_ARB_texture_filter_minmax`.
+    ReductionModeUnsupported,
+    /// A min or max reduction mode was set on a texture with an integer image format, which
+    /// can't be filtered.
+    ReductionModeNonFilterable(ReductionMode),
+}
+
+/// Error returned when a texture is missing a mip level that its minification filter samples from.
+///
+/// OpenGL treats such textures as incomplete, and sampling them returns black.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureIncomplete {
+    /// The first mip level that hasn't had an image allocated.
+    pub missing_level: u8,
+    pub filter_min: FilterMin,
 }
 
 impl<D, T> GLObject for Texture<D, T>
 where
     D: Dimension<u32>,
-    T: TextureType<D>,
+    T: ?Sized + TextureType<D>,
 {
     #[inline(always)]
     fn handle(&self) -> Handle {
@@ -178,11 +246,7 @@ impl GLObject for Sampler {
     }
 }
 
-pub(crate) struct ImageUnits(RawImageUnits);
-pub(crate) struct BoundTexture<'a, D, T>(RawBoundTexture<'a, D, T>)
-where
-    D: Dimension<u32>,
-    T: ?Sized + TextureType<D>;
+pub(crate) struct ImageUnits(pub(crate) RawImageUnits);
 
 impl<D, T> Texture<D, T>
 where
@@ -190,6 +254,53 @@ where
     T: TextureType<D>,
     T::Format: ConcreteImageFormat,
 {
+    fn track(&self) {
+        self.state.object_registry.track(
+            ObjectKind::Texture,
+            self.handle(),
+            any::type_name::<Self>(),
+            || {
+                if self.raw.sparse_page_size().is_some() {
+                    return None;
+                }
+                let dims = self.raw.dims();
+                let size = (0..self.raw.num_mips())
+                    .map(|level| {
+                        let mip_dims = dims.mip_dims(level as GLint);
+                        T::Format::bytes_for_dims(GLVec3::new(
+                            mip_dims.width(),
+                            mip_dims.height(),
+                            mip_dims.depth(),
+                        ))
+                    })
+                    .sum::<usize>();
+                let faces = match T::BIND_TARGET {
+                    gl::TEXTURE_CUBE_MAP => 6,
+                    _ => 1,
+                };
+                Some(size * faces)
+            },
+        );
+    }
+
+    /// Wraps a freshly-allocated `raw` texture, surfacing any error from allocating its images.
+    ///
+    /// The texture gets built before checking `alloc_result` so that `raw` is deleted on failure.
+    fn finish_create(
+        raw: RawTexture<D, T>,
+        state: Rc<ContextState>,
+        alloc_result: Result<(), GLError>,
+    ) -> Result<Texture<D, T>, TextureCreateError<D, T>> {
+        let texture = Texture {
+            raw,
+            sample_parameters: Cell::new(SampleParameters::default()),
+            state,
+        };
+        alloc_result.map_err(TextureCreateError::GLError)?;
+        texture.track();
+        Ok(texture)
+    }
+
     fn check_max_size(dims: T::Dims, state: &ContextState) -> Result<(), TextureCreateError<D, T>> {
         let max_size = T::max_size(&state);
         let (max_width, max_height, max_depth) =
@@ -226,7 +337,7 @@ where
         Self::check_max_size(dims, &state)?;
 
         let mut raw = RawTexture::new(dims, &state.gl);
-        {
+        let alloc_result = {
             let last_unit = state.image_units.0.num_units() - 1;
             let mut bind = unsafe {
                 state
@@ -234,16 +345,40 @@ where
                     .0
                     .bind_texture_mut(last_unit, &mut raw, &state.gl)
             };
-            for level in mip_count.iter_less() {
-                bind.alloc_image::<!>(level, (), None);
-            }
-
             if mip_count == 0 {
                 panic!("mip_count must be greater than 0");
             }
-        }
 
-        Ok(Texture { raw, state })
+            mip_count
+                .iter_less()
+                .try_for_each(|level| bind.alloc_image::<!>(level, (), None))
+        };
+
+        Self::finish_create(raw, state, alloc_result)
+    }
+
+    /// Creates a new render target texture with the given number of mip levels, without
+    /// uploading any data to the GPU.
+    ///
+    /// This is identical to [`with_mip_count`], but only accepts renderable image formats. The
+    /// returned texture can be attached to a [`FramebufferObject`] immediately, which makes this
+    /// the path for creating G-buffers and post-processing targets.
+    ///
+    /// ## Panics
+    /// Will panic if `mip_count == 0`.
+    ///
+    /// [`with_mip_count`]: ./struct.Texture.html#method.with_mip_count
+    /// [`FramebufferObject`]: ../framebuffer/struct.FramebufferObject.html
+    #[inline]
+    pub fn new_uninitialized(
+        dims: T::Dims,
+        mip_count: u8,
+        state: Rc<ContextState>,
+    ) -> Result<Texture<D, T>, TextureCreateError<D, T>>
+    where
+        T: TextureTypeRenderable<D, MipSelector = u8, Samples = ()>,
+    {
+        Self::with_mip_count(dims, mip_count, state)
     }
 
     /// Creates a new texture with the given images.
@@ -268,7 +403,7 @@ where
         Self::check_max_size(dims, &state)?;
 
         let mut raw = RawTexture::new(dims, &state.gl);
-        {
+        let alloc_result = {
             // We use the last texture unit to make sure that a program never accidentally uses a texture bound
             // during modification. We should probably make sure programs never accidentally use that unit.
             let last_unit = state.image_units.0.num_units() - 1;
@@ -279,16 +414,18 @@ where
                     .bind_texture_mut(last_unit, &mut raw, &state.gl)
             };
 
-            for (level, image) in image_mips.into_iter().enumerate() {
-                bind.alloc_image(level as u8, (), Some(image));
-            }
+            let result = image_mips
+                .into_iter()
+                .enumerate()
+                .try_for_each(|(level, image)| bind.alloc_image(level as u8, (), Some(image)));
 
             if bind.raw_tex().num_mips() == 0 {
                 panic!("image_mips iterator must contain at least one image");
             }
-        }
+            result
+        };
 
-        Ok(Texture { raw, state })
+        Self::finish_create(raw, state, alloc_result)
     }
 
     /// Creates a new texture with a single image.
@@ -306,7 +443,7 @@ where
         Self::check_max_size(dims, &state)?;
 
         let mut raw = RawTexture::new(dims, &state.gl);
-        {
+        let alloc_result = {
             let last_unit = state.image_units.0.num_units() - 1;
             let mut bind = unsafe {
                 state
@@ -315,10 +452,10 @@ where
                     .bind_texture_mut(last_unit, &mut raw, &state.gl)
             };
 
-            bind.alloc_image(T::MipSelector::base(), (), Some(image));
-        }
+            bind.alloc_image(T::MipSelector::base(), (), Some(image))
+        };
 
-        Ok(Texture { raw, state })
+        Self::finish_create(raw, state, alloc_result)
     }
 
     /// Initializes a texture to use a given number of samples when rendering.
@@ -335,7 +472,7 @@ where
         Self::check_max_size(dims, &state)?;
 
         let mut raw = RawTexture::new(dims, &state.gl);
-        {
+        let alloc_result = {
             let last_unit = state.image_units.0.num_units() - 1;
             let mut bind = unsafe {
                 state
@@ -344,10 +481,10 @@ where
                     .bind_texture_mut(last_unit, &mut raw, &state.gl)
             };
 
-            bind.alloc_image::<!>((), samples, None);
-        }
+            bind.alloc_image::<!>((), samples, None)
+        };
 
-        Ok(Texture { raw, state })
+        Self::finish_create(raw, state, alloc_result)
     }
 
     // You may notice that there's no function for creating a texture with both mipmaps and samples.
@@ -360,6 +497,17 @@ where
     // found a texture format that uses both mipmap levels and sample counts, feel free to open a PR
     // with a link to the offending texture format that adds the required functions.
 
+    /// Uploads `image` into the `sub_dims`-sized region of `mip_level` that starts at `offset`.
+    ///
+    /// ## Panics
+    /// Panics if the region doesn't fit within the mip level, or if the number of pixels in `image`
+    /// doesn't match `sub_dims`. Mip levels shrink with each level; see [`mip_dims`].
+    ///
+    /// Uploads don't return OpenGL errors, since the texture's storage has already been allocated
+    /// and the region gets checked beforehand. An error here indicates a bug in Gullery, and
+    /// panics as well.
+    ///
+    /// [`mip_dims`]: ./struct.Texture.html#method.mip_dims
     #[inline]
     pub fn sub_image<'a, I>(
         &mut self,
@@ -379,6 +527,304 @@ where
         };
         bind.sub_image(mip_level, offset, sub_dims, image);
     }
+
+    /// Downloads the image at `mip_level` into `data`.
+    ///
+    /// This waits for the GPU to finish all pending writes to the texture, so it's best kept out
+    /// of per-frame code.
+    ///
+    /// ## Panics
+    /// Panics if `data.len()` doesn't match the number of pixels in the mip level, or if the
+    /// texture's format is compressed.
+    pub fn get_image(&mut self, mip_level: T::MipSelector, data: &mut [T::Format])
+    where
+        T: TextureTypeBasicImage<D>,
+    {
+        let last_unit = self.state.image_units.0.num_units() - 1;
+        let mut bind = unsafe {
+            self.state
+                .image_units
+                .0
+                .bind_texture_mut(last_unit, &mut self.raw, &self.state.gl)
+        };
+        bind.get_image(mip_level, data);
+    }
+
+    /// The virtual page size of sparse textures of this type, as reported by
+    /// `glGetInternalformativ`.
+    ///
+    /// Returns `None` if the OpenGL implementation doesn't support `GL_ARB_sparse_texture`, or
+    /// doesn't support sparse textures with this format and type.
+    pub fn sparse_page_size(state: &ContextState) -> Option<GLVec3<u32, NonNormalized>> {
+        if !state.sparse_texture_supported() {
+            return None;
+        }
+
+        let internal_format = T::Format::FORMAT.internal_format();
+        let query = |pname| {
+            let mut value = 0;
+            unsafe {
+                state
+                    .gl
+                    .GetInternalformativ(T::BIND_TARGET, internal_format, pname, 1, &mut value)
+            };
+            value as u32
+        };
+        match query(gl::NUM_VIRTUAL_PAGE_SIZES_ARB) {
+            0 => None,
+            _ => Some(GLVec3::new(
+                query(gl::VIRTUAL_PAGE_SIZE_X_ARB),
+                query(gl::VIRTUAL_PAGE_SIZE_Y_ARB),
+                query(gl::VIRTUAL_PAGE_SIZE_Z_ARB),
+            )),
+        }
+    }
+
+    /// Creates a sparse texture with the given number of mip levels.
+    ///
+    /// Sparse textures only have memory backing the regions committed with [`commit_region`], so
+    /// they can be much larger than the memory available to them. Reading from uncommitted
+    /// regions returns undefined values, and writes to them get discarded.
+    ///
+    /// Fails if sparse textures aren't supported for this type, or if `dims` isn't a multiple of
+    /// the [`sparse_page_size`].
+    ///
+    /// ## Panics
+    /// Will panic if `mip_count == 0`.
+    ///
+    /// [`commit_region`]: ./struct.Texture.html#method.commit_region
+    /// [`sparse_page_size`]: ./struct.Texture.html#method.sparse_page_size
+    pub fn with_sparse_storage(
+        dims: T::Dims,
+        mip_count: u8,
+        state: Rc<ContextState>,
+    ) -> Result<Texture<D, T>, TextureCreateError<D, T>>
+    where
+        T: TextureType<D, MipSelector = u8, Samples = ()>,
+    {
+        assert!(mip_count > 0, "mip_count must be greater than 0");
+        Self::check_max_size(dims, &state)?;
+        let page_size =
+            Self::sparse_page_size(&state).ok_or(TextureCreateError::SparseUnsupported)?;
+        if !(dims.width().is_multiple_of(page_size.x)
+            && dims.height().is_multiple_of(page_size.y)
+            && dims.depth().is_multiple_of(page_size.z))
+        {
+            return Err(TextureCreateError::DimsNotPageAligned {
+                requested: dims,
+                page_size,
+            });
+        }
+
+        let mut raw = RawTexture::new(dims, &state.gl);
+        let alloc_result = {
+            let last_unit = state.image_units.0.num_units() - 1;
+            let mut bind = unsafe {
+                state
+                    .image_units
+                    .0
+                    .bind_texture_mut(last_unit, &mut raw, &state.gl)
+            };
+            bind.alloc_storage_sparse(mip_count, page_size)
+        };
+
+        Self::finish_create(raw, state, alloc_result)
+    }
+
+    /// Commits memory to the `sub_dims`-sized region of `mip_level` that starts at `offset`, so
+    /// that it can be written to and read from. Regions of cubemaps cover all six faces.
+    ///
+    /// The region must start on a page boundary, and must end on one unless it reaches the edge
+    /// of the mip level.
+    ///
+    /// ## Panics
+    /// Panics if the region doesn't fit within the mip level.
+    #[inline]
+    pub fn commit_region(
+        &mut self,
+        mip_level: u8,
+        offset: <T::Dims as Dims>::Offset,
+        sub_dims: T::Dims,
+    ) -> Result<(), SparseCommitError>
+    where
+        T: TextureType<D, MipSelector = u8>,
+    {
+        self.page_commitment(mip_level, offset, sub_dims, true)
+    }
+
+    /// Releases the memory backing the `sub_dims`-sized region of `mip_level` that starts at
+    /// `offset`. The region's contents are lost.
+    ///
+    /// The region has the same requirements as in [`commit_region`].
+    ///
+    /// ## Panics
+    /// Panics if the region doesn't fit within the mip level.
+    ///
+    /// [`commit_region`]: ./struct.Texture.html#method.commit_region
+    #[inline]
+    pub fn decommit_region(
+        &mut self,
+        mip_level: u8,
+        offset: <T::Dims as Dims>::Offset,
+        sub_dims: T::Dims,
+    ) -> Result<(), SparseCommitError>
+    where
+        T: TextureType<D, MipSelector = u8>,
+    {
+        self.page_commitment(mip_level, offset, sub_dims, false)
+    }
+
+    fn page_commitment(
+        &mut self,
+        mip_level: u8,
+        offset: <T::Dims as Dims>::Offset,
+        sub_dims: T::Dims,
+        commit: bool,
+    ) -> Result<(), SparseCommitError>
+    where
+        T: TextureType<D, MipSelector = u8>,
+    {
+        let page_size = self
+            .raw
+            .sparse_page_size()
+            .ok_or(SparseCommitError::NotSparse)?;
+        let mip_dims = self.mip_dims(mip_level);
+        let offset = <T::Dims as Dims>::offset_xyz(offset);
+        let sub_dims = GLVec3::new(sub_dims.width(), sub_dims.height(), sub_dims.depth());
+        let mip_dims: GLVec3<u32, NonNormalized> =
+            GLVec3::new(mip_dims.width(), mip_dims.height(), mip_dims.depth());
+        if offset.x + sub_dims.x > mip_dims.x
+            || offset.y + sub_dims.y > mip_dims.y
+            || offset.z + sub_dims.z > mip_dims.z
+        {
+            panic!(
+                "Sparse region with offset {}x{}x{} and dimensions {}x{}x{} doesn't fit in mip \
+                 level {}, which has dimensions {}x{}x{}",
+                offset.x,
+                offset.y,
+                offset.z,
+                sub_dims.x,
+                sub_dims.y,
+                sub_dims.z,
+                mip_level,
+                mip_dims.x,
+                mip_dims.y,
+                mip_dims.z
+            );
+        }
+
+        let aligned = |offset: u32, len: u32, page: u32, mip: u32| {
+            offset.is_multiple_of(page) && (len.is_multiple_of(page) || offset + len == mip)
+        };
+        if !(aligned(offset.x, sub_dims.x, page_size.x, mip_dims.x)
+            && aligned(offset.y, sub_dims.y, page_size.y, mip_dims.y)
+            && aligned(offset.z, sub_dims.z, page_size.z, mip_dims.z))
+        {
+            return Err(SparseCommitError::NotPageAligned { page_size });
+        }
+
+        let last_unit = self.state.image_units.0.num_units() - 1;
+        let mut bind = unsafe {
+            self.state
+                .image_units
+                .0
+                .bind_texture_mut(last_unit, &mut self.raw, &self.state.gl)
+        };
+        bind.page_commitment(mip_level, offset, sub_dims, commit);
+        Ok(())
+    }
+}
+
+impl<C> Texture<D2, types::MultisampleTex<C>>
+where
+    C: ImageFormatRenderable + ConcreteImageFormat,
+{
+    /// Initializes a multisampled texture with immutable storage, using
+    /// `glTexStorage2DMultisample`.
+    ///
+    /// Immutable storage is required to create texture views of multisampled textures, and lets
+    /// the driver skip completeness checks it would otherwise make on every use. Otherwise, this
+    /// behaves the same as [`with_sample_count`].
+    ///
+    /// ## Panics
+    /// Panics if immutable multisampled storage isn't supported. See
+    /// [`ContextState::texture_storage_multisample_supported`].
+    ///
+    /// [`with_sample_count`]: ./struct.Texture.html#method.with_sample_count
+    /// [`ContextState::texture_storage_multisample_supported`]: ../struct.ContextState.html#method.texture_storage_multisample_supported
+    pub fn with_sample_count_immutable(
+        dims: GLVec2<u32, NonNormalized>,
+        samples: u8,
+        state: Rc<ContextState>,
+    ) -> Result<Self, TextureCreateError<D2, types::MultisampleTex<C>>> {
+        assert!(
+            state.texture_storage_multisample_supported(),
+            "glTexStorage2DMultisample requires OpenGL 4.3 or GL_ARB_texture_storage_multisample"
+        );
+        Self::check_max_size(dims, &state)?;
+
+        let mut max_samples = 0;
+        unsafe { state.gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples) };
+        if max_samples < GLint::from(samples) {
+            return Err(TextureCreateError::SamplesExceedMax {
+                requested: samples,
+                max: max_samples as u32,
+            });
+        }
+
+        let mut raw = RawTexture::new(dims, &state.gl);
+        let alloc_result = {
+            let last_unit = state.image_units.0.num_units() - 1;
+            let mut bind = unsafe {
+                state
+                    .image_units
+                    .0
+                    .bind_texture_mut(last_unit, &mut raw, &state.gl)
+            };
+
+            bind.alloc_storage_multisample(samples)
+        };
+
+        Self::finish_create(raw, state, alloc_result)
+    }
+}
+
+impl<C> Texture<D2, types::CubemapTex<C>>
+where
+    C: ConcreteImageFormat,
+{
+    /// Creates a cubemap from six square images, one for each face of the cube.
+    ///
+    /// The faces are given in OpenGL's face order: `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`. This is
+    /// the same order as the `GL_TEXTURE_CUBE_MAP_*` face targets, so images that are numbered for
+    /// OpenGL can be passed in as-is. See [`CubemapImage`] for naming each face explicitly.
+    ///
+    /// ## Panics
+    /// Panics if any face doesn't contain exactly enough pixels for a face of size `dims`.
+    ///
+    /// [`CubemapImage`]: ./types/struct.CubemapImage.html
+    pub fn from_faces(
+        faces: [&[C]; 6],
+        dims: DimsSquare,
+        state: Rc<ContextState>,
+    ) -> Result<Self, TextureCreateError<D2, types::CubemapTex<C>>> {
+        let expected = C::blocks_for_dims(GLVec3::new(dims.side, dims.side, 1));
+        let face_names = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"];
+        for (face, name) in faces.iter().zip(face_names.iter()) {
+            if face.len() != expected {
+                panic!(
+                    "Cubemap face {} has {} blocks, but a {}x{} face needs {} blocks",
+                    name,
+                    face.len(),
+                    dims.side,
+                    dims.side,
+                    expected
+                );
+            }
+        }
+
+        Self::with_image(dims, types::CubemapImage::from_faces(faces), state)
+    }
 }
 
 impl<D, T> Texture<D, T>
@@ -386,6 +832,17 @@ where
     D: Dimension<u32>,
     T: ?Sized + TextureType<D>,
 {
+    /// Sets the texture's debug label, which graphics debuggers and driver debug messages use to
+    /// identify it. Does nothing on the OpenGL side unless the context supports OpenGL 4.3 or
+    /// `GL_KHR_debug`. With the `object-registry` feature, the label also shows up in the
+    /// texture's [`ObjectInfo`].
+    ///
+    /// [`ObjectInfo`]: ../struct.ObjectInfo.html
+    pub fn set_label(&self, label: &str) {
+        self.state
+            .set_object_label(ObjectKind::Texture, self.handle(), label);
+    }
+
     /// The number of mipmap levels the texture has.
     #[inline]
     pub fn num_mips(&self) -> u8 {
@@ -398,6 +855,210 @@ where
         self.raw.dims()
     }
 
+    /// The number of multisampling samples. Textures that aren't multisampled have 0 samples.
+    #[inline]
+    pub fn samples(&self) -> u8 {
+        self.raw.samples()
+    }
+
+    /// Whether the texture was created with sparse storage. See [`with_sparse_storage`].
+    ///
+    /// [`with_sparse_storage`]: ./struct.Texture.html#method.with_sparse_storage
+    #[inline]
+    pub fn is_sparse(&self) -> bool {
+        self.raw.sparse_page_size().is_some()
+    }
+
+    /// The dimensions of the given mip level of the texture.
+    ///
+    /// Each axis is `max(1, base_dim >> level)`, following OpenGL's rules. Array layers and
+    /// cubemap faces don't shrink with the mip level.
+    ///
+    /// ## Panics
+    /// Panics if `level` is greater than or equal to the texture's number of mip levels.
+    #[inline]
+    pub fn mip_dims(&self, level: T::MipSelector) -> T::Dims {
+        assert!(
+            level.to_glint() < self.num_mips() as GLint,
+            "mip level {} out of range for texture with {} mip levels",
+            level.to_glint(),
+            self.num_mips()
+        );
+        T::mip_dims(self.dims(), level)
+    }
+
+    /// Checks that the texture has an image for every mip level sampled by the texture's own
+    /// minification filter, which gets used when the texture is sampled without a [`Sampler`].
+    ///
+    /// Mip levels only get images once they're allocated, so a texture can end up missing levels
+    /// if `sub_image` gets called on a level past the ones the texture was created with. Sampling
+    /// such a texture with a filter that reads from the missing levels returns black, so this can
+    /// be used to diagnose textures that don't show up. Debug builds run this check automatically
+    /// whenever a texture gets bound for sampling, and panic on failure.
+    ///
+    /// [`Sampler`]: ./struct.Sampler.html
+    #[inline]
+    pub fn check_complete(&self) -> Result<(), TextureIncomplete> {
+        self.check_complete_with(self.sample_parameters.get().filter_min)
+    }
+
+    /// The parameters used to sample the texture when it isn't paired with a [`Sampler`].
+    ///
+    /// [`Sampler`]: ./struct.Sampler.html
+    #[inline]
+    pub fn sample_parameters(&self) -> SampleParameters {
+        self.sample_parameters.get()
+    }
+
+    /// Sets the parameters used to sample the texture when it isn't paired with a [`Sampler`].
+    ///
+    /// Only the parameters that differ from the texture's current parameters get uploaded. Fails
+    /// without changing anything if the minification filter samples from mipmaps the texture
+    /// doesn't have.
+    ///
+    /// [`Sampler`]: ./struct.Sampler.html
+    pub fn set_sample_parameters(
+        &mut self,
+        parameters: SampleParameters,
+    ) -> Result<(), SampleParametersError>
+    where
+        T: TextureType<D, Samples = ()>,
+    {
+        let dims = self.dims();
+        if parameters.filter_min.uses_mipmaps()
+            && self.num_mips() == 1
+            && (dims.width(), dims.height(), dims.depth()) != (1, 1, 1)
+        {
+            return Err(SampleParametersError::MipFilterWithoutMips(
+                parameters.filter_min,
+            ));
+        }
+        self.check_complete_with(parameters.filter_min)
+            .map_err(SampleParametersError::Incomplete)?;
+        self.check_reduction_mode(parameters.reduction_mode)?;
+
+        if parameters != self.sample_parameters.get() {
+            let last_unit = self.state.image_units.0.num_units() - 1;
+            let bind = unsafe {
+                self.state
+                    .image_units
+                    .0
+                    .bind_texture(last_unit, &self.raw, &self.state.gl)
+            };
+            bind.upload_parameters(parameters, &self.sample_parameters);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_reduction_mode(
+        &self,
+        reduction_mode: Option<ReductionMode>,
+    ) -> Result<(), SampleParametersError> {
+        match reduction_mode {
+            None => Ok(()),
+            Some(_) if !self.state.texture_filter_minmax_supported() => {
+                Err(SampleParametersError::ReductionModeUnsupported)
+            }
+            Some(ReductionMode::WeightedAverage) => Ok(()),
+            Some(reduction_mode) => {
+                match <<T::Format as ImageFormat>::ScalarType as ScalarType>::IS_INTEGER {
+                    true => Err(SampleParametersError::ReductionModeNonFilterable(
+                        reduction_mode,
+                    )),
+                    false => Ok(()),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn check_complete_with(&self, filter_min: FilterMin) -> Result<(), TextureIncomplete> {
+        // Gullery sets `GL_TEXTURE_MAX_LEVEL` to the last mip level, so only the levels below
+        // `num_mips` get sampled. OpenGL also ignores levels past the one that's `1x1`.
+        let mut sampled_mips = 1;
+        if filter_min.uses_mipmaps() {
+            let dims = self.dims();
+            let mut level = T::MipSelector::base();
+            while sampled_mips < self.num_mips() {
+                let next_level = level.try_increment();
+                let (mip, next_mip) = (T::mip_dims(dims, level), T::mip_dims(dims, next_level));
+                if (mip.width(), mip.height(), mip.depth())
+                    == (next_mip.width(), next_mip.height(), next_mip.depth())
+                {
+                    break;
+                }
+                level = next_level;
+                sampled_mips += 1;
+            }
+        }
+
+        match (0..sampled_mips).find(|&level| !self.raw.is_mip_allocated(level)) {
+            Some(missing_level) => Err(TextureIncomplete {
+                missing_level,
+                filter_min,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Retrieves the bindless handle for this texture.
+    ///
+    /// Once retrieved, the texture's sampling parameters are frozen for the lifetime of the
+    /// texture.
+    ///
+    /// ## Panics
+    /// Panics if the OpenGL implementation doesn't support `GL_ARB_bindless_texture`. Use
+    /// [`TextureHandle::is_supported`] to check for support beforehand.
+    ///
+    /// [`TextureHandle::is_supported`]: ./struct.TextureHandle.html#method.is_supported
+    #[inline]
+    pub fn bindless_handle(&self) -> TextureHandle {
+        assert!(
+            TextureHandle::is_supported(&self.state),
+            "GL_ARB_bindless_texture not supported"
+        );
+        unsafe {
+            let handle = self.state.gl.GetTextureHandleARB(self.raw.handle().get());
+            assert_ne!(0, handle);
+            TextureHandle(handle)
+        }
+    }
+
+    /// Makes the texture's bindless handle resident, allowing shaders to access it.
+    ///
+    /// Does nothing if the handle is already resident.
+    #[inline]
+    pub fn make_resident(&self) {
+        let handle = self.bindless_handle();
+        let gl = &self.state.gl;
+        unsafe {
+            if gl.IsTextureHandleResidentARB(handle.0) == gl::FALSE {
+                gl.MakeTextureHandleResidentARB(handle.0);
+            }
+        }
+    }
+
+    /// Makes the texture's bindless handle non-resident. Shaders must not access the handle
+    /// afterwards.
+    ///
+    /// Does nothing if the handle isn't resident.
+    #[inline]
+    pub fn make_non_resident(&self) {
+        let handle = self.bindless_handle();
+        let gl = &self.state.gl;
+        unsafe {
+            if gl.IsTextureHandleResidentARB(handle.0) != gl::FALSE {
+                gl.MakeTextureHandleNonResidentARB(handle.0);
+            }
+        }
+    }
+
+    /// Returns whether the texture's bindless handle is resident.
+    #[inline]
+    pub fn is_resident(&self) -> bool {
+        let handle = self.bindless_handle();
+        unsafe { self.state.gl.IsTextureHandleResidentARB(handle.0) != gl::FALSE }
+    }
+
     /// Sets the swizzle parameters for when a shader reads from a texture.
     ///
     /// Swizzling lets you change what values a shader reads from a particular texture channel without
@@ -461,6 +1122,50 @@ where
         bind.swizzle_read(r, g, b, a);
     }
 
+    /// Generates every mip level below the base level by repeatedly downsampling the base level.
+    ///
+    /// This extends the texture's mip count to the full mip chain, down to the level that's `1x1`,
+    /// replacing the contents of any existing levels past the base level. On OpenGL 4.5, this uses
+    /// `glGenerateTextureMipmap` to avoid binding the texture.
+    ///
+    /// ## Panics
+    /// Panics if the base level hasn't been allocated.
+    pub fn generate_mipmaps(&mut self)
+    where
+        T: TextureType<D, MipSelector = u8>,
+        T::Format: ImageFormatRenderable,
+    {
+        assert!(
+            self.raw.is_mip_allocated(0),
+            "can't generate mipmaps for texture without base level"
+        );
+
+        if self.state.direct_state_access_supported {
+            unsafe { self.raw.generate_mipmaps_dsa(&self.state.gl) };
+        } else {
+            let last_unit = self.state.image_units.0.num_units() - 1;
+            let mut bind = unsafe {
+                self.state
+                    .image_units
+                    .0
+                    .bind_texture_mut(last_unit, &mut self.raw, &self.state.gl)
+            };
+            bind.generate_mipmaps();
+        }
+    }
+
+    /// Reads the red channel into the red, green and blue channels, with alpha always read as `1`.
+    ///
+    /// This lets single-channel images, like font glyphs or grayscale masks, get sampled as
+    /// luminance. It's equivalent to calling [`swizzle_read`] with
+    /// `(Swizzle::Red, Swizzle::Red, Swizzle::Red, Swizzle::One)`.
+    ///
+    /// [`swizzle_read`]: ./struct.Texture.html#method.swizzle_read
+    #[inline]
+    pub fn broadcast_red(&mut self) {
+        self.swizzle_read(Swizzle::Red, Swizzle::Red, Swizzle::Red, Swizzle::One);
+    }
+
     /// Returns a reference to this texture with the concrete texture type erased.
     ///
     /// Ideally this function wouldn't be necessary, and you'd be able to do this:
@@ -619,32 +1324,36 @@ impl Sampler {
     }
 }
 
+impl TextureHandle {
+    /// Returns whether the OpenGL implementation supports bindless textures.
+    ///
+    /// Requires `GL_ARB_bindless_texture`.
+    #[inline]
+    pub fn is_supported(state: &ContextState) -> bool {
+        state.supports_extension("GL_ARB_bindless_texture")
+    }
+
+    /// The raw 64-bit handle value.
+    #[inline]
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+}
+
 impl ImageUnits {
     #[inline]
-    pub fn new(gl: &Gl) -> ImageUnits {
-        ImageUnits(RawImageUnits::new(gl))
+    pub fn new(gl: &Gl, multi_bind_supported: bool) -> ImageUnits {
+        ImageUnits(RawImageUnits::new(gl, multi_bind_supported))
     }
 
     #[inline]
-    pub unsafe fn bind<'a, D, T>(
-        &'a self,
-        unit: u32,
-        tex: &'a Texture<D, T>,
-        sampler: Option<&Sampler>,
-        gl: &'a Gl,
-    ) -> BoundTexture<D, T>
-    where
-        D: Dimension<u32>,
-        T: ?Sized + TextureType<D>,
-    {
-        let tex_bind = self.0.bind_texture(unit, &tex.raw, gl);
-        match sampler {
-            Some(sampler) => self.0.bind_sampler(unit, &sampler.raw, gl),
-            None => {
-                self.0.unbind_sampler_from_unit(unit, gl);
-            }
-        }
-        BoundTexture(tex_bind)
+    pub fn bind_buffer(&self) -> Vec<UnitBind> {
+        self.0.bind_buffer()
+    }
+
+    #[inline]
+    pub unsafe fn bind_units(&self, first_unit: u32, binds: Vec<UnitBind>, gl: &Gl) {
+        self.0.bind_units(first_unit, binds, gl)
     }
 }
 
@@ -654,6 +1363,9 @@ where
     T: ?Sized + TextureType<D>,
 {
     fn drop(&mut self) {
+        self.state
+            .object_registry
+            .untrack(ObjectKind::Texture, self.handle());
         unsafe {
             self.raw.delete(&self.state);
         }
@@ -688,7 +1400,7 @@ macro_rules! texture_type_uniform {
             }
             #[inline]
             unsafe fn upload(&self, loc: GLint, binder: &mut TextureUniformBinder, gl: &Gl) {
-                let unit = binder.bind(self, None, gl);
+                let unit = binder.bind(self, None);
                 gl.Uniform1i(loc, unit as GLint);
             }
         }
@@ -709,6 +1421,22 @@ texture_type_uniform! {
     impl &Texture<D2, types::ArrayTex<types::MultisampleTex<C>>> = (Sampler2DMSArray, USampler2DMSArray, ISampler2DMSArray);
 }
 
+impl<'a, D, T> SampledTexture<'a, D, T>
+where
+    D: Dimension<u32>,
+    T: ?Sized + TextureType<D>,
+{
+    /// Checks that the texture has every mip level sampled by the sampler's minification filter.
+    /// See [`Texture::check_complete`].
+    ///
+    /// [`Texture::check_complete`]: ./struct.Texture.html#method.check_complete
+    #[inline]
+    pub fn check_complete(&self) -> Result<(), TextureIncomplete> {
+        self.texture
+            .check_complete_with(self.sampler.sample_parameters.filter_min)
+    }
+}
+
 unsafe impl<'a, D, T> UniformType for SampledTexture<'a, D, T>
 where
     D: Dimension<u32>,
@@ -721,11 +1449,22 @@ where
     }
     #[inline]
     unsafe fn upload(&self, loc: GLint, binder: &mut TextureUniformBinder, gl: &Gl) {
-        let unit = binder.bind(self.texture, Some(self.sampler), gl);
+        let unit = binder.bind(self.texture, Some(self.sampler));
         gl.Uniform1i(loc, unit as GLint);
     }
 }
 
+unsafe impl UniformType for TextureHandle {
+    #[inline]
+    fn uniform_tag() -> TypeTag {
+        TypeTag::Single(TypeTagSingle::UVec2)
+    }
+    #[inline]
+    unsafe fn upload(&self, loc: GLint, _: &mut TextureUniformBinder, gl: &Gl) {
+        gl.Uniform2ui(loc, self.0 as GLuint, (self.0 >> 32) as GLuint);
+    }
+}
+
 impl<D, T> From<TextureCreateError<D, T>> for io::Error
 where
     D: Dimension<u32>,
@@ -737,6 +1476,61 @@ where
     }
 }
 
+impl Error for TextureIncomplete {}
+
+impl Error for SparseCommitError {}
+
+impl fmt::Display for SparseCommitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SparseCommitError::NotSparse => {
+                write!(f, "texture wasn't created with sparse storage")
+            }
+            SparseCommitError::NotPageAligned { page_size } => write!(
+                f,
+                "sparse region isn't aligned to the texture's virtual page size {}x{}x{}",
+                page_size.x, page_size.y, page_size.z
+            ),
+        }
+    }
+}
+
+impl Error for SampleParametersError {}
+
+impl fmt::Display for SampleParametersError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SampleParametersError::MipFilterWithoutMips(filter_min) => write!(
+                f,
+                "minification filter {:?} samples from mipmaps, but the texture only has a base \
+                 level",
+                filter_min
+            ),
+            SampleParametersError::Incomplete(incomplete) => incomplete.fmt(f),
+            SampleParametersError::ReductionModeUnsupported => write!(
+                f,
+                "texture reduction modes require GL_ARB_texture_filter_minmax"
+            ),
+            SampleParametersError::ReductionModeNonFilterable(reduction_mode) => write!(
+                f,
+                "reduction mode {:?} can't be used with integer image formats",
+                reduction_mode
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TextureIncomplete {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "texture mip level {} has no image, but minification filter {:?} samples from it; \
+             sampling the texture will return black",
+            self.missing_level, self.filter_min
+        )
+    }
+}
+
 impl<D: Dimension<u32>, T: TextureType<D>> Error for TextureCreateError<D, T> where
     TextureCreateError<D, T>: fmt::Debug + fmt::Display
 {
@@ -755,6 +1549,27 @@ where
                 "requested dimensions {} exceed OpenGL implementation's maximum dimensions {}",
                 requested, max,
             ),
+            TextureCreateError::SamplesExceedMax { requested, max } => write!(
+                f,
+                "requested sample count {} exceeds OpenGL implementation's maximum sample count {}",
+                requested, max,
+            ),
+            TextureCreateError::SparseUnsupported => write!(
+                f,
+                "sparse textures of this format and type aren't supported by the OpenGL \
+                 implementation"
+            ),
+            TextureCreateError::DimsNotPageAligned {
+                requested,
+                page_size,
+            } => write!(
+                f,
+                "requested dimensions {} aren't a multiple of the virtual page size {}x{}x{}",
+                requested, page_size.x, page_size.y, page_size.z,
+            ),
+            TextureCreateError::GLError(error) => {
+                write!(f, "OpenGL error {} in texture allocation", error)
+            }
         }
     }
 }
@@ -778,3 +1593,180 @@ where
     T: ?Sized + TextureType<D>,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::{NonNormalized, Normalized},
+        image_format::{Depth16, Depth24Stencil8, Red, Rg, Rgb, Rgba},
+        test_helper::CONTEXT_STATE,
+    };
+
+    /// Uploads `color` into a 1x1 texture and downloads it again, checking that it comes back
+    /// unchanged.
+    fn round_trip<C>(color: C) -> bool
+    where
+        C: ConcreteImageFormat + fmt::Debug + PartialEq,
+        C: TextureType<
+            D2,
+            Format = C,
+            Dims = GLVec2<u32, NonNormalized>,
+            MipSelector = u8,
+            Samples = (),
+        >,
+    {
+        CONTEXT_STATE.with(|state| {
+            let mut texture: Texture<D2, C> =
+                Texture::with_image(GLVec2::new(1, 1), &[color][..], state.clone()).unwrap();
+            let mut read = [unsafe { mem::zeroed::<C>() }];
+            texture.get_image(0, &mut read);
+            read[0] == color
+        })
+    }
+
+    macro_rules! round_trip_tests {
+        ($($name:ident: $prim:ty, $normalization:ty, $fix:expr;)*) => {$(
+            quickcheck! {
+                fn $name(r: $prim, g: $prim, b: $prim, a: $prim) -> bool {
+                    let fix: fn($prim) -> $prim = $fix;
+                    let (r, g, b, a) = (fix(r), fix(g), fix(b), fix(a));
+                    round_trip(Rgba::<$prim, $normalization>::new(r, g, b, a)) &&
+                        round_trip(Rgb::<$prim, $normalization>::new(r, g, b)) &&
+                        round_trip(Rg::<$prim, $normalization>::new(r, g)) &&
+                        round_trip(Red::<$prim, $normalization>::new(r))
+                }
+            }
+        )*};
+    }
+
+    #[test]
+    fn sub_image_mip_levels() {
+        CONTEXT_STATE.with(|state| {
+            let mut texture: Texture<D2, Rgba<u8>> =
+                Texture::with_mip_count(GLVec2::new(13, 7), 4, state.clone()).unwrap();
+            for level in 0..4 {
+                let dims = texture.mip_dims(level);
+                let color = Rgba::new(level * 60, 255 - level * 60, 0, 255);
+                let pixels = vec![color; (dims.x * dims.y) as usize];
+                texture.sub_image(level, GLVec2::new(0, 0), dims, &pixels[..]);
+
+                let mut read = vec![Rgba::new(0, 0, 0, 0); pixels.len()];
+                texture.get_image(level, &mut read);
+                assert_eq!(pixels, read);
+            }
+
+            // Level 2 of a 13x7 texture is 3x1, so the rightmost pixel sits at x = 2.
+            let red = [Rgba::new(255, 0, 0, 255)];
+            texture.sub_image(2, GLVec2::new(2, 0), GLVec2::new(1, 1), &red[..]);
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn sparse_commit() {
+        CONTEXT_STATE.with(|state| {
+            let mut dense: Texture<D2, Rgba<u8>> =
+                Texture::with_mip_count(GLVec2::new(4, 4), 1, state.clone()).unwrap();
+            assert!(!dense.is_sparse());
+            assert_eq!(
+                Err(SparseCommitError::NotSparse),
+                dense.commit_region(0, GLVec2::new(0, 0), GLVec2::new(4, 4))
+            );
+
+            let page_size = match Texture::<D2, Rgba<u8>>::sparse_page_size(state) {
+                Some(page_size) => page_size,
+                None => {
+                    let result = Texture::<D2, Rgba<u8>>::with_sparse_storage(
+                        GLVec2::new(4, 4),
+                        1,
+                        state.clone(),
+                    );
+                    assert!(matches!(result, Err(TextureCreateError::SparseUnsupported)));
+                    return;
+                }
+            };
+
+            let page = GLVec2::new(page_size.x, page_size.y);
+            let mut texture: Texture<D2, Rgba<u8>> =
+                Texture::with_sparse_storage(GLVec2::new(page.x * 2, page.y * 2), 1, state.clone())
+                    .unwrap();
+            assert!(texture.is_sparse());
+
+            assert_eq!(Ok(()), texture.commit_region(0, GLVec2::new(0, 0), page));
+            let pixels = vec![Rgba::new(255, 0, 0, 255); (page.x * page.y) as usize];
+            texture.sub_image(0, GLVec2::new(0, 0), page, &pixels[..]);
+            assert_eq!(Ok(()), texture.decommit_region(0, GLVec2::new(0, 0), page));
+
+            assert_eq!(
+                Err(SparseCommitError::NotPageAligned { page_size }),
+                texture.commit_region(0, GLVec2::new(1, 0), page)
+            );
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn cubemap_from_faces() {
+        CONTEXT_STATE.with(|state| {
+            let faces = [0, 1, 2, 3, 4, 5].map(|i| [Rgba::new(i * 40, 0, 0, 255); 4]);
+            let texture: Texture<D2, types::CubemapTex<Rgba<u8>>> = Texture::from_faces(
+                faces.each_ref().map(|face| &face[..]),
+                DimsSquare::new(2),
+                state.clone(),
+            )
+            .unwrap();
+            assert_eq!(DimsSquare::new(2), texture.dims());
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "Cubemap face -Y has 3 blocks")]
+    fn cubemap_from_faces_wrong_size() {
+        CONTEXT_STATE.with(|state| {
+            let face = [Rgba::new(0u8, 0, 0, 255); 4];
+            let short_face = [Rgba::new(0u8, 0, 0, 255); 3];
+            let _: Result<Texture<D2, types::CubemapTex<Rgba<u8>>>, _> = Texture::from_faces(
+                [&face, &face, &face, &short_face, &face, &face],
+                DimsSquare::new(2),
+                state.clone(),
+            );
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit in mip level 1")]
+    fn sub_image_outside_mip_level() {
+        CONTEXT_STATE.with(|state| {
+            let mut texture: Texture<D2, Rgba<u8>> =
+                Texture::with_mip_count(GLVec2::new(13, 7), 4, state.clone()).unwrap();
+            // Level 1 is 6x3, so a region that would fit in the base level doesn't fit here.
+            let pixels = [Rgba::new(0, 0, 0, 255); 7 * 3];
+            texture.sub_image(1, GLVec2::new(0, 0), GLVec2::new(7, 3), &pixels[..]);
+        })
+    }
+
+    // Signed normalized formats map both the minimum value and the minimum value plus one to
+    // `-1.0`, so the minimum value doesn't survive the round trip and gets skipped.
+    round_trip_tests! {
+        round_trip_u8_norm: u8, Normalized, |c| c;
+        round_trip_u16_norm: u16, Normalized, |c| c;
+        round_trip_i8_norm: i8, Normalized, |c| c.max(-i8::MAX);
+        round_trip_i16_norm: i16, Normalized, |c| c.max(-i16::MAX);
+        round_trip_f32: f32, NonNormalized, |c| c;
+        round_trip_u8_int: u8, NonNormalized, |c| c;
+        round_trip_u16_int: u16, NonNormalized, |c| c;
+        round_trip_u32_int: u32, NonNormalized, |c| c;
+        round_trip_i8_int: i8, NonNormalized, |c| c;
+        round_trip_i16_int: i16, NonNormalized, |c| c;
+        round_trip_i32_int: i32, NonNormalized, |c| c;
+    }
+
+    quickcheck! {
+        fn round_trip_depth_stencil(depth: u32, stencil: u8) -> bool {
+            round_trip(Depth24Stencil8::new(depth >> 8, stencil))
+                && round_trip(Depth16((depth >> 16) as u16))
+        }
+    }
+}
@@ -22,6 +22,7 @@ use crate::{
 };
 
 use crate::{
+    error::{ErrorScope, GLError},
     image_format::{ConcreteImageFormat, FormatAttributes, ImageFormat, ImageFormatRenderable},
     ContextState,
 };
@@ -46,6 +47,12 @@ where
     handle: Handle,
     dims: T::Dims,
     num_mips: T::MipSelector,
+    /// Bitmask of the mip levels that have had storage allocated.
+    allocated_mips: u32,
+    /// The number of multisampling samples, or 0 if the texture isn't multisampled.
+    samples: u8,
+    /// The virtual page size of the texture, or `None` if the texture isn't sparse.
+    sparse_page_size: Option<GLVec3<u32, NonNormalized>>,
     _sendsync_optout: PhantomData<*const ()>,
 }
 
@@ -69,11 +76,23 @@ struct ImageUnit {
     sampler: Cell<Option<Handle>>,
 }
 
+/// A texture and optional sampler to bind to an image unit with `RawImageUnits::bind_units`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitBind {
+    pub texture: Handle,
+    pub target: GLenum,
+    pub sampler: Option<Handle>,
+}
+
 pub struct RawImageUnits {
     /// The number of image units is never going to change, so storing this as `Box<[]>` means we
     /// don't have to deal with storing the capacity.
     image_units: Box<[ImageUnit]>,
     active_unit: Cell<u32>,
+    multi_bind_supported: bool,
+    /// Storage reused between draw calls, so that binding textures doesn't allocate every draw.
+    bind_buffer: Cell<Vec<UnitBind>>,
+    handle_buffer: Cell<Vec<GLuint>>,
 }
 
 #[repr(C)]
@@ -103,6 +122,8 @@ pub struct DimsSquare {
 
 pub trait Dims: 'static + Copy {
     type Offset: Copy;
+    /// Converts an offset to a 3D offset, with the axes that `Self` doesn't have set to `0`.
+    fn offset_xyz(offset: Self::Offset) -> GLVec3<u32, NonNormalized>;
     fn width(self) -> u32;
     fn height(self) -> u32;
     fn depth(self) -> u32;
@@ -111,6 +132,40 @@ pub trait Dims: 'static + Copy {
     fn mip_dims(self, mip_level: GLint) -> Self;
 }
 
+/// Computes the length of a texture axis at the given mip level, which is `max(1, dim >> level)`.
+///
+/// Zero-length axes stay zero-length.
+#[inline]
+fn mip_dim(dim: u32, mip_level: GLint) -> u32 {
+    match dim {
+        0 => 0,
+        _ => dim.checked_shr(mip_level as u32).unwrap_or(0).max(1),
+    }
+}
+
+/// Computes the number of blocks an upload of the given dimensions reads from its source slice.
+///
+/// For uncompressed 3D and array images, layers are `GL_UNPACK_IMAGE_HEIGHT` rows apart if it's
+/// set, and the last layer only needs `dims.y` rows.
+unsafe fn blocks_for_upload<F: ConcreteImageFormat>(
+    dims: GLVec3<u32, NonNormalized>,
+    gl: &Gl,
+) -> usize {
+    match F::FORMAT {
+        FormatAttributes::Uncompressed { .. } if dims.z > 1 => {
+            let mut image_height = 0;
+            gl.GetIntegerv(gl::UNPACK_IMAGE_HEIGHT, &mut image_height);
+            let layer_rows = match image_height {
+                0 => dims.y,
+                _ => image_height as u32,
+            };
+            (dims.x as usize) * (layer_rows as usize) * (dims.z as usize - 1)
+                + (dims.x as usize) * (dims.y as usize)
+        }
+        _ => F::blocks_for_dims(dims),
+    }
+}
+
 pub trait DimsArray: Dims {
     fn max_size_array(state: &ContextState) -> Self;
     fn mip_dims_array(self, mip_level: GLint) -> Self;
@@ -207,6 +262,9 @@ where
                 handle,
                 dims,
                 num_mips: mem::zeroed(),
+                allocated_mips: 0,
+                samples: 0,
+                sparse_page_size: None,
                 _sendsync_optout: PhantomData,
             }
         }
@@ -228,6 +286,22 @@ where
         }
     }
 
+    #[inline]
+    pub fn samples(&self) -> u8 {
+        self.samples
+    }
+
+    #[inline]
+    pub fn sparse_page_size(&self) -> Option<GLVec3<u32, NonNormalized>> {
+        self.sparse_page_size
+    }
+
+    /// Returns `true` if storage has been allocated for the given mip level.
+    #[inline]
+    pub fn is_mip_allocated(&self, level: u8) -> bool {
+        level < 32 && self.allocated_mips & (1 << level) != 0
+    }
+
     #[inline(always)]
     pub fn handle(&self) -> Handle {
         self.handle
@@ -242,6 +316,47 @@ where
     }
 }
 
+impl<D, T> RawTexture<D, T>
+where
+    D: Dimension<u32>,
+    T: ?Sized + TextureType<D, MipSelector = u8>,
+{
+    /// Extends the texture's mip count to its full mip chain, down to the level that's `1x1`, and
+    /// marks every level as allocated. Returns the last level.
+    fn fill_mip_chain(&mut self) -> u8 {
+        let mut last_level = 0;
+        while last_level < 31 {
+            let (mip, next_mip) = (
+                T::mip_dims(self.dims, last_level),
+                T::mip_dims(self.dims, last_level + 1),
+            );
+            if (mip.width(), mip.height(), mip.depth())
+                == (next_mip.width(), next_mip.height(), next_mip.depth())
+            {
+                break;
+            }
+            last_level += 1;
+        }
+
+        self.num_mips = last_level + 1;
+        self.allocated_mips |= u32::MAX >> (31 - last_level);
+        last_level
+    }
+
+    /// Generates the full mip chain from the base level with `glGenerateTextureMipmap`, without
+    /// binding the texture. Requires OpenGL 4.5 or `GL_ARB_direct_state_access`.
+    pub unsafe fn generate_mipmaps_dsa(&mut self, gl: &Gl) {
+        let _error_scope = ErrorScope::new(gl, "mipmap generation");
+        let last_level = self.fill_mip_chain();
+        gl.TextureParameteri(
+            self.handle.get(),
+            gl::TEXTURE_MAX_LEVEL,
+            last_level as GLint,
+        );
+        gl.GenerateTextureMipmap(self.handle.get());
+    }
+}
+
 impl RawSampler {
     pub fn new(gl: &Gl) -> RawSampler {
         unsafe {
@@ -271,7 +386,7 @@ impl RawSampler {
 }
 
 impl RawImageUnits {
-    pub fn new(gl: &Gl) -> RawImageUnits {
+    pub fn new(gl: &Gl, multi_bind_supported: bool) -> RawImageUnits {
         let mut max_tex_units = 0;
         unsafe {
             gl.GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max_tex_units);
@@ -283,6 +398,9 @@ impl RawImageUnits {
         RawImageUnits {
             image_units: vec![ImageUnit::default(); max_tex_units as usize].into_boxed_slice(),
             active_unit: Cell::new(0),
+            multi_bind_supported,
+            bind_buffer: Cell::new(Vec::new()),
+            handle_buffer: Cell::new(Vec::new()),
         }
     }
 
@@ -345,21 +463,93 @@ impl RawImageUnits {
         RawBoundTextureMut { tex, gl }
     }
 
+    /// Returns an empty `Vec` to collect binds for `bind_units` in, reusing the storage of the
+    /// last `Vec` passed to `bind_units`.
     #[inline]
-    pub unsafe fn bind_sampler(&self, unit: u32, sampler: &RawSampler, gl: &Gl) {
-        let max_unit = self.image_units.len() as u32 - 1;
-
-        if max_unit < unit {
+    pub fn bind_buffer(&self) -> Vec<UnitBind> {
+        let mut binds = self.bind_buffer.take();
+        binds.clear();
+        binds
+    }
+
+    /// Binds textures and samplers to consecutive image units, starting at `first_unit`.
+    ///
+    /// With OpenGL 4.4 or `GL_ARB_multi_bind`, all the textures get bound with one
+    /// `glBindTextures` call and all the samplers with one `glBindSamplers` call. Otherwise, each
+    /// unit gets bound individually. Either way, nothing gets bound if the units already match the
+    /// cache. `binds` gets kept around and handed back out by `bind_buffer`.
+    pub unsafe fn bind_units(&self, first_unit: u32, binds: Vec<UnitBind>, gl: &Gl) {
+        self.bind_units_slice(first_unit, &binds, gl);
+        self.bind_buffer.set(binds);
+    }
+
+    unsafe fn bind_units_slice(&self, first_unit: u32, binds: &[UnitBind], gl: &Gl) {
+        let num_units = self.image_units.len();
+        if num_units < first_unit as usize + binds.len() {
             panic!(
                 "attempted to bind to unavailable sampler unit {}; highest unit is {}",
-                unit, max_unit
+                first_unit as usize + binds.len() - 1,
+                num_units - 1
             );
         }
 
-        let active_image_unit = &self.image_units[unit as usize];
-        if active_image_unit.sampler.get() != Some(sampler.handle) {
-            gl.BindSampler(unit, sampler.handle.get());
+        let units = &self.image_units[first_unit as usize..first_unit as usize + binds.len()];
+        let up_to_date = units.iter().zip(binds).all(|(unit, bind)| {
+            unit.texture.get() == Some(bind.texture) && unit.sampler.get() == bind.sampler
+        });
+        if up_to_date {
+            return;
+        }
+
+        if self.multi_bind_supported {
+            let mut handles = self.handle_buffer.take();
+            handles.clear();
+            handles.extend(binds.iter().map(|b| b.texture.get()));
+            handles.extend(binds.iter().map(|b| b.sampler.map_or(0, |s| s.get())));
+            let (textures, samplers) = handles.split_at(binds.len());
+            gl.BindTextures(first_unit, binds.len() as GLsizei, textures.as_ptr());
+            gl.BindSamplers(first_unit, binds.len() as GLsizei, samplers.as_ptr());
+            self.handle_buffer.set(handles);
+        } else {
+            for ((unit_index, unit), bind) in (first_unit..).zip(units).zip(binds) {
+                if unit.texture.get() != Some(bind.texture) {
+                    if unit_index != self.active_unit.get() {
+                        self.active_unit.set(unit_index);
+                        gl.ActiveTexture(gl::TEXTURE0 + unit_index);
+                    }
+                    gl.BindTexture(bind.target, bind.texture.get());
+                }
+                if unit.sampler.get() != bind.sampler {
+                    gl.BindSampler(unit_index, bind.sampler.map(|s| s.get()).unwrap_or(0));
+                }
+            }
+        }
+
+        for (unit, bind) in units.iter().zip(binds) {
+            unit.texture.set(Some(bind.texture));
+            unit.sampler.set(bind.sampler);
+        }
+    }
+
+    /// Discards the cached texture and sampler bindings, and syncs the active unit with OpenGL.
+    /// Returns the active unit. Should be called when code outside of Gullery may have changed
+    /// the bindings.
+    pub unsafe fn invalidate(&self, gl: &Gl) -> u32 {
+        let mut active_texture = 0;
+        gl.GetIntegerv(gl::ACTIVE_TEXTURE, &mut active_texture);
+        let active_unit = active_texture as GLenum - gl::TEXTURE0;
+        self.active_unit.set(active_unit);
+
+        for unit in self.image_units.iter() {
+            unit.texture.set(None);
+            unit.sampler.set(None);
         }
+        active_unit
+    }
+
+    pub unsafe fn set_active_unit(&self, unit: u32, gl: &Gl) {
+        gl.ActiveTexture(gl::TEXTURE0 + unit);
+        self.active_unit.set(unit);
     }
 
     unsafe fn unbind_texture(&self, handle: Handle, target: GLuint, gl: &Gl) {
@@ -381,12 +571,6 @@ impl RawImageUnits {
             }
         }
     }
-
-    pub unsafe fn unbind_sampler_from_unit(&self, unit_index: GLuint, gl: &Gl) {
-        let unit = &self.image_units[unit_index as usize];
-        gl.BindSampler(unit_index, 0);
-        unit.sampler.set(None);
-    }
 }
 
 impl<'a, D, T> RawBoundTexture<'a, D, T>
@@ -445,6 +629,10 @@ pub trait ParameterUploader {
                 self.float(gl::TEXTURE_MAX_LOD, lod.max);
                 self.float(gl::TEXTURE_LOD_BIAS, lod.bias);
             };
+            reduction_mode => self.int(
+                gl::TEXTURE_REDUCTION_MODE_ARB,
+                GLenum::from(reduction_mode.unwrap_or_default()) as i32,
+            );
         }
         old_parameters_cell.set(parameters);
     }
@@ -460,10 +648,12 @@ where
         level: T::MipSelector,
         samples: T::Samples,
         image: Option<I>,
-    ) where
+    ) -> Result<(), GLError>
+    where
         I: Image<'b, D, T>,
         T::Format: ConcreteImageFormat,
     {
+        let error_scope = ErrorScope::new(self.gl, "texture image allocation");
         unsafe {
             let mip_level = level.to_glint();
 
@@ -473,18 +663,24 @@ where
                     .TexParameteri(T::BIND_TARGET, gl::TEXTURE_MAX_LEVEL, mip_level);
             }
 
+            if mip_level < 32 {
+                self.tex.allocated_mips |= 1 << mip_level;
+            }
+            if let Some(samples) = samples.samples() {
+                self.tex.samples = samples as u8;
+            }
+
             let mip_dims = T::mip_dims(self.tex.dims(), level);
-            let num_blocks_expected = T::Format::blocks_for_dims(GLVec3::new(
-                mip_dims.width(),
-                mip_dims.height(),
-                mip_dims.depth(),
-            ));
+            let num_blocks_expected = blocks_for_upload::<T::Format>(
+                GLVec3::new(mip_dims.width(), mip_dims.height(), mip_dims.depth()),
+                self.gl,
+            );
 
             match image {
                 Some(image_data) => image_data.variants(|image_bind, data| {
                     let num_blocks = data.len();
                     if num_blocks == num_blocks_expected {
-                        let data_bytes_len = data.len() * mem::size_of::<T::Format>();
+                        let data_bytes_len = data.len() * T::Format::BYTES_PER_PIXEL;
                         T::alloc_image(
                             self.gl,
                             image_bind,
@@ -496,7 +692,8 @@ where
                         );
                     } else {
                         panic!(
-                            "Mismatched image size; expected {} blocks, found {} blocks",
+                            "Mismatched image size; expected {} blocks, found {} blocks. \
+                             Layers of 3D and array images are spaced by the image height stride",
                             num_blocks_expected, num_blocks
                         );
                     }
@@ -513,9 +710,8 @@ where
                     )
                 }),
             }
-
-            assert_eq!(0, self.gl.GetError());
         }
+        error_scope.finish()
     }
 
     pub fn sub_image<'b, I>(
@@ -528,23 +724,55 @@ where
         I: Image<'b, D, T>,
         T::Format: ConcreteImageFormat,
     {
+        let _error_scope = ErrorScope::new(self.gl, "texture image upload");
         unsafe {
             let mip_level = level.to_glint();
 
+            // Mip levels shrink along with the texture, so the region has to be checked against
+            // the dimensions of the level it's getting uploaded to.
+            let mip_dims = T::mip_dims(self.tex.dims(), level);
+            let sub_offset = T::Dims::offset_xyz(offset);
+            let fits = |offset: u32, sub_dim: u32, mip_dim: u32| {
+                offset
+                    .checked_add(sub_dim)
+                    .map(|end| end <= mip_dim)
+                    .unwrap_or(false)
+            };
+            if !(fits(sub_offset.x, sub_dims.width(), mip_dims.width())
+                && fits(sub_offset.y, sub_dims.height(), mip_dims.height())
+                && fits(sub_offset.z, sub_dims.depth(), mip_dims.depth()))
+            {
+                panic!(
+                    "Sub-image with offset {}x{}x{} and dimensions {}x{}x{} doesn't fit in mip \
+                     level {}, which has dimensions {}x{}x{}",
+                    sub_offset.x,
+                    sub_offset.y,
+                    sub_offset.z,
+                    sub_dims.width(),
+                    sub_dims.height(),
+                    sub_dims.depth(),
+                    mip_level,
+                    mip_dims.width(),
+                    mip_dims.height(),
+                    mip_dims.depth()
+                );
+            }
+
             if mip_level >= self.tex.num_mips() as GLint {
                 self.tex.num_mips = level.try_increment();
                 self.gl
                     .TexParameteri(T::BIND_TARGET, gl::TEXTURE_MAX_LEVEL, mip_level);
             }
 
-            let dims = self.tex.dims();
-            let num_blocks_expected =
-                T::Format::blocks_for_dims(GLVec3::new(dims.width(), dims.height(), dims.depth()));
+            let num_blocks_expected = blocks_for_upload::<T::Format>(
+                GLVec3::new(sub_dims.width(), sub_dims.height(), sub_dims.depth()),
+                self.gl,
+            );
 
             image.variants(|image_bind, data| {
                 let num_blocks = data.len();
                 if num_blocks == num_blocks_expected {
-                    let data_bytes_len = data.len() * mem::size_of::<T::Format>();
+                    let data_bytes_len = data.len() * T::Format::BYTES_PER_PIXEL;
                     T::sub_image(
                         self.gl,
                         image_bind,
@@ -556,17 +784,190 @@ where
                     );
                 } else {
                     panic!(
-                        "Mismatched image size; expected {} blocks, found {} blocks",
+                        "Mismatched image size; expected {} blocks, found {} blocks. \
+                         Layers of 3D and array images are spaced by the image height stride",
                         num_blocks_expected, num_blocks
                     );
                 }
             });
+        }
+    }
+}
+
+impl<'a, D, T> RawBoundTextureMut<'a, D, T>
+where
+    D: Dimension<u32>,
+    T: TextureTypeBasicImage<D>,
+    T::Format: ConcreteImageFormat,
+{
+    /// Downloads the image at `level` with `glGetTexImage`.
+    pub fn get_image(&mut self, level: T::MipSelector, data: &mut [T::Format]) {
+        let (pixel_format, pixel_type) = match T::Format::FORMAT {
+            FormatAttributes::Uncompressed {
+                pixel_format,
+                pixel_type,
+                ..
+            } => (pixel_format, pixel_type),
+            FormatAttributes::Compressed { .. } => {
+                panic!("Compressed textures cannot be downloaded")
+            }
+        };
+
+        let mip_dims = T::mip_dims(self.tex.dims(), level);
+        let num_pixels = mip_dims.num_pixels() as usize;
+        assert_eq!(
+            num_pixels,
+            data.len(),
+            "expected buffer of length {}, but got buffer of length {}",
+            num_pixels,
+            data.len()
+        );
+
+        let _error_scope = ErrorScope::new(self.gl, "glGetTexImage");
+        unsafe {
+            self.gl.GetTexImage(
+                T::BIND_TARGET,
+                level.to_glint(),
+                pixel_format,
+                pixel_type,
+                data.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+    }
+}
+
+impl<'a, D, T> RawBoundTextureMut<'a, D, T>
+where
+    D: Dimension<u32>,
+    T: ?Sized + TextureType<D, MipSelector = u8>,
+{
+    /// Generates the full mip chain from the base level with `glGenerateMipmap`.
+    pub fn generate_mipmaps(&mut self) {
+        let _error_scope = ErrorScope::new(self.gl, "mipmap generation");
+        let last_level = self.tex.fill_mip_chain();
+        unsafe {
+            self.gl
+                .TexParameteri(T::BIND_TARGET, gl::TEXTURE_MAX_LEVEL, last_level as GLint);
+            self.gl.GenerateMipmap(T::BIND_TARGET);
+        }
+    }
+}
+
+impl<'a, D, T> RawBoundTextureMut<'a, D, T>
+where
+    D: Dimension<u32>,
+    T: TextureType<D, MipSelector = u8>,
+    T::Format: ConcreteImageFormat,
+{
+    /// Allocates sparse immutable storage for `mip_count` levels of the texture with
+    /// `glTexStorage*`. None of the texture's pages are committed. Requires
+    /// `GL_ARB_sparse_texture`.
+    pub fn alloc_storage_sparse(
+        &mut self,
+        mip_count: u8,
+        page_size: GLVec3<u32, NonNormalized>,
+    ) -> Result<(), GLError> {
+        self.tex.num_mips = mip_count;
+        self.tex.allocated_mips = u32::MAX >> (32 - u32::from(mip_count));
+        self.tex.sparse_page_size = Some(page_size);
+
+        let dims = self.tex.dims();
+        let (width, height, depth) = (
+            dims.width() as GLsizei,
+            dims.height() as GLsizei,
+            dims.depth() as GLsizei,
+        );
+        let internal_format = T::Format::FORMAT.internal_format();
+        let levels = GLsizei::from(mip_count);
+        let error_scope = ErrorScope::new(self.gl, "sparse texture storage allocation");
+        unsafe {
+            self.gl
+                .TexParameteri(T::BIND_TARGET, gl::TEXTURE_SPARSE_ARB, gl::TRUE as GLint);
+            match T::BIND_TARGET {
+                gl::TEXTURE_1D => {
+                    self.gl
+                        .TexStorage1D(T::BIND_TARGET, levels, internal_format, width)
+                }
+                gl::TEXTURE_3D | gl::TEXTURE_2D_ARRAY => self.gl.TexStorage3D(
+                    T::BIND_TARGET,
+                    levels,
+                    internal_format,
+                    width,
+                    height,
+                    depth,
+                ),
+                _ => self
+                    .gl
+                    .TexStorage2D(T::BIND_TARGET, levels, internal_format, width, height),
+            }
+        }
+        error_scope.finish()
+    }
 
-            assert_eq!(0, self.gl.GetError());
+    /// Commits or decommits the pages covering a region of a sparse texture with
+    /// `glTexPageCommitmentARB`. Cubemap regions cover all six faces.
+    pub fn page_commitment(
+        &mut self,
+        level: u8,
+        offset: GLVec3<u32, NonNormalized>,
+        sub_dims: GLVec3<u32, NonNormalized>,
+        commit: bool,
+    ) {
+        let (zoffset, depth) = match T::BIND_TARGET {
+            gl::TEXTURE_CUBE_MAP => (0, 6),
+            _ => (offset.z, sub_dims.z),
+        };
+        let _error_scope = ErrorScope::new(self.gl, "glTexPageCommitmentARB");
+        unsafe {
+            self.gl.TexPageCommitmentARB(
+                T::BIND_TARGET,
+                GLint::from(level),
+                offset.x as GLint,
+                offset.y as GLint,
+                zoffset as GLint,
+                sub_dims.x as GLsizei,
+                sub_dims.y as GLsizei,
+                depth as GLsizei,
+                commit as GLboolean,
+            );
         }
     }
 }
 
+impl<'a, C> RawBoundTextureMut<'a, D2, types::MultisampleTex<C>>
+where
+    C: ImageFormatRenderable + ConcreteImageFormat,
+{
+    /// Allocates immutable storage for the texture with `glTexStorage2DMultisample`. Requires
+    /// OpenGL 4.3 or `GL_ARB_texture_storage_multisample`.
+    pub fn alloc_storage_multisample(&mut self, samples: u8) -> Result<(), GLError> {
+        let internal_format = match C::FORMAT {
+            FormatAttributes::Uncompressed {
+                internal_format, ..
+            } => internal_format,
+            FormatAttributes::Compressed { .. } => {
+                panic!("Compressed textures cannot be rendered to")
+            }
+        };
+        self.tex.allocated_mips |= 1;
+        self.tex.samples = samples;
+
+        let dims = self.tex.dims();
+        let error_scope = ErrorScope::new(self.gl, "glTexStorage2DMultisample");
+        unsafe {
+            self.gl.TexStorage2DMultisample(
+                gl::TEXTURE_2D_MULTISAMPLE,
+                samples as GLsizei,
+                internal_format,
+                dims.width() as GLsizei,
+                dims.height() as GLsizei,
+                gl::FALSE,
+            );
+        }
+        error_scope.finish()
+    }
+}
+
 impl<'a, D, T> RawBoundTextureMut<'a, D, T>
 where
     D: Dimension<u32>,
@@ -680,6 +1081,11 @@ impl DimsSquare {
 impl Dims for u32 {
     type Offset = Self;
 
+    #[inline]
+    fn offset_xyz(offset: u32) -> GLVec3<u32, NonNormalized> {
+        GLVec3::new(offset, 0, 0)
+    }
+
     #[inline]
     fn width(self) -> u32 {
         self
@@ -705,14 +1111,17 @@ impl Dims for u32 {
         }
     }
     fn mip_dims(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
-        self / dim_divisor
+        mip_dim(self, mip_level)
     }
 }
 
 impl Dims for GLVec2<u32, NonNormalized> {
     type Offset = Self;
     #[inline]
+    fn offset_xyz(offset: Self) -> GLVec3<u32, NonNormalized> {
+        GLVec3::new(offset.x, offset.y, 0)
+    }
+    #[inline]
     fn width(self) -> u32 {
         self.x
     }
@@ -737,8 +1146,10 @@ impl Dims for GLVec2<u32, NonNormalized> {
         }
     }
     fn mip_dims(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
-        GLVec2::new(self.width() / dim_divisor, self.height() / dim_divisor)
+        GLVec2::new(
+            mip_dim(self.width(), mip_level),
+            mip_dim(self.height(), mip_level),
+        )
     }
 }
 impl DimsArray for GLVec2<u32, NonNormalized> {
@@ -754,14 +1165,17 @@ impl DimsArray for GLVec2<u32, NonNormalized> {
         }
     }
     fn mip_dims_array(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
-        GLVec2::new(self.width() / dim_divisor, self.height())
+        GLVec2::new(mip_dim(self.width(), mip_level), self.height())
     }
 }
 
 impl Dims for DimsSquare {
     type Offset = GLVec2<u32, NonNormalized>;
     #[inline]
+    fn offset_xyz(offset: GLVec2<u32, NonNormalized>) -> GLVec3<u32, NonNormalized> {
+        GLVec3::new(offset.x, offset.y, 0)
+    }
+    #[inline]
     fn width(self) -> u32 {
         self.side
     }
@@ -786,13 +1200,16 @@ impl Dims for DimsSquare {
         }
     }
     fn mip_dims(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
-        DimsSquare::new(self.side / dim_divisor)
+        DimsSquare::new(mip_dim(self.side, mip_level))
     }
 }
 impl Dims for GLVec3<u32, NonNormalized> {
     type Offset = Self;
     #[inline]
+    fn offset_xyz(offset: Self) -> GLVec3<u32, NonNormalized> {
+        offset
+    }
+    #[inline]
     fn width(self) -> u32 {
         self.x
     }
@@ -817,11 +1234,10 @@ impl Dims for GLVec3<u32, NonNormalized> {
         }
     }
     fn mip_dims(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
         GLVec3::new(
-            self.width() / dim_divisor,
-            self.height() / dim_divisor,
-            self.depth() / dim_divisor,
+            mip_dim(self.width(), mip_level),
+            mip_dim(self.height(), mip_level),
+            mip_dim(self.depth(), mip_level),
         )
     }
 }
@@ -838,10 +1254,9 @@ impl DimsArray for GLVec3<u32, NonNormalized> {
         }
     }
     fn mip_dims_array(self, mip_level: GLint) -> Self {
-        let dim_divisor = 2u32.pow(mip_level as u32);
         GLVec3::new(
-            self.width() / dim_divisor,
-            self.height() / dim_divisor,
+            mip_dim(self.width(), mip_level),
+            mip_dim(self.height(), mip_level),
             self.depth(),
         )
     }
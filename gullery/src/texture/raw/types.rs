@@ -181,6 +181,25 @@ pub struct CubemapImage<'a, I: ImageFormat> {
     pub neg_z: &'a [I],
 }
 
+impl<'a, I: ImageFormat> CubemapImage<'a, I> {
+    /// Builds an image from an array of faces given in OpenGL's face order.
+    ///
+    /// That order is `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`, which matches the `GL_TEXTURE_CUBE_MAP_*`
+    /// face targets and the layer index of each face when rendering to a cubemap.
+    #[inline]
+    pub fn from_faces(faces: [&'a [I]; 6]) -> CubemapImage<'a, I> {
+        let [pos_x, neg_x, pos_y, neg_y, pos_z, neg_z] = faces;
+        CubemapImage {
+            pos_x,
+            neg_x,
+            pos_y,
+            neg_y,
+            pos_z,
+            neg_z,
+        }
+    }
+}
+
 impl<'a, I: ImageFormat> Clone for CubemapImage<'a, I> {
     fn clone(&self) -> CubemapImage<'a, I> {
         CubemapImage {
@@ -10,9 +10,9 @@ pub enum Swizzle {
     Red,
     /// The green channel of the image, or `0` if it has no green channel.
     Green,
-    /// The blue channel of the image, or `0` if it has no green channel.
+    /// The blue channel of the image, or `0` if it has no blue channel.
     Blue,
-    /// The alpha channel of the image, or `1` if it has no green channel.
+    /// The alpha channel of the image, or `1` if it has no alpha channel.
     Alpha,
     /// Always `0`.
     Zero,
@@ -65,6 +65,25 @@ pub enum FilterMag {
     Linear,
 }
 
+/// The function used to combine the texels a filter reads into a single value.
+///
+/// Corresponds to `GL_TEXTURE_REDUCTION_MODE_ARB`, and requires `GL_ARB_texture_filter_minmax`.
+/// [`Min`] and [`Max`] are useful for building min/max mip chains, like the depth pyramids used
+/// for hierarchical Z culling, with hardware filtering.
+///
+/// [`Min`]: ./enum.ReductionMode.html#variant.Min
+/// [`Max`]: ./enum.ReductionMode.html#variant.Max
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReductionMode {
+    /// **Default value.** Weighted average of the texels, as described by the filter.
+    WeightedAverage,
+    /// The component-wise minimum of the texels the filter reads.
+    Min,
+    /// The component-wise maximum of the texels the filter reads.
+    Max,
+}
+
 /// The sampling behavior used for coordinates that fall outside of the `0.0..=1.0` range.
 ///
 /// Corresponds to `GL_TEXTURE_WRAP_{axis}`, where `{axis}` is the `s`, `t`, or `r` axis in
@@ -139,10 +158,117 @@ pub struct SampleParameters {
     pub texture_wrap: TextureWrap,
     /// The texture's LOD sampling parameters.
     pub lod: Lod,
+    /// The texture's reduction mode. `None` leaves the reduction mode alone, so that it can be
+    /// used on implementations without `GL_ARB_texture_filter_minmax`.
+    pub reduction_mode: Option<ReductionMode>,
     // pub border_color: Option<Rgba<f32>>,
     // TODO: GL_TEXTURE_COMPARE_MODE
 }
 
+/// Builder for [`SampleParameters`], created with [`SampleParameters::builder`].
+///
+/// Unlike `SampleParameters::default()`, which matches OpenGL's defaults, the builder starts out
+/// with [`FilterMin::Linear`] and [`FilterMag::Linear`] filtering and
+/// [`TextureWrapAxis::ClampToEdge`] wrapping on every axis, which works for textures without
+/// mipmaps.
+///
+/// [`SampleParameters`]: ./struct.SampleParameters.html
+/// [`SampleParameters::builder`]: ./struct.SampleParameters.html#method.builder
+/// [`FilterMin::Linear`]: ./enum.FilterMin.html#variant.Linear
+/// [`FilterMag::Linear`]: ./enum.FilterMag.html#variant.Linear
+/// [`TextureWrapAxis::ClampToEdge`]: ./enum.TextureWrapAxis.html#variant.ClampToEdge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleParametersBuilder {
+    parameters: SampleParameters,
+}
+
+impl SampleParameters {
+    /// Starts building a set of sample parameters. See [`SampleParametersBuilder`].
+    ///
+    /// [`SampleParametersBuilder`]: ./struct.SampleParametersBuilder.html
+    #[inline]
+    pub fn builder() -> SampleParametersBuilder {
+        SampleParametersBuilder {
+            parameters: SampleParameters {
+                filter_min: FilterMin::Linear,
+                filter_mag: FilterMag::Linear,
+                texture_wrap: TextureWrap {
+                    s: TextureWrapAxis::ClampToEdge,
+                    t: TextureWrapAxis::ClampToEdge,
+                    r: TextureWrapAxis::ClampToEdge,
+                },
+                ..SampleParameters::default()
+            },
+        }
+    }
+}
+
+impl SampleParametersBuilder {
+    #[inline]
+    pub fn min_filter(mut self, filter_min: FilterMin) -> SampleParametersBuilder {
+        self.parameters.filter_min = filter_min;
+        self
+    }
+
+    #[inline]
+    pub fn mag_filter(mut self, filter_mag: FilterMag) -> SampleParametersBuilder {
+        self.parameters.filter_mag = filter_mag;
+        self
+    }
+
+    /// Sets the wrapping behavior on every axis.
+    #[inline]
+    pub fn wrap(mut self, wrap: TextureWrapAxis) -> SampleParametersBuilder {
+        self.parameters.texture_wrap = TextureWrap {
+            s: wrap,
+            t: wrap,
+            r: wrap,
+        };
+        self
+    }
+
+    #[inline]
+    pub fn wrap_s(mut self, wrap: TextureWrapAxis) -> SampleParametersBuilder {
+        self.parameters.texture_wrap.s = wrap;
+        self
+    }
+
+    #[inline]
+    pub fn wrap_t(mut self, wrap: TextureWrapAxis) -> SampleParametersBuilder {
+        self.parameters.texture_wrap.t = wrap;
+        self
+    }
+
+    #[inline]
+    pub fn wrap_r(mut self, wrap: TextureWrapAxis) -> SampleParametersBuilder {
+        self.parameters.texture_wrap.r = wrap;
+        self
+    }
+
+    #[inline]
+    pub fn anisotropy_max(mut self, anisotropy_max: f32) -> SampleParametersBuilder {
+        self.parameters.anisotropy_max = anisotropy_max;
+        self
+    }
+
+    #[inline]
+    pub fn lod(mut self, lod: Lod) -> SampleParametersBuilder {
+        self.parameters.lod = lod;
+        self
+    }
+
+    #[inline]
+    pub fn reduction_mode(mut self, reduction_mode: ReductionMode) -> SampleParametersBuilder {
+        self.parameters.reduction_mode = Some(reduction_mode);
+        self
+    }
+
+    #[inline]
+    pub fn build(self) -> SampleParameters {
+        self.parameters
+    }
+}
+
 impl Default for FilterMin {
     #[inline(always)]
     fn default() -> FilterMin {
@@ -157,6 +283,13 @@ impl Default for FilterMag {
     }
 }
 
+impl Default for ReductionMode {
+    #[inline(always)]
+    fn default() -> ReductionMode {
+        ReductionMode::WeightedAverage
+    }
+}
+
 impl Default for TextureWrapAxis {
     #[inline(always)]
     fn default() -> TextureWrapAxis {
@@ -173,6 +306,7 @@ impl Default for SampleParameters {
             lod: Lod::default(),
             anisotropy_max: 1.0,
             texture_wrap: TextureWrap::default(),
+            reduction_mode: None,
         }
     }
 }
@@ -203,6 +337,14 @@ impl From<Swizzle> for GLenum {
     }
 }
 
+impl FilterMin {
+    /// Returns `true` if the filter samples from mipmaps other than the base level.
+    #[inline]
+    pub fn uses_mipmaps(self) -> bool {
+        !matches!(self, FilterMin::Nearest | FilterMin::Linear)
+    }
+}
+
 impl From<FilterMin> for GLenum {
     #[inline]
     fn from(filter: FilterMin) -> GLenum {
@@ -229,6 +371,18 @@ impl From<FilterMag> for GLenum {
     }
 }
 
+impl From<ReductionMode> for GLenum {
+    #[inline]
+    fn from(reduction_mode: ReductionMode) -> GLenum {
+        use self::ReductionMode::*;
+        match reduction_mode {
+            WeightedAverage => gl::WEIGHTED_AVERAGE_ARB,
+            Min => gl::MIN,
+            Max => gl::MAX,
+        }
+    }
+}
+
 impl From<TextureWrapAxis> for GLenum {
     #[inline]
     fn from(wrap_mode: TextureWrapAxis) -> GLenum {
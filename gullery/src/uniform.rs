@@ -18,13 +18,17 @@ use crate::{
     geometry::Dimension,
     geometry::*,
     image_format::{Red, Rg, Rgb, Rgba},
-    texture::{ImageUnits, Sampler, Texture, TextureType},
+    texture::{Sampler, Texture, TextureType, UnitBind},
+    GLObject,
 };
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem};
 
+/// Assigns image units to the textures in a set of uniforms.
+///
+/// Textures don't get bound immediately. Instead, the binds are collected so that they can all be
+/// made at once after the uniforms have been uploaded.
 pub struct TextureUniformBinder<'a> {
-    pub(crate) image_units: &'a ImageUnits,
-    pub(crate) unit: &'a mut u32,
+    pub(crate) binds: &'a mut Vec<UnitBind>,
 }
 
 impl<'a> TextureUniformBinder<'a> {
@@ -32,22 +36,114 @@ impl<'a> TextureUniformBinder<'a> {
         &mut self,
         tex: &Texture<D, T>,
         sampler: Option<&Sampler>,
-        gl: &Gl,
     ) -> u32
     where
         D: Dimension<u32>,
         T: ?Sized + TextureType<D>,
     {
+        if cfg!(debug_assertions) {
+            let parameters = sampler
+                .map(|s| s.sample_parameters)
+                .unwrap_or_else(|| tex.sample_parameters());
+            if let Err(err) = tex.check_complete_with(parameters.filter_min) {
+                panic!("{}", err);
+            }
+            if let Err(err) = tex.check_reduction_mode(parameters.reduction_mode) {
+                panic!("{}", err);
+            }
+        }
+
         if let Some(sampler) = sampler {
             sampler.upload_parameters();
         } /* else {
               tex.upload_parameters();
           }*/
 
-        let ret = *self.unit;
-        self.image_units.bind(*self.unit, tex, sampler, gl);
-        *self.unit += 1;
-        ret
+        let unit = self.binds.len() as u32;
+        self.binds.push(UnitBind {
+            texture: tex.handle(),
+            target: T::BIND_TARGET,
+            sampler: sampler.map(|s| s.handle()),
+        });
+        unit
+    }
+}
+
+/// A matrix uniform that gets transposed when it's uploaded.
+///
+/// GLSL matrices are column-major, as are Gullery's matrix types and cgmath's, so matrices normally
+/// get uploaded as-is. Matrices from row-major math libraries can be wrapped in `Transposed`
+/// instead of getting transposed by hand. A row-major matrix has the same memory layout as the
+/// column-major matrix with its rows and columns swapped, so a row-major 2-row, 3-column matrix
+/// gets stored in a [`GLMat3r2c`], and a `Transposed<GLMat3r2c>` uniform gets type-checked
+/// against a GLSL `mat3x2`.
+///
+/// Row-major `mint` matrices can be converted directly into the matching `Transposed` matrix.
+///
+/// [`GLMat3r2c`]: ../geometry/struct.GLMat3r2c.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transposed<M>(pub M);
+
+/// A dash pattern for drawing dashed lines, replacing the line stipple removed from the core
+/// profile.
+///
+/// The pattern gets uploaded as a single `vec3`, holding the dash length, gap length and offset in
+/// that order. All three are measured in the same units as the distance along the line the shader
+/// passes to its fragment stage, usually pixels. A fragment shader can then discard fragments that
+/// fall in a gap:
+///
+/// ```glsl
+/// uniform vec3 dash_pattern;
+/// in float line_distance;
+///
+/// // in main():
+/// float t = mod(line_distance + dash_pattern.z, dash_pattern.x + dash_pattern.y);
+/// if (t >= dash_pattern.x) discard;
+/// ```
+///
+/// [`is_dash`] performs the same test on the CPU.
+///
+/// [`is_dash`]: ./struct.DashPattern.html#method.is_dash
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DashPattern {
+    /// The length of each dash.
+    pub dash: f32,
+    /// The length of the gap between two dashes.
+    pub gap: f32,
+    /// How far along the pattern the start of the line is. Animating this makes the dashes crawl
+    /// along the line.
+    pub offset: f32,
+}
+
+impl DashPattern {
+    #[inline]
+    pub fn new(dash: f32, gap: f32) -> DashPattern {
+        DashPattern {
+            dash,
+            gap,
+            offset: 0.0,
+        }
+    }
+
+    /// A pattern without any gaps, which draws the line as a solid line.
+    #[inline]
+    pub fn solid() -> DashPattern {
+        DashPattern::new(1.0, 0.0)
+    }
+
+    /// The length of one dash and one gap.
+    #[inline]
+    pub fn period(&self) -> f32 {
+        self.dash + self.gap
+    }
+
+    /// Whether the point `distance` units along the line lies on a dash, as opposed to in a gap.
+    pub fn is_dash(&self, distance: f32) -> bool {
+        let period = self.period();
+        if period <= 0.0 {
+            return true;
+        }
+        (distance + self.offset).rem_euclid(period) < self.dash
     }
 }
 
@@ -80,6 +176,7 @@ pub trait Uniforms: Sized + Copy {
 
         let mut num = 0;
         Self::members(MemberCounter::<Self>(&mut num, PhantomData));
+        crate::assert_num_members::<Self>("Uniforms", Self::ULC::new_zeroed().as_ref().len(), num);
         num
     }
 }
@@ -177,6 +274,20 @@ impl_glsl_type_uniform! {
     GLMat4r2c<f32>, (m, loc, gl) => gl.UniformMatrix2x4fv(loc, 1, gl::FALSE, &m.x.x),
     GLMat4r3c<f32>, (m, loc, gl) => gl.UniformMatrix3x4fv(loc, 1, gl::FALSE, &m.x.x),
 
+    f64, (d, loc, gl) => {crate::assert_double_precision_supported(gl); gl.Uniform1d(loc, d)},
+    GLVec2<f64>, (v, loc, gl) => {crate::assert_double_precision_supported(gl); gl.Uniform2d(loc, v.x, v.y)},
+    GLVec3<f64>, (v, loc, gl) => {crate::assert_double_precision_supported(gl); gl.Uniform3d(loc, v.x, v.y, v.z)},
+    GLVec4<f64>, (v, loc, gl) => {crate::assert_double_precision_supported(gl); gl.Uniform4d(loc, v.x, v.y, v.z, v.w)},
+    GLMat2r2c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix2dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat3r3c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix3dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat4r4c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix4dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat2r3c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix3x2dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat2r4c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix4x2dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat3r2c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix2x3dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat3r4c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix4x3dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat4r2c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix2x4dv(loc, 1, gl::FALSE, &m.x.x)},
+    GLMat4r3c<f64>, (m, loc, gl) => {crate::assert_double_precision_supported(gl); gl.UniformMatrix3x4dv(loc, 1, gl::FALSE, &m.x.x)},
+
     u8, (u, loc, gl) => gl.Uniform1ui(loc, u as u32),
     u16, (u, loc, gl) => gl.Uniform1ui(loc, u as u32),
     u32, (u, loc, gl) => gl.Uniform1ui(loc, u as u32),
@@ -278,9 +389,76 @@ impl_glsl_type_uniform! {
     [GLMat4r3c<f32>], (a, loc, gl) => gl.UniformMatrix3x4fv(loc, a.len() as _, gl::FALSE, a.as_ptr() as *const f32),
 }
 
+macro_rules! impl_transposed_uniform {
+    ($($Matrix:ident, $RowMatrix:ident => $Transposed:ident, $upload:ident;)*) => {$(
+        unsafe impl UniformType for Transposed<$Matrix<f32>> {
+            #[inline]
+            fn uniform_tag() -> TypeTag {
+                TypeTag::Single(<$Transposed<f32>>::prim_tag())
+            }
+            unsafe fn upload(&self, loc: GLint, _: &mut TextureUniformBinder, gl: &Gl) {
+                gl.$upload(loc, 1, gl::TRUE, &self.0.x.x)
+            }
+        }
+
+        impl From<mint::$RowMatrix<f32>> for Transposed<$Matrix<f32>> {
+            #[inline]
+            fn from(matrix: mint::$RowMatrix<f32>) -> Transposed<$Matrix<f32>> {
+                Transposed(unsafe { mem::transmute::<mint::$RowMatrix<f32>, $Matrix<f32>>(matrix) })
+            }
+        }
+    )*};
+}
+
+impl_transposed_uniform! {
+    GLMat2r2c, RowMatrix2 => GLMat2r2c, UniformMatrix2fv;
+    GLMat3r3c, RowMatrix3 => GLMat3r3c, UniformMatrix3fv;
+    GLMat4r4c, RowMatrix4 => GLMat4r4c, UniformMatrix4fv;
+    GLMat2r3c, RowMatrix3x2 => GLMat3r2c, UniformMatrix2x3fv;
+    GLMat2r4c, RowMatrix4x2 => GLMat4r2c, UniformMatrix2x4fv;
+    GLMat3r2c, RowMatrix2x3 => GLMat2r3c, UniformMatrix3x2fv;
+    GLMat3r4c, RowMatrix4x3 => GLMat4r3c, UniformMatrix3x4fv;
+    GLMat4r2c, RowMatrix2x4 => GLMat2r4c, UniformMatrix4x2fv;
+    GLMat4r3c, RowMatrix3x4 => GLMat3r4c, UniformMatrix4x3fv;
+}
+
+unsafe impl UniformType for DashPattern {
+    #[inline]
+    fn uniform_tag() -> TypeTag {
+        TypeTag::Single(TypeTagSingle::Vec3)
+    }
+    unsafe fn upload(&self, loc: GLint, _: &mut TextureUniformBinder, gl: &Gl) {
+        gl.Uniform3f(loc, self.dash, self.gap, self.offset)
+    }
+}
+
 impl<const N: usize> UniformLocContainer for [GLint; N] {
     #[inline]
     fn new_zeroed() -> [GLint; N] {
         [0; N]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dash_pattern() {
+        let pattern = DashPattern::new(3.0, 1.0);
+        let dashes: Vec<bool> = (0..8).map(|i| pattern.is_dash(i as f32 + 0.5)).collect();
+        assert_eq!(
+            vec![true, true, true, false, true, true, true, false],
+            dashes
+        );
+
+        let shifted = DashPattern {
+            offset: 1.0,
+            ..pattern
+        };
+        assert!(!shifted.is_dash(2.5));
+        assert!(shifted.is_dash(-0.5));
+
+        assert!((0..8).all(|i| DashPattern::solid().is_dash(i as f32)));
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2018 Osspial
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! High-level vertex and index storage.
+
+use crate::{
+    buffer::{Buffer, BufferUsage},
+    framebuffer::{render_state::RenderState, DrawMode, Framebuffer},
+    program::Program,
+    uniform::Uniforms,
+    vertex::{Index, Vertex, VertexArrayObject},
+    ContextState, GLObject, Handle,
+};
+
+use std::rc::Rc;
+
+/// A vertex buffer, an optional index buffer, and the VAO tying them together.
+///
+/// This is a convenience layer over [`Buffer`] and [`VertexArrayObject`] for the common case of
+/// drawing a single piece of geometry in its entirety. If you need finer control over buffer
+/// sharing or the drawn range, use those types directly.
+///
+/// [`Buffer`]: ../buffer/struct.Buffer.html
+/// [`VertexArrayObject`]: ./struct.VertexArrayObject.html
+pub struct Mesh<V: Vertex, I: Index> {
+    vao: VertexArrayObject<V, I>,
+    usage: BufferUsage,
+}
+
+impl<V: Vertex, I: Index> Mesh<V, I> {
+    /// Upload vertex and index data to the GPU and create a mesh from it.
+    ///
+    /// ## Parameters
+    /// * `usage`: The usage hint used for both the vertex and index buffers.
+    /// * `vertices`: The vertex data.
+    /// * `indices`: The index data. If `None`, vertices are drawn in the order they're stored.
+    ///
+    /// ## Panics
+    /// Panics if the GPU is out of memory.
+    pub fn new(
+        usage: BufferUsage,
+        vertices: &[V],
+        indices: Option<&[I]>,
+        state: Rc<ContextState>,
+    ) -> Mesh<V, I> {
+        let index_buffer = indices.map(|i| Buffer::with_data(usage, i, state.clone()));
+        let vertex_buffer = Buffer::with_data(usage, vertices, state);
+        Mesh {
+            vao: VertexArrayObject::new(vertex_buffer, index_buffer),
+            usage,
+        }
+    }
+
+    /// The VAO used to draw the mesh.
+    #[inline]
+    pub fn vao(&self) -> &VertexArrayObject<V, I> {
+        &self.vao
+    }
+
+    /// The number of vertices stored in the mesh.
+    #[inline]
+    pub fn num_vertices(&self) -> usize {
+        self.vao.vertex_buffer().len()
+    }
+
+    /// The number of indices stored in the mesh, or `None` if the mesh isn't indexed.
+    #[inline]
+    pub fn num_indices(&self) -> Option<usize> {
+        self.vao.index_buffer().as_ref().map(|b| b.len())
+    }
+
+    /// Replace the mesh's vertex data.
+    ///
+    /// If `vertices` is the same length as the current vertex data, it gets written into the
+    /// existing storage. Otherwise, the storage gets orphaned and reallocated.
+    pub fn update_vertices(&mut self, vertices: &[V]) {
+        let usage = self.usage;
+        let vertex_buffer = self.vao.vertex_buffer_mut();
+        if vertex_buffer.len() == vertices.len() {
+            vertex_buffer.sub_data(0, vertices);
+        } else {
+            vertex_buffer.orphan(usage, vertices);
+        }
+    }
+
+    /// Replace the mesh's index data.
+    ///
+    /// If the mesh didn't previously have an index buffer, one gets created. Otherwise, this
+    /// follows the same reuse rules as [`update_vertices`].
+    ///
+    /// [`update_vertices`]: ./struct.Mesh.html#method.update_vertices
+    pub fn update_indices(&mut self, indices: &[I]) {
+        let usage = self.usage;
+        let state = self.vao.state().clone();
+        match self.vao.index_buffer_mut() {
+            Some(index_buffer) => {
+                if index_buffer.len() == indices.len() {
+                    index_buffer.sub_data(0, indices);
+                } else {
+                    index_buffer.orphan(usage, indices);
+                }
+            }
+            index_buffer @ None => *index_buffer = Some(Buffer::with_data(usage, indices, state)),
+        }
+    }
+
+    /// Draw the entire mesh to `framebuffer`.
+    ///
+    /// See [`Framebuffer::draw`] for details on the parameters.
+    ///
+    /// [`Framebuffer::draw`]: ../framebuffer/trait.Framebuffer.html#method.draw
+    #[inline]
+    pub fn draw<F, U>(
+        &self,
+        framebuffer: &mut F,
+        mode: DrawMode,
+        program: &Program<V, U::Static, F::AttachmentsStatic>,
+        uniforms: &U,
+        render_state: &RenderState,
+    ) where
+        F: Framebuffer,
+        U: Uniforms,
+    {
+        framebuffer.draw(mode, .., &self.vao, program, uniforms, render_state);
+    }
+
+    /// Unwrap the mesh into its vertex and index buffers.
+    pub fn unwrap(self) -> (Buffer<V>, Option<Buffer<I>>) {
+        self.vao.unwrap()
+    }
+}
+
+impl<V: Vertex, I: Index> GLObject for Mesh<V, I> {
+    #[inline]
+    fn handle(&self) -> Handle {
+        self.vao.handle()
+    }
+    #[inline]
+    fn state(&self) -> &Rc<ContextState> {
+        self.vao.state()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::{GLVec2, GLVec3},
+        gl::{self, types::GLint},
+        test_helper::{TestVertex, CONTEXT_STATE},
+    };
+
+    fn vertex(x: f32) -> TestVertex {
+        TestVertex {
+            pos: GLVec2::new(x, x),
+            color: GLVec3::new(x, 0.0, 1.0),
+        }
+    }
+
+    fn read_indices(mesh: &Mesh<TestVertex, u16>) -> Vec<u16> {
+        let index_buffer = mesh.vao().index_buffer().as_ref().unwrap();
+        let mut indices = vec![0; index_buffer.len()];
+        unsafe { index_buffer.get_data(0, &mut indices) };
+        indices
+    }
+
+    #[test]
+    fn update_vertices_same_len() {
+        CONTEXT_STATE.with(|state| {
+            let mut mesh: Mesh<TestVertex, u16> = Mesh::new(
+                BufferUsage::DynamicDraw,
+                &[vertex(0.0), vertex(1.0), vertex(2.0)],
+                None,
+                state.clone(),
+            );
+            let handle = mesh.vao().vertex_buffer().handle();
+
+            let vertices = [vertex(3.0), vertex(4.0), vertex(5.0)];
+            mesh.update_vertices(&vertices);
+            assert_eq!(3, mesh.num_vertices());
+            assert_eq!(handle, mesh.vao().vertex_buffer().handle());
+            assert_eq!(&vertices[..], &mesh.vao().read_vertices(..)[..]);
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn update_vertices_different_len() {
+        CONTEXT_STATE.with(|state| {
+            let mut mesh: Mesh<TestVertex, u16> = Mesh::new(
+                BufferUsage::DynamicDraw,
+                &[vertex(0.0), vertex(1.0), vertex(2.0)],
+                None,
+                state.clone(),
+            );
+            let handle = mesh.vao().vertex_buffer().handle();
+
+            let vertices = [
+                vertex(3.0),
+                vertex(4.0),
+                vertex(5.0),
+                vertex(6.0),
+                vertex(7.0),
+            ];
+            mesh.update_vertices(&vertices);
+            assert_eq!(5, mesh.num_vertices());
+            assert_eq!(handle, mesh.vao().vertex_buffer().handle());
+            assert_eq!(&vertices[..], &mesh.vao().read_vertices(..)[..]);
+
+            mesh.update_vertices(&vertices[..1]);
+            assert_eq!(1, mesh.num_vertices());
+            assert_eq!(&vertices[..1], &mesh.vao().read_vertices(..)[..]);
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn update_indices() {
+        CONTEXT_STATE.with(|state| {
+            let mut mesh: Mesh<TestVertex, u16> = Mesh::new(
+                BufferUsage::DynamicDraw,
+                &[vertex(0.0), vertex(1.0), vertex(2.0)],
+                Some(&[0, 1, 2]),
+                state.clone(),
+            );
+            let handle = mesh.vao().index_buffer().as_ref().unwrap().handle();
+
+            mesh.update_indices(&[2, 1, 0]);
+            assert_eq!(Some(3), mesh.num_indices());
+            assert_eq!(vec![2, 1, 0], read_indices(&mesh));
+
+            mesh.update_indices(&[0, 1, 2, 2, 1, 0]);
+            assert_eq!(Some(6), mesh.num_indices());
+            assert_eq!(vec![0, 1, 2, 2, 1, 0], read_indices(&mesh));
+            assert_eq!(handle, mesh.vao().index_buffer().as_ref().unwrap().handle());
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+
+    #[test]
+    fn update_indices_creates_index_buffer() {
+        CONTEXT_STATE.with(|state| {
+            let mut mesh: Mesh<TestVertex, u16> = Mesh::new(
+                BufferUsage::StaticDraw,
+                &[vertex(0.0), vertex(1.0), vertex(2.0)],
+                None,
+                state.clone(),
+            );
+            assert_eq!(None, mesh.num_indices());
+
+            mesh.update_indices(&[0, 2, 1]);
+            assert_eq!(Some(3), mesh.num_indices());
+            assert_eq!(vec![0, 2, 1], read_indices(&mesh));
+
+            // The VAO has to pick up the new index buffer the next time it's bound.
+            let index_handle = mesh.vao().index_buffer().as_ref().unwrap().handle();
+            let _bind = unsafe { state.vao_target.bind(mesh.vao()) };
+            let mut bound_index_buffer = 0;
+            unsafe {
+                state
+                    .gl
+                    .GetIntegerv(gl::ELEMENT_ARRAY_BUFFER_BINDING, &mut bound_index_buffer)
+            };
+            assert_eq!(index_handle.get() as GLint, bound_index_buffer);
+            assert_eq!(Ok(()), state.check_error());
+        })
+    }
+}
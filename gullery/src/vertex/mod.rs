@@ -12,18 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod mesh;
 pub(crate) mod vao;
-pub use self::vao::VertexArrayObject;
+pub use self::{mesh::Mesh, vao::VertexArrayObject};
 
 use crate::{
-    gl::types::{GLenum, GLint},
-    geometry::{ScalarBase, TransparentType},
+    gl::{
+        self,
+        types::{GLenum, GLint},
+    },
+    geometry::{Scalar, ScalarBase, ScalarType, TransparentType, TypeTagSingle},
+    program::error::UnsupportedAttribError,
 };
-use std::marker::PhantomData;
+use std::{any, marker::PhantomData, mem, mem::MaybeUninit};
 
 pub unsafe trait Index: 'static + Copy {
     const INDEX_GL_ENUM: Option<GLenum>;
     fn as_glint(&self) -> GLint;
+
+    /// The size in bytes of a buffer holding `count` indices.
+    #[inline]
+    fn buffer_size(count: usize) -> usize {
+        count * mem::size_of::<Self>()
+    }
 }
 unsafe impl Index for ! {
     const INDEX_GL_ENUM: Option<GLenum> = None;
@@ -48,33 +59,452 @@ unsafe impl Index for u32 {
 pub trait VertexMemberRegistry {
     type Group: Vertex;
     /// Add a member to the registry. Note that the value pointed to by `get_type` is allowed to be
-    /// instantiated with `mem::zeroed()`, and any references inside should not be dereferenced.
+    /// uninitialized, so `get_type` should only compute the member's address (as with
+    /// `mem::offset_of!` or `ptr::addr_of!`) without reading through the pointer.
     fn add_member<T>(&mut self, name: &str, get_type: fn(*const Self::Group) -> *const T)
     where
         T: TransparentType;
+
+    /// Add a member whose integer scalars get normalized to floats when read by the shader, in the
+    /// range `0.0..=1.0` for unsigned types and `-1.0..=1.0` for signed types. The shader reads the
+    /// member as a `float` or `vecN`.
+    ///
+    /// This takes precedence over the normalization specified by the member's type, so the same
+    /// scalar can be normalized in one attribute and passed as an integer in another without
+    /// needing `Normalized`/`NonNormalized` type parameters.
+    ///
+    /// ## Panics
+    /// Panics if the member is a matrix or has floating-point scalars.
+    #[inline]
+    fn add_vertex_attrib_normalized<T>(
+        &mut self,
+        name: &str,
+        get_type: fn(*const Self::Group) -> *const T,
+    ) where
+        T: TransparentType,
+    {
+        self.add_member(name, get_type)
+    }
+
+    /// Add a member whose integer scalars get passed to the shader unconverted. The shader reads
+    /// the member as an `int`, `uint`, `ivecN` or `uvecN`, depending on whether the scalar type is
+    /// signed.
+    ///
+    /// This takes precedence over the normalization specified by the member's type. See
+    /// [`add_vertex_attrib_normalized`](#method.add_vertex_attrib_normalized).
+    ///
+    /// ## Panics
+    /// Panics if the member is a matrix or has floating-point scalars.
+    #[inline]
+    fn add_vertex_attrib_integer<T>(
+        &mut self,
+        name: &str,
+        get_type: fn(*const Self::Group) -> *const T,
+    ) where
+        T: TransparentType,
+    {
+        self.add_member(name, get_type)
+    }
+}
+
+/// The conversion applied to a vertex attribute's scalars when the shader reads them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AttribConversion {
+    /// Use the conversion specified by the attribute's type.
+    Default,
+    Normalized,
+    Integer,
+}
+
+impl AttribConversion {
+    fn check_overridable<T: TransparentType>(self, name: &str) {
+        if self != AttribConversion::Default {
+            assert!(
+                T::Scalar::GL_ENUM != gl::FLOAT && T::Scalar::GL_ENUM != gl::DOUBLE,
+                "Can't override conversion of floating-point attribute {}",
+                name
+            );
+            assert!(
+                T::prim_tag().num_attrib_slots() == 1 && T::prim_tag().len() <= 4,
+                "Can't override conversion of matrix attribute {}",
+                name
+            );
+        }
+    }
+
+    /// The type the shader reads a single scalar of `T` as. Either `Float`, `Double`, `Int`, `UInt`
+    /// or `Bool`.
+    pub(crate) fn scalar_tag<T: TransparentType>(self, name: &str) -> TypeTagSingle {
+        self.check_overridable::<T>(name);
+        match self {
+            AttribConversion::Default => {
+                <T::Scalar as Scalar<T::Normalization>>::ScalarType::PRIM_TAG
+            }
+            AttribConversion::Normalized => TypeTagSingle::Float,
+            AttribConversion::Integer => match T::Scalar::SIGNED {
+                true => TypeTagSingle::Int,
+                false => TypeTagSingle::UInt,
+            },
+        }
+    }
+
+    /// Whether integer scalars get normalized.
+    pub(crate) fn normalized<T: TransparentType>(self) -> bool {
+        match self {
+            AttribConversion::Default => <T::Scalar as Scalar<T::Normalization>>::NORMALIZED,
+            AttribConversion::Normalized => true,
+            AttribConversion::Integer => false,
+        }
+    }
+
+    /// Checks that `T` can be uploaded as a vertex attribute with this conversion.
+    pub(crate) fn check_attrib<T: TransparentType>(
+        self,
+        name: &str,
+    ) -> Result<(), UnsupportedAttribError> {
+        let rust_ty = T::prim_tag();
+        let attrib_len = rust_ty.len() / rust_ty.num_attrib_slots();
+        let reason = if self.scalar_tag::<T>(name) == TypeTagSingle::Bool {
+            "booleans can't be vertex attributes in the core profile; use an integer type instead"
+        } else if attrib_len > 4 {
+            "each vertex attribute slot holds at most four components"
+        } else if rust_ty.len() * mem::size_of::<T::Scalar>() > mem::size_of::<T>() {
+            "the type is smaller than the GLSL type it claims to be"
+        } else {
+            return Ok(());
+        };
+        Err(UnsupportedAttribError {
+            ident: name.to_string(),
+            rust_ty,
+            reason,
+        })
+    }
+
+    /// The type the shader reads `T` as.
+    pub(crate) fn glsl_type<T: TransparentType>(self, name: &str) -> TypeTagSingle {
+        use self::TypeTagSingle::*;
+        if self == AttribConversion::Default {
+            return T::prim_tag();
+        }
+        match (self.scalar_tag::<T>(name), T::prim_tag().len()) {
+            (Float, 1) => Float,
+            (Float, 2) => Vec2,
+            (Float, 3) => Vec3,
+            (Float, 4) => Vec4,
+            (Int, 1) => Int,
+            (Int, 2) => IVec2,
+            (Int, 3) => IVec3,
+            (Int, 4) => IVec4,
+            (UInt, 1) => UInt,
+            (UInt, 2) => UVec2,
+            (UInt, 3) => UVec3,
+            (UInt, 4) => UVec4,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The memory layout of a [`Vertex`] type, as described by its `members`. Returned by
+/// [`Vertex::layout`].
+///
+/// [`Vertex`]: ./trait.Vertex.html
+/// [`Vertex::layout`]: ./trait.Vertex.html#method.layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// The size of the vertex type in bytes, which is also the stride between vertices.
+    pub size: usize,
+    pub members: Vec<VertexLayoutMember>,
+}
+
+/// A single member of a [`VertexLayout`].
+///
+/// [`VertexLayout`]: ./struct.VertexLayout.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexLayoutMember {
+    pub name: String,
+    /// The GLSL type the shader reads the member as, after any normalization or integer
+    /// conversion.
+    pub glsl_type: TypeTagSingle,
+    /// The offset of the member from the start of the vertex, in bytes.
+    pub offset: usize,
+}
+
+/// The offset in bytes of the member located by `get_type`.
+///
+/// ## Panics
+/// Panics if the member isn't contained within `V`.
+pub(crate) fn member_offset<V, T>(get_type: fn(*const V) -> *const T) -> usize {
+    let vertex = MaybeUninit::<V>::uninit();
+
+    let member_ptr = get_type(vertex.as_ptr());
+    let member_offset = member_ptr as *const u8 as isize - vertex.as_ptr() as *const u8 as isize;
+
+    // Make sure the member is actually inside of the type, instead of pointing to a static or smth.
+    assert!(member_offset >= 0);
+    let member_offset = member_offset as usize;
+    assert!(member_offset + mem::size_of::<T>() <= mem::size_of::<V>());
+    member_offset
 }
 
 pub trait Vertex: 'static + Copy {
+    /// The number of fields `members` is expected to register, if known. Set by the derive, and
+    /// checked by `num_members`. Impls that leave this as `None` get checked for unregistered
+    /// bytes instead.
+    #[doc(hidden)]
+    const NUM_FIELDS: Option<usize> = None;
+
     fn members<M>(reg: M)
     where
         M: VertexMemberRegistry<Group = Self>;
 
     #[inline]
     fn num_members() -> usize {
-        struct MemberCounter<'a, G>(&'a mut usize, PhantomData<G>);
+        struct MemberCounter<'a, G>(&'a mut Vec<MemberRange>, PhantomData<G>);
         impl<'a, G: Vertex> VertexMemberRegistry for MemberCounter<'a, G> {
             type Group = G;
             #[inline]
-            fn add_member<T>(&mut self, _: &str, _: fn(*const G) -> *const T)
+            fn add_member<T>(&mut self, _: &str, get_type: fn(*const G) -> *const T)
             where
                 T: TransparentType,
             {
-                *self.0 += 1;
+                self.0.push(MemberRange {
+                    offset: member_offset(get_type),
+                    size: mem::size_of::<T>(),
+                    align: mem::align_of::<T>(),
+                });
+            }
+        }
+
+        let mut members = Vec::new();
+        Self::members(MemberCounter::<Self>(&mut members, PhantomData));
+        match Self::NUM_FIELDS {
+            Some(num_fields) => {
+                crate::assert_num_members::<Self>("Vertex", num_fields, members.len())
+            }
+            None => assert_members_cover::<Self>(&mut members),
+        }
+        members.len()
+    }
+
+    /// The size in bytes of a buffer holding `count` vertices.
+    ///
+    /// Useful for preallocating buffers with [`Buffer::with_size`] and checking them against
+    /// buffer size limits.
+    ///
+    /// [`Buffer::with_size`]: ../buffer/struct.Buffer.html#method.with_size
+    #[inline]
+    fn buffer_size(count: usize) -> usize {
+        count * mem::size_of::<Self>()
+    }
+
+    /// Checks that every member can be used as a vertex attribute, returning an error for the
+    /// first one that can't.
+    ///
+    /// Creating a [`Program`] runs this check, and creating a [`VertexArrayObject`] panics with
+    /// the error's message, so this is mainly useful for testing hand-written `Vertex` impls.
+    ///
+    /// [`Program`]: ../program/struct.Program.html
+    /// [`VertexArrayObject`]: ./struct.VertexArrayObject.html
+    fn check_attribs() -> Result<(), UnsupportedAttribError> {
+        struct AttribChecker<'a, G>(&'a mut Result<(), UnsupportedAttribError>, PhantomData<G>);
+        impl<'a, G: Vertex> AttribChecker<'a, G> {
+            fn check<T: TransparentType>(&mut self, name: &str, conversion: AttribConversion) {
+                if self.0.is_ok() {
+                    *self.0 = conversion.check_attrib::<T>(name);
+                }
             }
         }
+        impl<'a, G: Vertex> VertexMemberRegistry for AttribChecker<'a, G> {
+            type Group = G;
+            fn add_member<T>(&mut self, name: &str, _: fn(*const G) -> *const T)
+            where
+                T: TransparentType,
+            {
+                self.check::<T>(name, AttribConversion::Default);
+            }
+            fn add_vertex_attrib_normalized<T>(&mut self, name: &str, _: fn(*const G) -> *const T)
+            where
+                T: TransparentType,
+            {
+                self.check::<T>(name, AttribConversion::Normalized);
+            }
+            fn add_vertex_attrib_integer<T>(&mut self, name: &str, _: fn(*const G) -> *const T)
+            where
+                T: TransparentType,
+            {
+                self.check::<T>(name, AttribConversion::Integer);
+            }
+        }
+
+        let mut result = Ok(());
+        Self::members(AttribChecker::<Self>(&mut result, PhantomData));
+        result
+    }
+
+    /// Computes the vertex type's layout from its members, without creating any OpenGL objects.
+    ///
+    /// This is useful for unit-testing vertex types, and for checking them against shader
+    /// reflection data offline.
+    fn layout() -> VertexLayout {
+        struct LayoutBuilder<'a, G>(&'a mut Vec<VertexLayoutMember>, PhantomData<G>);
+        impl<'a, G: Vertex> LayoutBuilder<'a, G> {
+            fn add<T>(
+                &mut self,
+                name: &str,
+                get_type: fn(*const G) -> *const T,
+                conversion: AttribConversion,
+            ) where
+                T: TransparentType,
+            {
+                self.0.push(VertexLayoutMember {
+                    name: name.to_string(),
+                    glsl_type: conversion.glsl_type::<T>(name),
+                    offset: member_offset(get_type),
+                });
+            }
+        }
+        impl<'a, G: Vertex> VertexMemberRegistry for LayoutBuilder<'a, G> {
+            type Group = G;
+            fn add_member<T>(&mut self, name: &str, get_type: fn(*const G) -> *const T)
+            where
+                T: TransparentType,
+            {
+                self.add(name, get_type, AttribConversion::Default);
+            }
+            fn add_vertex_attrib_normalized<T>(
+                &mut self,
+                name: &str,
+                get_type: fn(*const G) -> *const T,
+            ) where
+                T: TransparentType,
+            {
+                self.add(name, get_type, AttribConversion::Normalized);
+            }
+            fn add_vertex_attrib_integer<T>(
+                &mut self,
+                name: &str,
+                get_type: fn(*const G) -> *const T,
+            ) where
+                T: TransparentType,
+            {
+                self.add(name, get_type, AttribConversion::Integer);
+            }
+        }
+
+        let mut members = Vec::new();
+        Self::members(LayoutBuilder::<Self>(&mut members, PhantomData));
+        VertexLayout {
+            size: mem::size_of::<Self>(),
+            members,
+        }
+    }
+}
+
+/// The bytes of a vertex occupied by one of its registered members.
+struct MemberRange {
+    offset: usize,
+    size: usize,
+    align: usize,
+}
+
+/// Panics if `members` leaves a gap in `V` that's too large to be padding, which means that a
+/// field didn't get registered.
+///
+/// Padding before a field is always smaller than the field's alignment, and padding at the end
+/// of the vertex is smaller than the vertex's alignment. This can't catch every missing field,
+/// since a small field may fit in a gap that could also be padding.
+fn assert_members_cover<V: Vertex>(members: &mut [MemberRange]) {
+    members.sort_by_key(|member| member.offset);
+
+    let mut covered_to = 0;
+    for member in members.iter() {
+        if member.offset >= covered_to + member.align {
+            panic_unregistered_bytes::<V>(covered_to, member.offset);
+        }
+        covered_to = covered_to.max(member.offset + member.size);
+    }
+    if mem::size_of::<V>() >= covered_to + mem::align_of::<V>() {
+        panic_unregistered_bytes::<V>(covered_to, mem::size_of::<V>());
+    }
+}
+
+fn panic_unregistered_bytes<V: Vertex>(start: usize, end: usize) -> ! {
+    panic!(
+        "`<{} as Vertex>::members` didn't register anything in bytes {}..{}; check that every \
+         field gets added to the registry",
+        any::type_name::<V>(),
+        start,
+        end
+    );
+}
+
+/// A vertex without any attributes.
+///
+/// Drawing from a `VertexArrayObject<(), _>` runs the vertex shader once per vertex in the
+/// buffer without feeding it any data, which is how procedural geometry like fullscreen
+/// triangles gets drawn from `gl_VertexID`. `()` is zero-sized, so the vertex buffer doesn't
+/// allocate any GPU memory; create it with [`Buffer::with_size`] to set the vertex count.
+///
+/// [`Buffer::with_size`]: ../buffer/struct.Buffer.html#method.with_size
+impl Vertex for () {
+    const NUM_FIELDS: Option<usize> = Some(0);
+
+    #[inline]
+    fn members<M>(_: M)
+    where
+        M: VertexMemberRegistry<Group = ()>,
+    {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{GLVec2, GLVec3};
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct SkippedMemberVertex {
+        pos: GLVec2<f32>,
+        color: GLVec3<f32>,
+        uv: GLVec2<f32>,
+    }
+
+    impl Vertex for SkippedMemberVertex {
+        fn members<M>(mut reg: M)
+        where
+            M: VertexMemberRegistry<Group = Self>,
+        {
+            reg.add_member("pos", |t| unsafe { &(*t).pos });
+            reg.add_member("uv", |t| unsafe { &(*t).uv });
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct PaddedVertex {
+        flag: u8,
+        pos: GLVec2<f32>,
+    }
+
+    impl Vertex for PaddedVertex {
+        fn members<M>(mut reg: M)
+        where
+            M: VertexMemberRegistry<Group = Self>,
+        {
+            reg.add_member("flag", |t| unsafe { &(*t).flag });
+            reg.add_member("pos", |t| unsafe { &(*t).pos });
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "didn't register anything in bytes 8..20")]
+    fn hand_written_impl_skips_member() {
+        SkippedMemberVertex::num_members();
+    }
 
-        let mut num = 0;
-        Self::members(MemberCounter::<Self>(&mut num, PhantomData));
-        num
+    #[test]
+    fn hand_written_impl_with_padding() {
+        assert_eq!(2, PaddedVertex::num_members());
     }
 }
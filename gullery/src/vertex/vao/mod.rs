@@ -17,11 +17,16 @@ use self::raw::*;
 
 use crate::{
     buffer::Buffer,
+    framebuffer::attachments::Attachments,
+    gl::types::GLuint,
+    object_registry::ObjectKind,
+    program::Program,
+    uniform::Uniforms,
     vertex::{Index, Vertex},
     ContextState, GLObject, Handle,
 };
 
-use std::{mem, ptr, rc::Rc};
+use std::{any, mem, ops::RangeBounds, ptr, rc::Rc};
 
 pub struct VertexArrayObject<V: Vertex, I: Index> {
     raw: RawVAO<V>,
@@ -39,7 +44,7 @@ impl<V: Vertex, I: Index> GLObject for VertexArrayObject<V, I> {
     }
 }
 
-pub(crate) struct VAOTarget(RawVAOTarget);
+pub(crate) struct VAOTarget(pub(crate) RawVAOTarget);
 pub(crate) struct BoundVAO<'a, V: Vertex, I: Index> {
     vao: &'a VertexArrayObject<V, I>,
     _bind: RawBoundVAO<'a, V>,
@@ -49,6 +54,14 @@ impl<V: Vertex, I: Index> VertexArrayObject<V, I> {
     pub fn new(
         vertex_buffer: Buffer<V>,
         index_buffer: Option<Buffer<I>>,
+    ) -> VertexArrayObject<V, I> {
+        VertexArrayObject::with_attrib_locs(vertex_buffer, index_buffer, None)
+    }
+
+    fn with_attrib_locs(
+        vertex_buffer: Buffer<V>,
+        index_buffer: Option<Buffer<I>>,
+        attrib_locs: Option<Box<[Option<GLuint>]>>,
     ) -> VertexArrayObject<V, I> {
         let vertex_buffer_context_ptr = vertex_buffer.state().as_ref() as *const _;
         let index_buffer_context_ptr = index_buffer
@@ -59,12 +72,60 @@ impl<V: Vertex, I: Index> VertexArrayObject<V, I> {
         if vertex_buffer_context_ptr != index_buffer_context_ptr {
             panic!("vertex buffer and index buffer using different contexts");
         }
+        V::num_members();
 
-        VertexArrayObject {
-            raw: RawVAO::new(&vertex_buffer.state().gl),
+        let vao = VertexArrayObject {
+            raw: RawVAO::new(attrib_locs, &vertex_buffer.state().gl),
             vertex_buffer,
             index_buffer,
+        };
+        vao.state().object_registry.track(
+            ObjectKind::VertexArray,
+            vao.handle(),
+            any::type_name::<Self>(),
+            || None,
+        );
+        vao
+    }
+
+    /// Creates a new VAO, binding each vertex attribute to the location `program` assigned it.
+    ///
+    /// By default, attributes are bound to locations sequentially in the order `Vertex::members`
+    /// lists them. This queries each member's location by name instead, so the VAO matches the
+    /// program even if the shader specifies its own attribute locations. Members that the program
+    /// doesn't use (or that got optimized away) aren't bound at all.
+    ///
+    /// The VAO can still be used with other programs, but its attribute locations are only
+    /// guaranteed to match `program`'s.
+    ///
+    /// ## Panics
+    /// Panics if the buffers and program were created with different contexts.
+    pub fn with_program_locations<U, A>(
+        vertex_buffer: Buffer<V>,
+        index_buffer: Option<Buffer<I>>,
+        program: &Program<V, U, A>,
+    ) -> VertexArrayObject<V, I>
+    where
+        U: 'static + Uniforms,
+        A: 'static + Attachments,
+    {
+        if !Rc::ptr_eq(vertex_buffer.state(), program.state()) {
+            panic!("vertex buffer and program using different contexts");
         }
+
+        let attrib_locs = query_attrib_locs::<V>(program.handle(), &program.state().gl);
+        VertexArrayObject::with_attrib_locs(vertex_buffer, index_buffer, Some(attrib_locs))
+    }
+
+    /// Sets the VAO's debug label, which graphics debuggers and driver debug messages use to
+    /// identify it. Does nothing on the OpenGL side unless the context supports OpenGL 4.3 or
+    /// `GL_KHR_debug`. With the `object-registry` feature, the label also shows up in the
+    /// VAO's [`ObjectInfo`].
+    ///
+    /// [`ObjectInfo`]: ../struct.ObjectInfo.html
+    pub fn set_label(&self, label: &str) {
+        self.state()
+            .set_object_label(ObjectKind::VertexArray, self.handle(), label);
     }
 
     #[inline]
@@ -87,6 +148,54 @@ impl<V: Vertex, I: Index> VertexArrayObject<V, I> {
         &mut self.index_buffer
     }
 
+    /// Sets the divisor of the attribute for the vertex member named `name`. With a non-zero
+    /// divisor, instanced draws advance the attribute once every `divisor` instances instead of
+    /// once per vertex. A divisor of `0`, the default, advances the attribute once per vertex.
+    ///
+    /// This lets one VAO be used for both instanced and non-instanced draws. Note that the divisor
+    /// is stored in the VAO, so it stays in effect for every later draw using the VAO until it
+    /// gets set again. Does nothing if the member isn't bound to an attribute, which can happen
+    /// with VAOs created with [`with_program_locations`].
+    ///
+    /// ## Panics
+    /// Panics if `V` doesn't have a member named `name`.
+    ///
+    /// [`with_program_locations`]: ./struct.VertexArrayObject.html#method.with_program_locations
+    pub fn set_attrib_divisor(&mut self, name: &str, divisor: u32) {
+        let locs = match self.raw.member_attrib_locs(name) {
+            Some(locs) => locs,
+            None => return,
+        };
+
+        let state = self.vertex_buffer.state();
+        let _bind = unsafe { state.vao_target.bind(self) };
+        for loc in locs {
+            unsafe { state.gl.VertexAttribDivisor(loc, divisor) };
+        }
+    }
+
+    /// Reads the vertices within `range` back from the vertex buffer.
+    ///
+    /// This waits for the GPU to finish all pending writes to the buffer, so it's mainly useful
+    /// for debugging and for checking generated geometry in tests.
+    ///
+    /// ## Panics
+    /// Panics if the range starts after it ends or ends past the end of the vertex buffer, if the
+    /// vertex buffer is mapped, or if `V` has members that can't be used as vertex attributes.
+    pub fn read_vertices<R: RangeBounds<usize>>(&self, range: R) -> Vec<V> {
+        if let Err(err) = V::check_attribs() {
+            panic!("{}", err);
+        }
+        let slice = self.vertex_buffer.slice(range);
+
+        // `check_attribs` rejects booleans, so `V` is only made up of numeric scalars. Those are
+        // valid for any bit pattern, so zeroing the vertices and reading over them is sound even
+        // if the buffer's contents are unspecified.
+        let mut vertices = vec![unsafe { mem::zeroed::<V>() }; slice.len()];
+        unsafe { slice.get_data(0, &mut vertices) };
+        vertices
+    }
+
     pub fn unwrap(mut self) -> (Buffer<V>, Option<Buffer<I>>) {
         unsafe {
             self.destroy_in_place();
@@ -103,6 +212,9 @@ impl<V: Vertex, I: Index> VertexArrayObject<V, I> {
 
     /// Destroy the VAO **without** recursively dropping the contained vertex and index buffer
     unsafe fn destroy_in_place(&mut self) {
+        self.state()
+            .object_registry
+            .untrack(ObjectKind::VertexArray, self.handle());
         self.raw.delete(&**self.vertex_buffer.state());
     }
 }
@@ -148,6 +260,14 @@ mod tests {
     use super::*;
     use crate::{
         buffer::BufferUsage,
+        framebuffer::{
+            attachments::AttachmentsMemberRegistry, render_state::RenderState, DrawMode,
+            Framebuffer, FramebufferObject, FramebufferObjectAttached, Renderbuffer,
+        },
+        geometry::{GLVec2, GLVec3},
+        gl,
+        image_format::Rgba,
+        program::Shader,
         test_helper::{TestVertex, CONTEXT_STATE},
     };
 
@@ -158,5 +278,149 @@ mod tests {
                 let _vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertex_buffer, None);
             });
         }
+
+        fn read_vertices(buffer_data: Vec<TestVertex>) -> bool {
+            CONTEXT_STATE.with(|context_state| {
+                let vertex_buffer = Buffer::with_data(BufferUsage::StaticDraw, &buffer_data, context_state.clone());
+                let vao: VertexArrayObject<TestVertex, !> = VertexArrayObject::new(vertex_buffer, None);
+                let half = buffer_data.len() / 2;
+
+                vao.read_vertices(..) == buffer_data && vao.read_vertices(half..) == buffer_data[half..]
+            })
+        }
+    }
+
+    #[test]
+    fn set_attrib_divisor() {
+        CONTEXT_STATE.with(|context_state| {
+            let vertex_buffer =
+                Buffer::with_size(BufferUsage::StaticDraw, 4, context_state.clone());
+            let mut vao: VertexArrayObject<TestVertex, !> =
+                VertexArrayObject::new(vertex_buffer, None);
+
+            let divisor = |vao: &VertexArrayObject<TestVertex, !>, loc| {
+                let _bind = unsafe { context_state.vao_target.bind(vao) };
+                let mut divisor = -1;
+                unsafe {
+                    context_state.gl.GetVertexAttribiv(
+                        loc,
+                        gl::VERTEX_ATTRIB_ARRAY_DIVISOR,
+                        &mut divisor,
+                    )
+                };
+                divisor
+            };
+
+            vao.set_attrib_divisor("color", 1);
+            assert_eq!(0, divisor(&vao, 0));
+            assert_eq!(1, divisor(&vao, 1));
+            vao.set_attrib_divisor("color", 0);
+            assert_eq!(0, divisor(&vao, 1));
+        });
+    }
+
+    struct ColorAttachments {
+        color: Renderbuffer<Rgba<u8>>,
+    }
+
+    impl Attachments for ColorAttachments {
+        type AHC = [Option<Handle>; 1];
+        type Static = Self;
+        fn members<R>(mut reg: R)
+        where
+            R: AttachmentsMemberRegistry<Attachments = Self>,
+        {
+            reg.add_renderbuffer("color", |a| &a.color);
+        }
+    }
+
+    #[test]
+    fn with_program_locations() {
+        CONTEXT_STATE.with(|context_state| {
+            const VERTEX_SHADER: &str = r#"
+                #version 330
+
+                layout(location = 3) in vec2 pos;
+                // Never read, so the program leaves it inactive.
+                layout(location = 5) in vec3 color;
+
+                void main() {
+                    gl_Position = vec4(pos, 0.0, 1.0);
+                }
+            "#;
+            const FRAGMENT_SHADER: &str = r#"
+                #version 330
+
+                out vec4 color;
+
+                void main() {
+                    color = vec4(1.0, 0.0, 0.0, 1.0);
+                }
+            "#;
+
+            let vertex_shader = Shader::new(VERTEX_SHADER, context_state.clone()).unwrap();
+            let fragment_shader = Shader::new(FRAGMENT_SHADER, context_state.clone()).unwrap();
+            let (program, _) = Program::<TestVertex, (), ColorAttachments>::new(
+                &vertex_shader,
+                None,
+                &fragment_shader,
+            )
+            .unwrap();
+
+            let vertex = |x, y| TestVertex {
+                pos: GLVec2::new(x, y),
+                color: GLVec3::new(0.0, 0.0, 0.0),
+            };
+            let vertex_buffer = Buffer::with_data(
+                BufferUsage::StaticDraw,
+                &[vertex(-1.0, -1.0), vertex(3.0, -1.0), vertex(-1.0, 3.0)],
+                context_state.clone(),
+            );
+            let vao: VertexArrayObject<TestVertex, !> =
+                VertexArrayObject::with_program_locations(vertex_buffer, None, &program);
+
+            let enabled_locs = {
+                let _bind = unsafe { context_state.vao_target.bind(&vao) };
+                let mut max_attribs = 0;
+                unsafe {
+                    context_state
+                        .gl
+                        .GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut max_attribs)
+                };
+                (0..max_attribs as GLuint)
+                    .filter(|&loc| {
+                        let mut enabled = 0;
+                        unsafe {
+                            context_state.gl.GetVertexAttribiv(
+                                loc,
+                                gl::VERTEX_ATTRIB_ARRAY_ENABLED,
+                                &mut enabled,
+                            )
+                        };
+                        enabled != 0
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(vec![3], enabled_locs);
+
+            let dims = GLVec2::new(16, 16);
+            let mut fbo = FramebufferObjectAttached {
+                fbo: FramebufferObject::new(context_state.clone()),
+                attachments: ColorAttachments {
+                    color: Renderbuffer::new(dims, 0, context_state.clone()),
+                },
+            };
+            fbo.clear_color_all(Rgba::new(0.0, 0.0, 0.0, 1.0));
+            let render_state = RenderState {
+                viewport: GLVec2::new(0, 0)..=dims,
+                ..RenderState::default()
+            };
+            fbo.draw(DrawMode::Triangles, .., &vao, &program, &(), &render_state);
+
+            let mut pixels = vec![Rgba::new(0, 0, 0, 0); 16 * 16];
+            fbo.read_pixels_attachment(GLVec2::new(0, 0)..=dims, &mut pixels, |a| &a.color);
+            assert!(pixels.iter().all(|&p| p == Rgba::new(255, 0, 0, 255)));
+            assert_eq!(Ok(()), context_state.check_error());
+        });
     }
 }
@@ -15,13 +15,15 @@
 use crate::gl::{self, types::*, Gl};
 
 use crate::{
+    bind::Bind,
     buffer::Buffer,
-    geometry::{Scalar, ScalarBase, ScalarType, TransparentType, TypeTagSingle},
-    vertex::{Index, Vertex, VertexMemberRegistry},
+    error::ErrorScope,
+    geometry::{ScalarBase, TransparentType, TypeTagSingle},
+    vertex::{self, AttribConversion, Index, Vertex, VertexMemberRegistry},
     ContextState, GLObject, Handle,
 };
 
-use std::{cell::Cell, marker::PhantomData, mem};
+use std::{cell::Cell, ffi::CString, marker::PhantomData, mem, ops::Range};
 
 pub struct RawVAO<V: Vertex> {
     handle: Handle,
@@ -29,6 +31,9 @@ pub struct RawVAO<V: Vertex> {
     vbuf: Cell<Option<Handle>>,
     /// Handle of the bound index buffer
     ibuf: Cell<Option<Handle>>,
+    /// Attribute location of each vertex member, if they were queried from a program. `None`
+    /// locations are inactive in the program and don't get bound.
+    attrib_locs: Option<Box<[Option<GLuint>]>>,
     _sendsync_optout: PhantomData<(*const (), V)>,
 }
 
@@ -42,13 +47,15 @@ pub struct RawBoundVAO<'a, V: Vertex>(PhantomData<(&'a RawVAO<V>, *const ())>);
 struct VertexAttribBuilder<'a, V: Vertex> {
     attrib_loc: u32,
     max_attribs: u32,
+    attrib_locs: Option<&'a [Option<GLuint>]>,
+    member_index: usize,
     gl: &'a Gl,
     _marker: PhantomData<*const V>,
 }
 
 impl<V: Vertex> RawVAO<V> {
     #[inline]
-    pub fn new(gl: &Gl) -> RawVAO<V> {
+    pub fn new(attrib_locs: Option<Box<[Option<GLuint>]>>, gl: &Gl) -> RawVAO<V> {
         unsafe {
             let mut handle = 0;
             gl.GenVertexArrays(1, &mut handle);
@@ -58,6 +65,7 @@ impl<V: Vertex> RawVAO<V> {
                 handle,
                 vbuf: Cell::new(None),
                 ibuf: Cell::new(None),
+                attrib_locs,
                 _sendsync_optout: PhantomData,
             }
         }
@@ -68,12 +76,60 @@ impl<V: Vertex> RawVAO<V> {
         self.handle
     }
 
+    /// The attribute locations the member named `name` gets bound to, or `None` if the member
+    /// isn't bound to any. Matrix members take up one location per column, and `dvec3` and `dvec4`
+    /// columns take up two.
+    ///
+    /// ## Panics
+    /// Panics if `V` doesn't have a member named `name`.
+    pub fn member_attrib_locs(&self, name: &str) -> Option<Range<GLuint>> {
+        struct AttribLocFinder<'a, V: Vertex> {
+            name: &'a str,
+            attrib_loc: GLuint,
+            attrib_locs: Option<&'a [Option<GLuint>]>,
+            member_index: usize,
+            found: &'a mut Option<Option<Range<GLuint>>>,
+            _marker: PhantomData<*const V>,
+        }
+        impl<'a, V: Vertex> VertexMemberRegistry for AttribLocFinder<'a, V> {
+            type Group = V;
+            fn add_member<T>(&mut self, name: &str, _: fn(*const V) -> *const T)
+            where
+                T: TransparentType,
+            {
+                let loc = match self.attrib_locs {
+                    Some(attrib_locs) => attrib_locs[self.member_index],
+                    None => Some(self.attrib_loc),
+                };
+                self.member_index += 1;
+
+                if let Some(loc) = loc {
+                    let slots = T::prim_tag().num_attrib_locations() as GLuint;
+                    if self.found.is_none() && name == self.name {
+                        *self.found = Some(Some(loc..loc + slots));
+                    }
+                    self.attrib_loc = loc + slots;
+                } else if self.found.is_none() && name == self.name {
+                    *self.found = Some(None);
+                }
+            }
+        }
+
+        let mut found = None;
+        V::members(AttribLocFinder::<V> {
+            name,
+            attrib_loc: 0,
+            attrib_locs: self.attrib_locs.as_deref(),
+            member_index: 0,
+            found: &mut found,
+            _marker: PhantomData,
+        });
+        found.unwrap_or_else(|| panic!("vertex type has no member named {}", name))
+    }
+
     pub unsafe fn delete(&mut self, state: &ContextState) {
         state.gl.DeleteVertexArrays(1, &self.handle.get());
-        let bound_vao = state.vao_target.0.bound_vao.get();
-        if bound_vao == Some(self.handle) {
-            state.vao_target.0.reset_bind(&state.gl);
-        }
+        state.vao_target.0.unbind_handle(self.handle, &state.gl);
     }
 }
 
@@ -98,10 +154,7 @@ impl RawVAOTarget {
         V: Vertex,
         I: Index,
     {
-        if self.bound_vao.get() != Some(vao.handle) {
-            gl.BindVertexArray(vao.handle.get());
-            self.bound_vao.set(Some(vao.handle));
-        }
+        self.bind_handle(Some(vao.handle), gl);
 
         // Make sure the given buffer are bound and if they aren't, bind them.
         if Some(vbuf.handle()) != vao.vbuf.get() {
@@ -115,6 +168,8 @@ impl RawVAOTarget {
             V::members(VertexAttribBuilder {
                 attrib_loc: 0,
                 max_attribs: max_attribs as u32,
+                attrib_locs: vao.attrib_locs.as_deref(),
+                member_index: 0,
                 gl,
                 _marker: PhantomData,
             })
@@ -130,11 +185,19 @@ impl RawVAOTarget {
 
         RawBoundVAO(PhantomData)
     }
+}
+
+impl Bind for RawVAOTarget {
+    const BINDING: GLenum = gl::VERTEX_ARRAY_BINDING;
+
+    #[inline]
+    fn bound_handle(&self) -> &Cell<Option<Handle>> {
+        &self.bound_vao
+    }
 
     #[inline]
-    pub unsafe fn reset_bind(&self, gl: &Gl) {
-        self.bound_vao.set(None);
-        gl.BindVertexArray(0);
+    unsafe fn gl_bind(&self, handle: Option<Handle>, gl: &Gl) {
+        gl.BindVertexArray(handle.map(|h| h.get()).unwrap_or(0));
     }
 }
 
@@ -145,71 +208,133 @@ impl<'a, V: Vertex> VertexMemberRegistry for VertexAttribBuilder<'a, V> {
     where
         T: TransparentType,
     {
-        let gl = self.gl;
-        let vertex = unsafe { mem::zeroed() };
+        self.add_attrib(name, get_type, AttribConversion::Default);
+    }
+
+    fn add_vertex_attrib_normalized<T>(&mut self, name: &str, get_type: fn(*const V) -> *const T)
+    where
+        T: TransparentType,
+    {
+        self.add_attrib(name, get_type, AttribConversion::Normalized);
+    }
 
-        let attrib_ptr = get_type(&vertex) as *const T;
-        let attrib_offset =
-            attrib_ptr as *const u8 as isize - &vertex as *const V as *const u8 as isize;
+    fn add_vertex_attrib_integer<T>(&mut self, name: &str, get_type: fn(*const V) -> *const T)
+    where
+        T: TransparentType,
+    {
+        self.add_attrib(name, get_type, AttribConversion::Integer);
+    }
+}
 
-        // Make sure the attribute is actually inside of the type, instead of pointing to a static or smth.
-        assert!(attrib_offset >= 0);
-        let attrib_offset = attrib_offset as usize;
-        assert!(attrib_offset + mem::size_of::<T>() <= mem::size_of::<V>());
+impl<'a, V: Vertex> VertexAttribBuilder<'a, V> {
+    fn add_attrib<T>(
+        &mut self,
+        name: &str,
+        get_type: fn(*const V) -> *const T,
+        conversion: AttribConversion,
+    ) where
+        T: TransparentType,
+    {
+        let gl = self.gl;
+        if let Some(attrib_locs) = self.attrib_locs {
+            let member_index = self.member_index;
+            self.member_index += 1;
+            match attrib_locs[member_index] {
+                Some(loc) => self.attrib_loc = loc,
+                None => return,
+            }
+        }
+        if let Err(err) = conversion.check_attrib::<T>(name) {
+            panic!("{}", err);
+        }
+        let attrib_offset = vertex::member_offset(get_type);
 
         let ty_attrib_slots = T::prim_tag().num_attrib_slots();
+        let ty_attrib_locs = T::prim_tag().num_attrib_locations();
+        let slot_locs = (ty_attrib_locs / ty_attrib_slots) as u32;
 
         let attrib_len = T::prim_tag().len() / ty_attrib_slots;
         let attrib_size = attrib_len * mem::size_of::<T::Scalar>();
-        assert!(attrib_size <= mem::size_of::<T>());
 
+        let _error_scope = ErrorScope::new(gl, "vertex attribute setup");
         unsafe {
             if self.attrib_loc < self.max_attribs {
                 // Enable all vertex attributes necessary. For matrices, there will be more than one
                 // attribute so that's why this loop is needed.
                 for slot in 0..ty_attrib_slots as u32 {
-                    gl.EnableVertexAttribArray(self.attrib_loc + slot);
+                    let slot_loc = self.attrib_loc + slot * slot_locs;
+                    gl.EnableVertexAttribArray(slot_loc);
                     let slot_offset = slot as usize * attrib_size;
 
-                    match <T::Scalar as Scalar<T::Normalization>>::ScalarType::PRIM_TAG {
+                    match conversion.scalar_tag::<T>(name) {
                         TypeTagSingle::Float => gl.VertexAttribPointer(
-                            self.attrib_loc + slot,
+                            slot_loc,
                             attrib_len as GLint,
                             T::Scalar::GL_ENUM,
-                            T::Scalar::NORMALIZED as GLboolean,
+                            conversion.normalized::<T>() as GLboolean,
                             mem::size_of::<V>() as GLsizei,
                             (attrib_offset + slot_offset) as *const GLvoid,
                         ),
-                        TypeTagSingle::Int | TypeTagSingle::UInt | TypeTagSingle::Bool =>
+                        TypeTagSingle::Int | TypeTagSingle::UInt =>
                             gl.VertexAttribIPointer(
-                                self.attrib_loc + slot,
+                                slot_loc,
                                 attrib_len as GLint,
                                 T::Scalar::GL_ENUM,
                                 mem::size_of::<V>() as GLsizei,
                                 (attrib_offset + slot_offset) as *const GLvoid,
                             ),
-                        // TypeTagSingle::Double => {
-                        //     panic!("Attempting to use OpenGL 4 feature")
-                        //     gl.VertexAttribLPointer(
-                        //         self.attrib_loc,
-                        //         T::len() as GLint,
-                        //         T::Scalar::GL_ENUM,
-                        //         mem::size_of::<V>() as GLsizei,
-                        //         attrib_offset as *const GLvoid
-                        //     );
-                        // },
-                        _ => panic!("Invalid scalar type tag"),
+                        TypeTagSingle::Double => {
+                            crate::assert_double_precision_supported(gl);
+                            gl.VertexAttribLPointer(
+                                slot_loc,
+                                attrib_len as GLint,
+                                T::Scalar::GL_ENUM,
+                                mem::size_of::<V>() as GLsizei,
+                                (attrib_offset + slot_offset) as *const GLvoid,
+                            )
+                        }
+                        _ => unreachable!(),
                     }
                 }
 
-                self.attrib_loc += ty_attrib_slots as u32;
+                self.attrib_loc += ty_attrib_locs as u32;
             } else {
                 panic!(
                     "Too many attributes on field {}; GL implementation has maximum of {}",
                     name, self.max_attribs
                 );
             }
-            assert_eq!(0, gl.GetError());
         }
     }
 }
+
+/// Queries the location of each of `V`'s members in a linked program. Members that aren't active in
+/// the program get a location of `None`.
+pub fn query_attrib_locs<V: Vertex>(program: Handle, gl: &Gl) -> Box<[Option<GLuint>]> {
+    struct AttribLocQuerier<'a, V: Vertex> {
+        locs: &'a mut Vec<Option<GLuint>>,
+        program: Handle,
+        gl: &'a Gl,
+        _marker: PhantomData<*const V>,
+    }
+    impl<'a, V: Vertex> VertexMemberRegistry for AttribLocQuerier<'a, V> {
+        type Group = V;
+        fn add_member<T>(&mut self, name: &str, _: fn(*const V) -> *const T)
+        where
+            T: TransparentType,
+        {
+            let cstr = CString::new(name).expect("Null terminator in member name string");
+            let loc = unsafe { self.gl.GetAttribLocation(self.program.get(), cstr.as_ptr()) };
+            self.locs.push(if loc < 0 { None } else { Some(loc as GLuint) });
+        }
+    }
+
+    let mut locs = Vec::with_capacity(V::num_members());
+    V::members(AttribLocQuerier::<V> {
+        locs: &mut locs,
+        program,
+        gl,
+        _marker: PhantomData,
+    });
+    locs.into_boxed_slice()
+}
@@ -16,7 +16,10 @@ extern crate gullery;
 #[macro_use]
 extern crate gullery_macros;
 
-use gullery::geometry::{GLVec3, GLVec4, TransparentType};
+use gullery::{
+    geometry::{GLMat4r4c, GLVec2, GLVec3, GLVec4, NonNormalized, TransparentType, TypeTagSingle},
+    vertex::{Index, Vertex, VertexLayoutMember},
+};
 
 #[derive(Vertex, Clone, Copy)]
 pub struct TestBlock {
@@ -29,3 +32,88 @@ pub struct TestBlockGeneric<T: TransparentType> {
     pub glsl_type: T,
     pub float: f32,
 }
+
+#[derive(Vertex, Clone, Copy)]
+#[repr(C)]
+pub struct TestVertex {
+    pub pos: GLVec2<f32>,
+    pub color: GLVec3<f32>,
+}
+
+#[test]
+fn vertex_layout() {
+    let layout = TestVertex::layout();
+    assert_eq!(20, layout.size);
+    assert_eq!(
+        vec![
+            VertexLayoutMember {
+                name: "pos".to_string(),
+                glsl_type: TypeTagSingle::Vec2,
+                offset: 0,
+            },
+            VertexLayoutMember {
+                name: "color".to_string(),
+                glsl_type: TypeTagSingle::Vec3,
+                offset: 8,
+            },
+        ],
+        layout.members
+    );
+}
+
+#[test]
+fn buffer_size() {
+    assert_eq!(0, TestVertex::buffer_size(0));
+    assert_eq!(60, TestVertex::buffer_size(3));
+    assert_eq!(6, u16::buffer_size(3));
+    assert_eq!(12, u32::buffer_size(3));
+}
+
+#[derive(Vertex, Clone, Copy)]
+pub struct EmptyVertex;
+
+#[test]
+fn empty_vertex_layout() {
+    let layout = EmptyVertex::layout();
+    assert_eq!(0, layout.size);
+    assert!(layout.members.is_empty());
+    assert_eq!(Ok(()), EmptyVertex::check_attribs());
+}
+
+#[derive(Vertex, Clone, Copy)]
+#[repr(C)]
+pub struct DoubleVertex {
+    pub pos: GLVec3<f64>,
+    pub transform: GLMat4r4c<f64>,
+}
+
+#[test]
+fn double_vertex_layout() {
+    let layout = DoubleVertex::layout();
+    assert_eq!(152, layout.size);
+    assert_eq!(TypeTagSingle::DVec3, layout.members[0].glsl_type);
+    assert_eq!(TypeTagSingle::DMat4, layout.members[1].glsl_type);
+    assert_eq!(24, layout.members[1].offset);
+    assert_eq!(Ok(()), DoubleVertex::check_attribs());
+
+    assert_eq!(2, TypeTagSingle::DVec3.num_attrib_locations());
+    assert_eq!(8, TypeTagSingle::DMat4.num_attrib_locations());
+    assert_eq!(3, TypeTagSingle::DMat3x2.num_attrib_slots());
+    assert_eq!(3, TypeTagSingle::DMat3x2.num_attrib_locations());
+}
+
+#[derive(Vertex, Clone, Copy)]
+#[repr(C)]
+pub struct BoolVertex {
+    pub pos: GLVec2<f32>,
+    pub flags: GLVec2<bool, NonNormalized>,
+}
+
+#[test]
+fn unsupported_attrib() {
+    assert_eq!(Ok(()), TestVertex::check_attribs());
+
+    let err = BoolVertex::check_attribs().unwrap_err();
+    assert_eq!("flags", err.ident);
+    assert_eq!(TypeTagSingle::BVec2, err.rust_ty);
+}
@@ -25,8 +25,11 @@ fn main() {
         "GL_EXT_texture_sRGB",
         "GL_EXT_texture_compression_s3tc",
         "GL_KHR_debug",
+        "GL_ARB_bindless_texture",
+        "GL_ARB_texture_filter_minmax",
+        "GL_ARB_sparse_texture",
     ];
-    Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, extensions)
+    Registry::new(Api::Gl, (4, 5), Profile::Core, Fallbacks::All, extensions)
         .write_bindings(StructGenerator, &mut file)
         .unwrap();
 }
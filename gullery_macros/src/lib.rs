@@ -53,20 +53,41 @@ fn impl_vertex(derive_input: &DeriveInput) -> proc_macro2::TokenStream {
         Data::Struct(ref variant) => {
             let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
             let idents = idents(variant.fields.iter().cloned());
+            let num_members = variant.fields.iter().len();
+
+            // Field offsets of non-generic structs are known at compile time, so they get computed
+            // with `offset_of!`. Generic structs fall back to projecting through the pointer at
+            // runtime.
+            let is_generic = generics.type_params().next().is_some()
+                || generics.const_params().next().is_some();
+            let get_members = match is_generic {
+                false => {
+                    let types = variant.fields.iter().map(|f| &f.ty);
+                    quote! {#(
+                        reg.add_member(stringify!(#idents), |t| {
+                            (t as *const u8).wrapping_add(::core::mem::offset_of!(Self, #idents)) as *const #types
+                        });
+                    )*}
+                }
+                true => quote! {#(
+                    reg.add_member(stringify!(#idents), |t| unsafe{ ::core::ptr::addr_of!((*t).#idents) });
+                )*},
+            };
 
+            // `reg` goes unused in structs without any fields, which are used for drawing without
+            // vertex attributes.
             quote! {
-                #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+                #[allow(non_upper_case_globals, unused_attributes, unused_qualifications, unused_mut, unused_variables)]
                 const _: () = {
                     extern crate gullery as _gullery;
                     #[automatically_derived]
                     impl #impl_generics _gullery::vertex::Vertex for #ident #ty_generics #where_clause {
+                        const NUM_FIELDS: Option<usize> = Some(#num_members);
                         #[inline]
                         fn members<M>(mut reg: M)
                             where M: _gullery::vertex::VertexMemberRegistry<Group=Self>
                         {
-                            #(
-                                reg.add_member(stringify!(#idents), |t| unsafe{ &(*t).#idents });
-                            )*
+                            #get_members
                         }
                     }
                 };
@@ -140,6 +161,7 @@ fn impl_attachments(derive_input: &DeriveInput) -> proc_macro2::TokenStream {
                 variant.ty
             });
             let types_1 = types.clone();
+            let types_2 = types.clone();
             let num_members = variant.fields.iter().len();
 
             quote! {
@@ -148,8 +170,8 @@ fn impl_attachments(derive_input: &DeriveInput) -> proc_macro2::TokenStream {
                     extern crate gullery as _gullery;
 
                     impl #impl_generics #ident #ty_generics #where_clause {
-                        /// Check to see that we have at no more than one depth attachment type. If we do,
-                        /// we fail to compile.
+                        /// Check to see that we have at no more than one depth attachment type and no more
+                        /// than one stencil attachment type. If we do, we fail to compile.
                         ///
                         /// Thanks to static_assertions crate and rust #49450 for inspiration on how to
                         /// do this.
@@ -161,11 +183,18 @@ fn impl_attachments(derive_input: &DeriveInput) -> proc_macro2::TokenStream {
                             }
                             const NUM_DEPTH_ATTACHMENTS: usize = 0
                                 #(+ unsafe {
-                                    Transmute{ from: <<#types as _gullery::framebuffer::attachments::AttachmentType>::Format as _gullery::image_format::ImageFormatRenderable>::FormatType::FORMAT_TYPE }.to
-                                    ==
-                                    Transmute{ from: _gullery::image_format::FormatTypeTag::Depth}.to
+                                    let format_type = Transmute{ from: <<#types as _gullery::framebuffer::attachments::AttachmentType>::Format as _gullery::image_format::ImageFormatRenderable>::FormatType::FORMAT_TYPE }.to;
+                                    (format_type == Transmute{ from: _gullery::image_format::FormatTypeTag::Depth}.to) |
+                                    (format_type == Transmute{ from: _gullery::image_format::FormatTypeTag::DepthStencil}.to)
+                                 } as usize)*;
+                            const NUM_STENCIL_ATTACHMENTS: usize = 0
+                                #(+ unsafe {
+                                    let format_type = Transmute{ from: <<#types_2 as _gullery::framebuffer::attachments::AttachmentType>::Format as _gullery::image_format::ImageFormatRenderable>::FormatType::FORMAT_TYPE }.to;
+                                    (format_type == Transmute{ from: _gullery::image_format::FormatTypeTag::Stencil}.to) |
+                                    (format_type == Transmute{ from: _gullery::image_format::FormatTypeTag::DepthStencil}.to)
                                  } as usize)*;
                             let _has_at_least_one_color_attachment = [(); 0 - (NUM_DEPTH_ATTACHMENTS > 1) as usize];
+                            let _has_at_most_one_stencil_attachment = [(); 0 - (NUM_STENCIL_ATTACHMENTS > 1) as usize];
                         }
                     }
 
@@ -178,7 +207,7 @@ fn impl_attachments(derive_input: &DeriveInput) -> proc_macro2::TokenStream {
                             where M: _gullery::framebuffer::attachments::AttachmentsMemberRegistry<Attachments=Self>
                         {
                             #(
-                                <#types_1 as _gullery::framebuffer::attachments::AttachmentType>::add_to_registry(&mut reg, stringify!(#idents), |t| &t.#idents, Default::default());
+                                <#types_1 as _gullery::framebuffer::attachments::AttachmentType>::add_to_registry(&mut reg, stringify!(#idents), |t| _gullery::framebuffer::attachments::AttachmentType::resolve(&t.#idents), Default::default());
                             )*
                         }
                     }